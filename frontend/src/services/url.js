@@ -32,3 +32,26 @@ export function getImageUrl(path) {
   // 浏览器环境仍保持相对路径（同源）
   return cleanPath;
 }
+
+/**
+ * 转换商品对象中的所有图片路径 (封面各尺寸 + 相册)
+ * 旧数据没有中图/缩略图时回退到原图
+ * @param {object} product - 后端返回的商品 (全局商品或场次商品)
+ */
+export function processProductImages(product) {
+  const imageUrl = getImageUrl(product.image_url);
+  return {
+    ...product,
+    image_url: imageUrl,
+    image_md_url: getImageUrl(product.image_md_url) || imageUrl,
+    image_sm_url: getImageUrl(product.image_sm_url) || imageUrl,
+    ...(product.images && {
+      images: product.images.map(img => ({
+        ...img,
+        url: getImageUrl(img.url),
+        url_md: getImageUrl(img.url_md),
+        url_sm: getImageUrl(img.url_sm),
+      })),
+    }),
+  };
+}
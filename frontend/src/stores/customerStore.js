@@ -3,7 +3,7 @@ import { ref, computed } from 'vue';
 import api from '@/services/api';
 import { socket } from '@/services/socketService';
 import { useAlert } from '@/services/useAlert';
-import { getImageUrl } from '@/services/url';
+import { getImageUrl, processProductImages } from '@/services/url';
 
 // 获取弹窗函数
 
@@ -16,6 +16,7 @@ export const useCustomerStore = defineStore('customer', () => {
   const error = ref(null);
   const activeEventId = ref(null);
   const activeEvent = ref(null); // 新增：当前展会信息
+  const selectedPaymentMethodId = ref(null); // 顾客选择的收款方式
 
   // --- Actions ---
   function setupStoreForEvent(eventId) {
@@ -49,10 +50,7 @@ export const useCustomerStore = defineStore('customer', () => {
     error.value = null;
     try {
       const response = await api.get(`/events/${activeEventId.value}/products`);
-      products.value = response.data.map(product => ({
-        ...product,
-        image_url: getImageUrl(product.image_url)
-      }));
+      products.value = response.data.map(processProductImages);
     } catch (err) {
       error.value = '加载商品失败，请联系摊主。';
       console.error(err);
@@ -68,8 +66,14 @@ export const useCustomerStore = defineStore('customer', () => {
       const response = await api.get(`/events/${activeEventId.value}`);
       activeEvent.value = {
         ...response.data,
-        qrcode_url: getImageUrl(response.data.qrcode_url)
+        qrcode_url: getImageUrl(response.data.qrcode_url),
+        payment_methods: (response.data.payment_methods || []).map(method => ({
+          ...method,
+          qrcode_url: getImageUrl(method.qrcode_url)
+        }))
       };
+      // 默认选中第一个收款方式
+      selectedPaymentMethodId.value = activeEvent.value.payment_methods[0]?.id ?? null;
     } catch (err) {
       console.error('加载展会信息失败:', err);
       // 不设置error，因为这不是关键功能
@@ -117,6 +121,7 @@ export const useCustomerStore = defineStore('customer', () => {
         product_id: item.id,
         quantity: item.quantity,
       })),
+      payment_method_id: selectedPaymentMethodId.value,
     };
 
     try {
@@ -126,9 +131,14 @@ export const useCustomerStore = defineStore('customer', () => {
       return response.data;
     } catch (err) {
       console.error("Order submission failed:", err);
-      
-      
-      
+      // 409：场次未开始或已结束，暂停接单
+      if (err.response?.status === 409 && err.response.data?.status) {
+        throw new Error(`本场展会${err.response.data.status}，暂不接受下单。`);
+      }
+      // 403：本场关闭了自助下单，需由摊主代下单
+      if (err.response?.status === 403) {
+        throw new Error('本场展会暂不支持自助下单，请联系摊主下单。');
+      }
       throw new Error(err.response?.data?.error || '下单失败，请重试。');
 
 
@@ -145,9 +155,12 @@ export const useCustomerStore = defineStore('customer', () => {
     return cart.value.reduce((total, item) => total + item.quantity, 0);
   });
 
-  // 新增：获取收款码URL的getter
+  const paymentMethods = computed(() => activeEvent.value?.payment_methods || []);
+
+  // 新增：获取收款码URL的getter (优先显示所选收款方式的收款码)
   const qrCodeUrl = computed(() => {
-    return activeEvent.value?.qrcode_url || null;
+    const selected = paymentMethods.value.find(m => m.id === selectedPaymentMethodId.value);
+    return selected?.qrcode_url || activeEvent.value?.qrcode_url || null;
   });
 
 
@@ -159,6 +172,8 @@ export const useCustomerStore = defineStore('customer', () => {
     activeEventId,
     activeEvent, // 新增：导出展会信息
     qrCodeUrl, // 新增：导出收款码URL
+    paymentMethods,
+    selectedPaymentMethodId,
     fetchProductsForEvent,
     fetchEventInfo, // 新增：导出获取展会信息方法
     addToCart,
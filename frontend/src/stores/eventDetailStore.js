@@ -1,7 +1,7 @@
 import { defineStore } from 'pinia';
 import { ref } from 'vue';
 import api from '@/services/api';
-import { getImageUrl } from '@/services/url';
+import { getImageUrl, processProductImages } from '@/services/url';
 
 export const useEventDetailStore = defineStore('eventDetail', () => {
   // --- State ---
@@ -29,10 +29,7 @@ export const useEventDetailStore = defineStore('eventDetail', () => {
     error.value = null;
     try {
       const response = await api.get(`/events/${eventId}/products`);
-      products.value = response.data.map(product => ({
-        ...product,
-        image_url: getImageUrl(product.image_url)
-      }));
+      products.value = response.data.map(processProductImages);
     } catch (err) {
       error.value = '无法加载展会商品列表。';
       console.error(err);
@@ -45,7 +42,7 @@ export const useEventDetailStore = defineStore('eventDetail', () => {
   async function addProductToEvent(eventId, productData) {
     try {
       const response = await api.post(`/events/${eventId}/products`, productData);
-      const product = { ...response.data, image_url: getImageUrl(response.data.image_url) };
+      const product = processProductImages(response.data);
       products.value.unshift(product);
       return product;
     } catch (err) {
@@ -56,16 +53,24 @@ export const useEventDetailStore = defineStore('eventDetail', () => {
   
   // 更新展会商品的库存或价格
   async function updateEventProduct(productId, productData) {
+    const index = products.value.findIndex(p => p.id === productId);
     try {
-      const response = await api.put(`/products/${productId}`, productData);
-      const product = { ...response.data, image_url: getImageUrl(response.data.image_url) };
-      const index = products.value.findIndex(p => p.id === productId);
+      // 乐观锁：未显式传入时带上本地缓存的版本号
+      const payload = { version: products.value[index]?.version, ...productData };
+      const response = await api.put(`/products/${productId}`, payload);
+      const product = processProductImages(response.data);
       if (index !== -1) {
         products.value[index] = product;
       }
       return product;
     } catch (err) {
       console.error(err);
+      // 409：数据已被其他设备修改，用服务端返回的最新数据刷新本地
+      const current = err.response?.status === 409 ? err.response.data?.current : null;
+      if (current && index !== -1) {
+        products.value[index] = processProductImages(current);
+        throw new Error('该商品已在其他设备上被修改，已刷新为最新数据，请确认后重试。');
+      }
       throw new Error(err.response?.data?.error || '更新商品失败。');
     }
   }
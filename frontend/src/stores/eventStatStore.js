@@ -5,6 +5,12 @@ import api from '@/services/api';
 export const useEventStatStore = defineStore('eventStat', () => {
   // --- State ---
   const stats = ref(null);
+  const sessionStats = ref(null); // 按营业日拆分的统计
+  const comparison = ref(null); // 跨场次对比
+  const basketStats = ref(null); // 客单价、购物篮分布、热力图、繁忙时段
+  const associations = ref(null); // 常一起买的商品组合
+  const forecast = ref(null); // 备货预测
+  const sellout = ref(null); // 现场售罄时间预估
   const isLoading = ref(false);
   const error = ref(null);
   const activeEventId = ref(null);
@@ -27,7 +33,12 @@ export const useEventStatStore = defineStore('eventStat', () => {
     }
   }
 
-  async function fetchStats({ productCode, startDate, endDate, intervalMinutes } = {}) {
+  // intervalMinutes: 5 ~ 1440；groupBy: 'product' | 'category' (返回 series 按组拆分)
+  // cumulative: 附带累计值；fillEmpty: 没有销售的时间段补零
+  async function fetchStats({
+    productCode, category, startDate, endDate, intervalMinutes, sessionId,
+    groupBy, cumulative, fillEmpty,
+  } = {}) {
     if (!activeEventId.value) {
       error.value = "没有提供展会ID。";
       return;
@@ -41,6 +52,11 @@ export const useEventStatStore = defineStore('eventStat', () => {
           start_date: startDate || undefined,
           end_date: endDate || undefined,
           interval_minutes: intervalMinutes || undefined,
+          session_id: sessionId || undefined,
+          category: category || undefined,
+          group_by: groupBy || undefined,
+          cumulative: cumulative || undefined,
+          fill_empty: fillEmpty || undefined,
         },
       });
       console.log("获取销售统计成功:", response.data);
@@ -58,13 +74,145 @@ export const useEventStatStore = defineStore('eventStat', () => {
     }
   }
 
+  async function fetchSessionStats() {
+    if (!activeEventId.value) return;
+    try {
+      const response = await api.get(`/events/${activeEventId.value}/stats/sessions`);
+      sessionStats.value = response.data;
+    } catch (err) {
+      console.error("获取每日统计失败:", err);
+      sessionStats.value = null;
+    }
+  }
+
+  async function fetchBasketStats({ sessionId, top } = {}) {
+    if (!activeEventId.value) return;
+    try {
+      const response = await api.get(`/events/${activeEventId.value}/stats/basket`, {
+        params: { session_id: sessionId || undefined, top: top || undefined },
+      });
+      basketStats.value = response.data;
+    } catch (err) {
+      console.error("获取顾客行为分析失败:", err);
+      basketStats.value = null;
+    }
+  }
+
+  // 备货预测：safetyMargin 为安全余量 (0.2 = 多备 20%)，hours 为预计营业小时数 (默认按营业日推算)
+  async function fetchForecast({ safetyMargin, lookback, hours } = {}) {
+    if (!activeEventId.value) return;
+    try {
+      const response = await api.get(`/events/${activeEventId.value}/stats/forecast`, {
+        params: {
+          safety_margin: safetyMargin ?? undefined,
+          lookback: lookback || undefined,
+          hours: hours || undefined,
+        },
+      });
+      forecast.value = response.data;
+    } catch (err) {
+      console.error("获取备货预测失败:", err);
+      forecast.value = null;
+    }
+  }
+
+  // 现场售罄时间预估：按最近 windowMinutes 分钟的销售速度推算
+  async function fetchSellout({ windowMinutes } = {}) {
+    if (!activeEventId.value) return;
+    try {
+      const response = await api.get(`/events/${activeEventId.value}/stats/sellout`, {
+        params: { window_minutes: windowMinutes || undefined },
+      });
+      sellout.value = response.data;
+    } catch (err) {
+      console.error("获取售罄时间预估失败:", err);
+      sellout.value = null;
+    }
+  }
+
+  // 跨场次对比：eventIds 为空时对比全部场次；日期按展会日期筛选
+  const comparisonParams = ({ eventIds, startDate, endDate, category } = {}) => ({
+    event_ids: eventIds?.length ? eventIds.join(',') : undefined,
+    start_date: startDate || undefined,
+    end_date: endDate || undefined,
+    category: category || undefined,
+  });
+
+  async function fetchComparison(filters = {}) {
+    isLoading.value = true;
+    error.value = null;
+    try {
+      const response = await api.get('/stats/compare', { params: comparisonParams(filters) });
+      comparison.value = response.data;
+    } catch (err) {
+      console.error("获取跨场次对比失败:", err);
+      error.value = err.response?.data?.error || "加载跨场次对比时发生网络错误。";
+      comparison.value = null;
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  // 关联分析阈值：minSupport / minConfidence / minLift / minOrders / maxSize / top
+  const thresholdParams = ({ minSupport, minConfidence, minLift, minOrders, maxSize, top } = {}) => ({
+    min_support: minSupport ?? undefined,
+    min_confidence: minConfidence ?? undefined,
+    min_lift: minLift ?? undefined,
+    min_orders: minOrders ?? undefined,
+    max_size: maxSize ?? undefined,
+    top: top ?? undefined,
+  });
+
+  // 关联分析：global 为 true 时跨场次统计 (支持 eventIds / startDate / endDate)，否则统计当前场次
+  async function fetchAssociations({ global = false, sessionId, eventIds, startDate, endDate, ...thresholds } = {}) {
+    if (!global && !activeEventId.value) return;
+    isLoading.value = true;
+    error.value = null;
+    try {
+      const response = global
+        ? await api.get('/stats/associations', {
+            params: { ...comparisonParams({ eventIds, startDate, endDate }), ...thresholdParams(thresholds) },
+          })
+        : await api.get(`/events/${activeEventId.value}/stats/associations`, {
+            params: { session_id: sessionId || undefined, ...thresholdParams(thresholds) },
+          });
+      associations.value = response.data;
+    } catch (err) {
+      console.error("获取商品关联分析失败:", err);
+      error.value = err.response?.data?.error || "加载商品关联分析时发生网络错误。";
+      associations.value = null;
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  function comparisonDownloadUrl(filters = {}) {
+    const query = new URLSearchParams(
+      Object.entries(comparisonParams(filters)).filter(([, v]) => v !== undefined)
+    ).toString();
+    return `/api/stats/compare/download${query ? `?${query}` : ''}`;
+  }
+
   return {
     stats,
+    comparison,
+    basketStats,
+    associations,
+    forecast,
+    sellout,
+    sessionStats,
     isLoading,
     error,
     activeEventId,
     downloadUrl,
     setActiveEvent,
     fetchStats,
+    fetchSessionStats,
+    fetchBasketStats,
+    fetchAssociations,
+    fetchForecast,
+    fetchSellout,
+    fetchComparison,
+    comparisonDownloadUrl,
   };
 });
\ No newline at end of file
@@ -8,11 +8,20 @@ export const useEventStore = defineStore('event', () => {
   const isLoading = ref(false);
   const error = ref(null);
 
+  // 收款方式的收款码 URL 转换为完整路径
+  const processPaymentMethod = (method) => {
+    return {
+      ...method,
+      qrcode_url: getImageUrl(method.qrcode_url)
+    };
+  };
+
   // 预处理展会数据,将付款码 URL 转换为完整路径
   const processEvent = (event) => {
     return {
       ...event,
-      qrcode_url: getImageUrl(event.qrcode_url)
+      qrcode_url: getImageUrl(event.qrcode_url),
+      payment_methods: (event.payment_methods || []).map(processPaymentMethod)
     };
   };
 
@@ -56,14 +65,21 @@ export const useEventStore = defineStore('event', () => {
   }
 
   // 【新增】Action: 更新展会状态
-  async function updateEventStatus(eventId, newStatus) {
+  // 允许的流转：未进行 → 进行中 → 已结束 (未进行也可直接结束)；其余变更需 force
+  async function updateEventStatus(eventId, newStatus, { force = false } = {}) {
     try {
       console.log('尝试更新展会状态', eventId, newStatus);
-      const response = await api.put(`/events/${eventId}/status`, { status: newStatus });
-      
       const index = events.value.findIndex(e => e.id === eventId);
+      const version = events.value[index]?.version;
+      const response = await api.put(`/events/${eventId}/status`, { status: newStatus, force, version });
+      
       if (index !== -1) {
-        events.value[index].status = response.data.status;
+        Object.assign(events.value[index], {
+          status: response.data.status,
+          started_at: response.data.started_at,
+          ended_at: response.data.ended_at,
+          version: response.data.version,
+        });
       }
       return response.data;
     } catch (err) {
@@ -72,8 +88,126 @@ export const useEventStore = defineStore('event', () => {
     }
   }
 
+  async function fetchStatusHistory(eventId) {
+    const response = await api.get(`/events/${eventId}/status/history`);
+    return response.data;
+  }
+
+  // 定时开闭场：schedule = { open_at?: 'YYYY-MM-DD HH:MM', close_at?: ... } 或 { use_sessions: true }
+  async function fetchSchedule(eventId) {
+    const response = await api.get(`/events/${eventId}/schedule`);
+    return response.data;
+  }
+
+  async function setSchedule(eventId, schedule) {
+    try {
+      const response = await api.put(`/events/${eventId}/schedule`, schedule);
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '保存定时设置失败，请重试。');
+    }
+  }
+
+  async function clearSchedule(eventId) {
+    await api.delete(`/events/${eventId}/schedule`);
+  }
+
+  // 场次配置：currency_symbol, self_ordering, pending_order_timeout_minutes, max_items_per_order,
+  // soldout_policy, receipt_header, receipt_footer, stats_interval_minutes, vendor_token_hours
+  async function fetchSettings(eventId) {
+    const response = await api.get(`/events/${eventId}/settings`);
+    return response.data;
+  }
+
+  // 部分更新：只提交需要修改的字段
+  async function updateSettings(eventId, changes) {
+    try {
+      const response = await api.put(`/events/${eventId}/settings`, changes);
+      // 售罄策略同时保存在展会信息上
+      const index = events.value.findIndex(e => e.id === Number(eventId));
+      if (index !== -1) {
+        events.value[index].soldout_policy = response.data.soldout_policy;
+      }
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '保存场次设置失败，请重试。');
+    }
+  }
+
+  // 收款方式：列表按显示顺序排列，第一个带图的即展会主收款码
+  async function fetchPaymentMethods(eventId) {
+    const response = await api.get(`/events/${eventId}/payment-methods`);
+    return response.data.map(processPaymentMethod);
+  }
+
+  // 主收款码可能随收款方式变化，修改后刷新本地展会信息
+  async function refreshEvent(eventId) {
+    const index = events.value.findIndex(e => e.id === Number(eventId));
+    if (index === -1) return;
+    const response = await api.get(`/events/${eventId}`);
+    events.value[index] = processEvent(response.data);
+  }
+
+  // formData 字段：label, sort_order, qr_code (图片)
+  async function createPaymentMethod(eventId, formData) {
+    try {
+      const response = await api.post(`/events/${eventId}/payment-methods`, formData);
+      await refreshEvent(eventId);
+      return processPaymentMethod(response.data);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '添加收款方式失败，请重试。');
+    }
+  }
+
+  // 只提交需要修改的字段；remove_qr_code=true 移除收款码
+  async function updatePaymentMethod(eventId, methodId, formData) {
+    try {
+      const response = await api.put(`/events/${eventId}/payment-methods/${methodId}`, formData);
+      await refreshEvent(eventId);
+      return processPaymentMethod(response.data);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '修改收款方式失败，请重试。');
+    }
+  }
+
+  async function deletePaymentMethod(eventId, methodId) {
+    try {
+      await api.delete(`/events/${eventId}/payment-methods/${methodId}`);
+      await refreshEvent(eventId);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '删除收款方式失败，请重试。');
+    }
+  }
+
+  // ids 为新的显示顺序
+  async function reorderPaymentMethods(eventId, ids) {
+    try {
+      const response = await api.put(`/events/${eventId}/payment-methods/order`, { ids });
+      await refreshEvent(eventId);
+      return response.data.map(processPaymentMethod);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '调整收款方式顺序失败，请重试。');
+    }
+  }
+
+  async function fetchUpcomingSchedules(limit) {
+    const response = await api.get('/schedules/upcoming', { params: { limit } });
+    return response.data;
+  }
+
   async function updateEvent(eventId, formData) {
+    const index = events.value.findIndex(e => e.id === Number(eventId));
     try {
+      // 乐观锁：未显式传入时带上本地缓存的版本号
+      if (!formData.has('version') && index !== -1) {
+        formData.append('version', events.value[index].version);
+      }
       // 使用 POST 方法发送 FormData，兼容性更好
       // Axios 会自动为 FormData 设置正确的 Content-Type
       // console.log('尝试更新展会信息', eventId, formData);
@@ -82,7 +216,6 @@ export const useEventStore = defineStore('event', () => {
       const processedEvent = processEvent(response.data);
       // 更新成功后，同样在前端直接更新数据
       // 注意：这里的 id 是 eventId，需要确保类型一致
-      const index = events.value.findIndex(e => e.id === Number(eventId));
       if (index !== -1) {
         // 使用 Object.assign 来合并更新后的字段
         Object.assign(events.value[index], processedEvent);
@@ -90,9 +223,16 @@ export const useEventStore = defineStore('event', () => {
       return processedEvent;
     } catch (err) {
       console.error(err);
+      // 409：数据已被其他设备修改，用服务端返回的最新数据刷新本地
+      const current = err.response?.status === 409 ? err.response.data?.current : null;
+      if (current && index !== -1) {
+        Object.assign(events.value[index], processEvent(current));
+        throw new Error('该展会已在其他设备上被修改，已刷新为最新数据，请确认后重试。');
+      }
       throw new Error(err.response?.data?.error || '更新展会信息失败。');
     }
   }
+  // 删除展会：移入回收站，可在回收站中恢复或彻底删除
   async function deleteEvent(eventId) {
     try {
       await api.delete(`/events/${eventId}`);
@@ -104,6 +244,91 @@ export const useEventStore = defineStore('event', () => {
     }
   }
 
+  // 回收站：每项附带 deleted_at, order_count, total_revenue
+  async function fetchTrash() {
+    const response = await api.get('/events/trash');
+    return response.data.map(processEvent);
+  }
+
+  async function restoreEvent(eventId) {
+    try {
+      const response = await api.post(`/events/${eventId}/restore`);
+      const processedEvent = processEvent(response.data);
+      events.value.unshift(processedEvent);
+      return processedEvent;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '恢复展会失败，请重试。');
+    }
+  }
+
+  // 彻底删除：订单、商品等数据将无法恢复
+  async function purgeEvent(eventId) {
+    try {
+      const response = await api.delete(`/events/${eventId}/purge`);
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '彻底删除失败，请重试。');
+    }
+  }
+
+  async function emptyTrash() {
+    try {
+      const response = await api.delete('/events/trash');
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      const failed = err.response?.data?.failed;
+      throw new Error(failed?.length ? `${failed.length} 个展会删除失败，请重试。` : '清空回收站失败，请重试。');
+    }
+  }
+
+  // 复制展会：options = { date, name?, location?, stock_mode?: 'initial'|'leftover'|'zero'|'fixed', stock? }
+  async function cloneEvent(eventId, options) {
+    try {
+      const response = await api.post(`/events/${eventId}/clone`, options);
+      const processedEvent = processEvent(response.data.event);
+      events.value.unshift(processedEvent);
+      return { ...response.data, event: processedEvent };
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '复制展会失败，请重试。');
+    }
+  }
+
+  // 营业日 (多日展会)：sessions = [{ date: 'YYYY-MM-DD', opens_at?: 'HH:MM', closes_at?: 'HH:MM' }]
+  async function fetchSessions(eventId) {
+    const response = await api.get(`/events/${eventId}/sessions`);
+    return response.data;
+  }
+
+  async function updateSessions(eventId, sessions) {
+    try {
+      const response = await api.put(`/events/${eventId}/sessions`, { sessions });
+      // 展会日期同步为第一天
+      const index = events.value.findIndex(e => e.id === Number(eventId));
+      if (index !== -1 && response.data.length > 0) {
+        events.value[index].date = response.data[0].date;
+      }
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '保存营业日失败，请重试。');
+    }
+  }
+
+  // 记录库存快照：kind = 'opening' (开场) | 'closing' (收摊)
+  async function recordSessionSnapshot(eventId, sessionId, kind) {
+    try {
+      const response = await api.post(`/events/${eventId}/sessions/${sessionId}/snapshot`, { kind });
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '记录库存失败，请重试。');
+    }
+  }
+
   return {
     events,
     isLoading,
@@ -113,5 +338,25 @@ export const useEventStore = defineStore('event', () => {
     updateEventStatus, 
     updateEvent,
     deleteEvent, // 【新增】导出删除函数
+    fetchTrash,
+    restoreEvent,
+    purgeEvent,
+    emptyTrash,
+    cloneEvent,
+    fetchSessions,
+    updateSessions,
+    recordSessionSnapshot,
+    fetchStatusHistory,
+    fetchSchedule,
+    setSchedule,
+    clearSchedule,
+    fetchUpcomingSchedules,
+    fetchSettings,
+    updateSettings,
+    fetchPaymentMethods,
+    createPaymentMethod,
+    updatePaymentMethod,
+    deletePaymentMethod,
+    reorderPaymentMethods,
   };
 });
\ No newline at end of file
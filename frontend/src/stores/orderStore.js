@@ -59,10 +59,13 @@ export const useOrderStore = defineStore('order', () => {
     pollingInterval = null;
   }
 
-  async function markOrderAsCompleted(orderId) {
+  // paymentMethodId：可选，记录/更正顾客实际使用的收款方式
+  async function markOrderAsCompleted(orderId, paymentMethodId = null) {
     if (!activeEventId.value) return;
     try {
-      const response = await api.put(`/events/${activeEventId.value}/orders/${orderId}/status`, { status: 'completed' });
+      const payload = { status: 'completed' };
+      if (paymentMethodId) payload.payment_method_id = paymentMethodId;
+      const response = await api.put(`/events/${activeEventId.value}/orders/${orderId}/status`, payload);
       // 更新成功后，将该订单从 pending 移到 completed
       const completedOrder = pendingOrders.value.find(order => order.id === orderId);
       if (completedOrder) {
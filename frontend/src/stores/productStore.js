@@ -1,7 +1,7 @@
 import { defineStore } from 'pinia';
 import api from '@/services/api';
-import { ref, computed } from 'vue';
-import { getImageUrl } from '@/services/url';
+import { ref, computed, watch } from 'vue';
+import { processProductImages } from '@/services/url';
 
 export const useProductStore = defineStore('masterProduct', () => {
   // --- State ---
@@ -10,12 +10,20 @@ export const useProductStore = defineStore('masterProduct', () => {
   const error = ref(null);
   const searchTerm = ref('');
   const showInactive = ref(false);
+  const selectedTagIds = ref([]); // 标签筛选：同组任一命中，跨组全部命中
+  const searchResultIds = ref(null); // 后端检索结果 (按相关度排序的 id)，null 表示尚未返回
   
   // --- Getters (Computed) ---
   const filteredProducts = computed(() => {
     if (!searchTerm.value.trim()) {
       return masterProducts.value;
     }
+    // 优先使用后端全文检索结果 (支持拼音首字母、假名/罗马音)
+    if (searchResultIds.value) {
+      const byId = new Map(masterProducts.value.map(p => [p.id, p]));
+      return searchResultIds.value.map(id => byId.get(id)).filter(Boolean);
+    }
+    // 检索结果返回前先用本地子串匹配兜底
     const lowerCaseSearchTerm = searchTerm.value.toLowerCase();
     return masterProducts.value.filter(product => {
       const nameMatch = product.name.toLowerCase().includes(lowerCaseSearchTerm);
@@ -25,18 +33,43 @@ export const useProductStore = defineStore('masterProduct', () => {
   });
 
   // --- Actions ---
+
+  // 调用后端检索接口，输入停顿 200ms 后触发
+  let searchTimer = null;
+  let searchSeq = 0;
+  async function searchMasterProducts(term) {
+    const seq = ++searchSeq;
+    try {
+      const params = { q: term, limit: 100, ...(showInactive.value ? { all: true } : {}) };
+      const response = await api.get('/master-products/search', { params });
+      // 丢弃过期的响应，避免快速输入时结果错乱
+      if (seq === searchSeq) {
+        searchResultIds.value = response.data.map(p => p.id);
+      }
+    } catch (err) {
+      console.error(err);
+    }
+  }
+
+  watch([searchTerm, showInactive], ([term]) => {
+    searchResultIds.value = null;
+    clearTimeout(searchTimer);
+    if (term.trim()) {
+      searchTimer = setTimeout(() => searchMasterProducts(term.trim()), 200);
+    }
+  });
   
   // fetchMasterProducts 无需修改
   async function fetchMasterProducts() {
     isLoading.value = true;
     error.value = null;
     try {
-      const params = showInactive.value ? { all: true } : {};
+      const params = {
+        ...(showInactive.value ? { all: true } : {}),
+        ...(selectedTagIds.value.length ? { tags: selectedTagIds.value.join(',') } : {}),
+      };
       const response = await api.get('/master-products', { params });
-      masterProducts.value = response.data.map(product => ({
-        ...product,
-        image_url: getImageUrl(product.image_url)
-      }));
+      masterProducts.value = response.data.map(processProductImages);
     } catch (err) {
       error.value = '无法加载商品库列表。';
       console.error(err);
@@ -51,7 +84,7 @@ export const useProductStore = defineStore('masterProduct', () => {
       // 组件已经准备好了 FormData，我们直接发送即可
       // Axios 会自动设置正确的 Content-Type
       const response = await api.post('/master-products', formData);
-      const product = { ...response.data, image_url: getImageUrl(response.data.image_url) };
+      const product = processProductImages(response.data);
       masterProducts.value.unshift(product);
       return product;
     } catch (err) {
@@ -62,11 +95,15 @@ export const useProductStore = defineStore('masterProduct', () => {
 
   // 【已修改】updateMasterProduct 现在接收 productId 和 FormData 两个参数
   async function updateMasterProduct(productId, formData) {
+    const index = masterProducts.value.findIndex(p => p.id === productId);
     try {
+      // 乐观锁：未显式传入时带上本地缓存的版本号
+      if (!formData.has('version') && index !== -1) {
+        formData.append('version', masterProducts.value[index].version);
+      }
       // 使用 POST 发送 FormData 来更新，以获得更好的兼容性
       const response = await api.post(`/master-products/${productId}`, formData);
-      const product = { ...response.data, image_url: getImageUrl(response.data.image_url) };
-      const index = masterProducts.value.findIndex(p => p.id === productId);
+      const product = processProductImages(response.data);
       if (index !== -1) {
         // 使用新数据替换旧数据，以确保响应性
         masterProducts.value[index] = product;
@@ -74,16 +111,24 @@ export const useProductStore = defineStore('masterProduct', () => {
       return product;
     } catch (err) {
       console.error(err);
+      // 409：数据已被其他设备修改，用服务端返回的最新数据刷新本地
+      const current = err.response?.status === 409 ? err.response.data?.current : null;
+      if (current && index !== -1) {
+        masterProducts.value[index] = processProductImages(current);
+        throw new Error('该商品已在其他设备上被修改，已刷新为最新数据，请确认后重试。');
+      }
       throw new Error(err.response?.data?.error || '更新商品失败，请重试。');
     }
   }
   
-  // toggleProductStatus 无需修改
   async function toggleProductStatus(product) {
     try {
       const newStatus = !product.is_active;
-      const response = await api.put(`/master-products/${product.id}/status`, { is_active: newStatus });
-      const updatedProduct = { ...response.data, image_url: getImageUrl(response.data.image_url) };
+      const response = await api.put(`/master-products/${product.id}/status`, {
+        is_active: newStatus,
+        version: product.version,
+      });
+      const updatedProduct = processProductImages(response.data);
       const index = masterProducts.value.findIndex(p => p.id === product.id);
       if (index !== -1) {
         masterProducts.value[index] = updatedProduct;
@@ -94,6 +139,143 @@ export const useProductStore = defineStore('masterProduct', () => {
     }
   }
 
+  // 相册操作统一返回带 images 的完整商品，直接替换本地缓存
+  function replaceProduct(data) {
+    const product = processProductImages(data);
+    const index = masterProducts.value.findIndex(p => p.id === product.id);
+    if (index !== -1) {
+      masterProducts.value[index] = product;
+    }
+    return product;
+  }
+
+  // 追加相册图片 (files 为 File 数组)
+  async function addProductImages(productId, files) {
+    try {
+      const formData = new FormData();
+      files.forEach(file => formData.append('image', file));
+      const response = await api.post(`/master-products/${productId}/images`, formData);
+      return replaceProduct(response.data);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '上传图片失败。');
+    }
+  }
+
+  // 调整相册顺序，第一张成为封面
+  async function reorderProductImages(productId, imageIds) {
+    try {
+      const response = await api.put(`/master-products/${productId}/images/order`, { image_ids: imageIds });
+      return replaceProduct(response.data);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '调整图片顺序失败。');
+    }
+  }
+
+  // 覆盖式设置商品标签
+  async function setProductTags(productId, tagIds) {
+    try {
+      const response = await api.put(`/master-products/${productId}/tags`, { tag_ids: tagIds });
+      return replaceProduct(response.data);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '设置标签失败。');
+    }
+  }
+
+  // translations: [{ locale: 'en', name, description }]，覆盖式设置
+  async function setProductTranslations(productId, translations) {
+    try {
+      const response = await api.put(`/master-products/${productId}/translations`, { translations });
+      return replaceProduct(response.data);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '保存多语言信息失败。');
+    }
+  }
+
+  async function deleteProductImage(productId, imageId) {
+    try {
+      const response = await api.delete(`/master-products/${productId}/images/${imageId}`);
+      return replaceProduct(response.data);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '删除图片失败。');
+    }
+  }
+
+  // 归档后从列表中移除；取消归档时重新拉取列表
+  // version 缺省时取本地缓存中的版本号 (取消归档时商品不在缓存中，需由调用方传入)
+  async function archiveProduct(productId, archived = true, version) {
+    try {
+      const current = masterProducts.value.find(p => p.id === productId);
+      const response = await api.put(`/master-products/${productId}/archive`, {
+        archived,
+        version: version ?? current?.version,
+      });
+      if (archived) {
+        masterProducts.value = masterProducts.value.filter(p => p.id !== productId);
+      } else {
+        await fetchMasterProducts();
+      }
+      return processProductImages(response.data);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '归档商品失败。');
+    }
+  }
+
+  // 已被场次引用时后端返回 409，错误信息中附带引用的场次名
+  async function deleteMasterProduct(productId) {
+    try {
+      await api.delete(`/master-products/${productId}`);
+      masterProducts.value = masterProducts.value.filter(p => p.id !== productId);
+    } catch (err) {
+      console.error(err);
+      if (err.response?.status === 409) {
+        const events = (err.response.data.events || []).join('、');
+        throw new Error(`该商品已在场次中使用 (${events})，请改为归档或合并。`);
+      }
+      throw new Error(err.response?.data?.error || '删除商品失败。');
+    }
+  }
+
+  // 把 duplicateId 合并到 keepId，返回 { product, merged_from, summary }
+  // version 缺省时取本地缓存中保留方的版本号
+  async function mergeMasterProducts(keepId, duplicateId, version) {
+    try {
+      const current = masterProducts.value.find(p => p.id === keepId);
+      const response = await api.post(`/master-products/${keepId}/merge`, {
+        duplicate_id: duplicateId,
+        version: version ?? current?.version,
+      });
+      masterProducts.value = masterProducts.value.filter(p => p.id !== duplicateId);
+      replaceProduct(response.data.product);
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '合并商品失败。');
+    }
+  }
+
+  // 把商品库的名称/编号 (可选价格) 同步到未结束场次；dryRun 时只返回差异预览
+  // options: { productIds, eventIds, includePrice, dryRun }
+  async function propagateToEvents(options = {}) {
+    try {
+      const response = await api.post('/master-products/propagate', {
+        product_ids: options.productIds || [],
+        event_ids: options.eventIds || [],
+        include_price: !!options.includePrice,
+        dry_run: !!options.dryRun,
+      });
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '同步到场次失败。');
+    }
+  }
+
   // --- Return ---
   return {
     masterProducts,
@@ -104,7 +286,18 @@ export const useProductStore = defineStore('masterProduct', () => {
     fetchMasterProducts,
     createMasterProduct,
     updateMasterProduct,
+    searchMasterProducts,
     toggleProductStatus,
+    addProductImages,
+    reorderProductImages,
+    deleteProductImage,
+    setProductTags,
+    setProductTranslations,
+    archiveProduct,
+    deleteMasterProduct,
+    mergeMasterProducts,
+    propagateToEvents,
     showInactive,
+    selectedTagIds,
   };
 });
\ No newline at end of file
@@ -216,12 +216,150 @@ export const useSyncStore = defineStore('sync', () => {
     }
   }
 
+  // 商品表格 (CSV / XLSX)：导出后可在 Excel 中批量编辑再导入
+  async function exportCatalog(format = 'xlsx') {
+    isExporting.value = true
+    lastError.value = null
+
+    const mime = format === 'csv'
+      ? 'text/csv'
+      : 'application/vnd.openxmlformats-officedocument.spreadsheetml.sheet'
+    try {
+      const response = await api.get('/sync/export-catalog', {
+        params: { format },
+        responseType: 'arraybuffer'
+      })
+      const disposition = response.headers?.['content-disposition'] || response.headers?.['Content-Disposition'] || ''
+      const filename = parseFilenameFromDisposition(disposition, `catalog.${format}`)
+
+      if (env.isTauri) {
+        const [dialogModule, fsModule] = await Promise.all([
+          import('@tauri-apps/plugin-dialog'),
+          import('@tauri-apps/plugin-fs')
+        ])
+        const filePath = await dialogModule.save({
+          defaultPath: filename,
+          filters: [{ name: format.toUpperCase(), extensions: [format] }]
+        })
+        if (!filePath) return { filename: null, cancelled: true }
+        await fsModule.writeFile(filePath, new Uint8Array(response.data))
+        return { filename: filePath }
+      }
+
+      triggerBrowserDownload(toBlob(response.data, mime), filename)
+      return { filename }
+    } catch (err) {
+      console.error(err)
+      lastError.value = err
+      throw new Error(err?.message || '导出失败，请稍后重试')
+    } finally {
+      isExporting.value = false
+    }
+  }
+
+  // options.images: 图片 ZIP (可选)；options.mapping: { 表头: 字段 }；options.dryRun: 只校验不写入
+  // 返回导入报告 { created, updated, skipped, errors: [{ row, column, message }], ... }
+  async function importCatalog(file, options = {}) {
+    if (!file) throw new Error('请选择要导入的文件')
+
+    isImporting.value = true
+    lastError.value = null
+    try {
+      const formData = new FormData()
+      formData.append('file', file)
+      if (options.images) formData.append('images', options.images)
+      if (options.mapping) formData.append('mapping', JSON.stringify(options.mapping))
+      if (options.dryRun) formData.append('dry_run', 'true')
+      const response = await api.post('/sync/import-catalog', formData)
+      return response.data
+    } catch (err) {
+      console.error(err)
+      lastError.value = err
+      const msg =
+        err?.response?.data?.error ||
+        err?.message ||
+        '导入失败，请检查表格格式'
+      throw new Error(msg)
+    } finally {
+      isImporting.value = false
+    }
+  }
+
+  // 展会归档 (.boothevent)：场次、商品、订单、盘点及用到的商品资料与图片
+  async function exportEvent(eventId) {
+    isExporting.value = true
+    lastError.value = null
+    try {
+      const response = await api.get(`/sync/export-event/${eventId}`, { responseType: 'arraybuffer' })
+      const disposition = response.headers?.['content-disposition'] || response.headers?.['Content-Disposition'] || ''
+      const filename = parseFilenameFromDisposition(disposition, `booth_event_${eventId}.boothevent`)
+
+      if (env.isTauri) {
+        const [dialogModule, fsModule] = await Promise.all([
+          import('@tauri-apps/plugin-dialog'),
+          import('@tauri-apps/plugin-fs')
+        ])
+        const filePath = await dialogModule.save({
+          defaultPath: filename,
+          filters: [{ name: 'Booth Event', extensions: ['boothevent', 'zip'] }]
+        })
+        if (!filePath) return { filename: null, cancelled: true }
+        await fsModule.writeFile(filePath, new Uint8Array(response.data))
+        return { filename: filePath }
+      }
+
+      triggerBrowserDownload(toBlob(response.data, 'application/zip'), filename)
+      return { filename }
+    } catch (err) {
+      console.error(err)
+      lastError.value = err
+      throw new Error(err?.message || '导出失败，请稍后重试')
+    } finally {
+      isExporting.value = false
+    }
+  }
+
+  // 同一归档已导入过时后端返回 409 (含已导入的 event_id)；options.force 强制再导入一份
+  async function importEvent(file, options = {}) {
+    if (!file) throw new Error('请选择要导入的文件')
+
+    isImporting.value = true
+    lastError.value = null
+    try {
+      const formData = new FormData()
+      formData.append('file', file)
+      const response = await api.post('/sync/import-event', formData, {
+        params: options.force ? { force: true } : undefined
+      })
+      return response.data
+    } catch (err) {
+      console.error(err)
+      lastError.value = err
+      if (err?.response?.status === 409) {
+        const e = new Error(err.response.data?.error || '该归档已导入过')
+        e.existingEventId = err.response.data?.event_id
+        throw e
+      }
+      const msg =
+        err?.response?.data?.error ||
+        err?.message ||
+        '导入失败，请检查文件格式'
+      throw new Error(msg)
+    } finally {
+      isImporting.value = false
+    }
+  }
+
   return {
     isExporting,
     isImporting,
     lastError,
     exportProducts,
     importProducts,
-    importProductsFromPath
+    importProductsFromPath,
+    exportCatalog,
+    importCatalog,
+    exportEvent,
+    importEvent
   }
 })
@@ -0,0 +1,109 @@
+// src/stores/tagStore.js
+
+import { defineStore } from 'pinia';
+import { ref } from 'vue';
+import api from '@/services/api';
+
+export const useTagStore = defineStore('tag', () => {
+  // --- State ---
+  const tagGroups = ref([]); // [{ id, name, sort_order, tags: [...] }]
+  const isLoading = ref(false);
+  const error = ref(null);
+
+  // --- Actions ---
+  async function fetchTagGroups() {
+    isLoading.value = true;
+    error.value = null;
+    try {
+      const response = await api.get('/tag-groups');
+      tagGroups.value = response.data;
+    } catch (err) {
+      error.value = '无法加载标签列表。';
+      console.error(err);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  async function createTagGroup(name) {
+    try {
+      const response = await api.post('/tag-groups', { name });
+      tagGroups.value.push({ ...response.data, tags: [] });
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '创建标签组失败。');
+    }
+  }
+
+  async function updateTagGroup(groupId, data) {
+    try {
+      const response = await api.put(`/tag-groups/${groupId}`, data);
+      const group = tagGroups.value.find(g => g.id === groupId);
+      if (group) Object.assign(group, response.data);
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '更新标签组失败。');
+    }
+  }
+
+  // 删除标签组会同时删除组内所有标签
+  async function deleteTagGroup(groupId) {
+    try {
+      await api.delete(`/tag-groups/${groupId}`);
+      tagGroups.value = tagGroups.value.filter(g => g.id !== groupId);
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '删除标签组失败。');
+    }
+  }
+
+  async function createTag(groupId, name, color = null) {
+    try {
+      const response = await api.post('/tags', { group_id: groupId, name, color });
+      const group = tagGroups.value.find(g => g.id === groupId);
+      if (group) group.tags.push(response.data);
+      return response.data;
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '创建标签失败。');
+    }
+  }
+
+  async function updateTag(tagId, data) {
+    try {
+      await api.put(`/tags/${tagId}`, data);
+      // 可能移动了标签组，直接重新拉取
+      await fetchTagGroups();
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '更新标签失败。');
+    }
+  }
+
+  async function deleteTag(tagId) {
+    try {
+      await api.delete(`/tags/${tagId}`);
+      tagGroups.value.forEach(g => {
+        g.tags = g.tags.filter(t => t.id !== tagId);
+      });
+    } catch (err) {
+      console.error(err);
+      throw new Error(err.response?.data?.error || '删除标签失败。');
+    }
+  }
+
+  return {
+    tagGroups,
+    isLoading,
+    error,
+    fetchTagGroups,
+    createTagGroup,
+    updateTagGroup,
+    deleteTagGroup,
+    createTag,
+    updateTag,
+    deleteTag,
+  };
+});
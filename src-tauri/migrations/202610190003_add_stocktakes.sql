@@ -0,0 +1,33 @@
+-- 盘点 (Stocktake)：每日收摊时清点实物库存，与系统 current_stock 对比
+
+-- 1. 盘点会话
+CREATE TABLE IF NOT EXISTS stocktakes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open', -- 'open', 'committed', 'cancelled'
+    note TEXT,
+    applied BOOLEAN NOT NULL DEFAULT 0,  -- 提交时是否已将差异写回 products.current_stock
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at DATETIME,
+
+    FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_stocktakes_event_id ON stocktakes(event_id);
+
+-- 2. 盘点明细
+-- 商品编号/名称/单价为快照，即使场次商品被删除，历史盘点仍可复盘
+CREATE TABLE IF NOT EXISTS stocktake_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    stocktake_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,       -- 指向 products 表 (库存表) 的 ID
+    product_code TEXT NOT NULL,
+    product_name TEXT NOT NULL,
+    unit_price REAL NOT NULL,
+    expected_stock INTEGER NOT NULL,   -- 开始盘点时系统记录的 current_stock
+    counted_stock INTEGER,             -- 实际清点数量，NULL 表示尚未清点
+    adjustment INTEGER,                -- 提交时实际写回的修正量 (counted - expected)
+
+    FOREIGN KEY (stocktake_id) REFERENCES stocktakes(id) ON DELETE CASCADE,
+    UNIQUE (stocktake_id, product_id)
+);
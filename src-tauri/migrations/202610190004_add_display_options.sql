@@ -0,0 +1,21 @@
+-- 场次商品展示控制：自定义排序、推荐/新品标记、售罄展示策略
+
+-- 1. 场次商品展示顺序与角标
+ALTER TABLE products ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0; -- 越小越靠前
+ALTER TABLE products ADD COLUMN is_featured BOOLEAN NOT NULL DEFAULT 0; -- 推荐角标
+ALTER TABLE products ADD COLUMN is_new BOOLEAN NOT NULL DEFAULT 0;      -- 新品角标
+
+-- 已有数据按原来的 product_code 顺序初始化，保证升级后展示顺序不变
+UPDATE products
+SET sort_order = (
+    SELECT COUNT(*) FROM products p2
+    WHERE p2.event_id = products.event_id
+      AND (p2.product_code < products.product_code
+           OR (p2.product_code = products.product_code AND p2.id < products.id))
+);
+
+CREATE INDEX IF NOT EXISTS idx_products_event_sort ON products(event_id, sort_order);
+
+-- 2. 售罄商品在顾客点单页的展示策略
+-- 枚举: 'show' (置灰展示), 'bottom' (移到末尾), 'hide' (隐藏)
+ALTER TABLE events ADD COLUMN soldout_policy TEXT NOT NULL DEFAULT 'show';
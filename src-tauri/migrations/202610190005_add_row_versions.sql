@@ -0,0 +1,5 @@
+-- 乐观并发控制：每次修改行数据时 version + 1
+-- 更新接口需要携带读取时的 version，不一致则拒绝写入 (409 Conflict)
+ALTER TABLE products ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE master_products ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE events ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,13 @@
+-- 商品全文检索 (FTS5)
+-- 索引内容由后端 utils::search 写入：拼音/罗马音需要在 Rust 侧计算，无法用触发器维护
+-- 中日文字符在写入前按字拆分 (以空格分隔)，使任意连续子串都可以用短语查询命中
+CREATE VIRTUAL TABLE IF NOT EXISTS product_search USING fts5(
+    master_product_id UNINDEXED,
+    product_code,
+    name,
+    category,
+    tags,
+    phonetic,           -- 拼音全拼/首字母、假名罗马音
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '1 2 3'
+);
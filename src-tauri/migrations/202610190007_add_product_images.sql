@@ -0,0 +1,33 @@
+-- 商品图片：多图相册 + 服务端生成的中图/缩略图
+
+-- 1. 相册 (position 越小越靠前，第一张为封面)
+CREATE TABLE IF NOT EXISTS master_product_images (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    master_product_id INTEGER NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    url TEXT NOT NULL,        -- 压缩后的大图 (长边 ≤ 1600)
+    url_md TEXT NOT NULL,     -- 中图 (长边 ≤ 800)
+    url_sm TEXT NOT NULL,     -- 缩略图 (长边 ≤ 320)
+    width INTEGER,
+    height INTEGER,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (master_product_id) REFERENCES master_products(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_master_product_images_product
+    ON master_product_images(master_product_id, position);
+
+-- 2. 封面各尺寸冗余在商品表上，列表/点单页无需再关联相册
+ALTER TABLE master_products ADD COLUMN image_md_url TEXT;
+ALTER TABLE master_products ADD COLUMN image_sm_url TEXT;
+
+-- 3. 旧数据：原图没有生成过其它尺寸，各尺寸都指向原图
+UPDATE master_products
+SET image_md_url = image_url, image_sm_url = image_url
+WHERE image_url IS NOT NULL;
+
+INSERT INTO master_product_images (master_product_id, position, url, url_md, url_sm)
+SELECT id, 0, image_url, image_url, image_url
+FROM master_products
+WHERE image_url IS NOT NULL;
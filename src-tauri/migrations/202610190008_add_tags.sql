@@ -0,0 +1,41 @@
+-- 商品标签体系：标签组 (作品/角色/品类/批次...) → 标签 → 商品 (多对多)
+
+-- 1. 标签组
+CREATE TABLE IF NOT EXISTS tag_groups (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 2. 标签 (同一组内名称唯一)
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    group_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    color TEXT,                          -- 前端展示用颜色，如 "#ff6699"
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (group_id) REFERENCES tag_groups(id) ON DELETE CASCADE,
+    UNIQUE (group_id, name)
+);
+
+-- 3. 商品-标签关联
+CREATE TABLE IF NOT EXISTS master_product_tags (
+    master_product_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+
+    PRIMARY KEY (master_product_id, tag_id),
+    FOREIGN KEY (master_product_id) REFERENCES master_products(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_master_product_tags_tag ON master_product_tags(tag_id);
+
+-- 4. 默认标签组
+INSERT OR IGNORE INTO tag_groups (name, sort_order) VALUES
+    ('作品/IP', 0),
+    ('角色', 1),
+    ('品类', 2),
+    ('批次', 3);
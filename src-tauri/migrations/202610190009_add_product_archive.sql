@@ -0,0 +1,3 @@
+-- 全局商品归档：归档后从商品库、检索和上架选择中隐藏，但保留历史场次与订单引用
+-- NULL 表示未归档
+ALTER TABLE master_products ADD COLUMN archived_at DATETIME;
@@ -0,0 +1,16 @@
+-- 商品多语言：默认语言的名称/描述仍在 master_products 上 (订单快照与报表始终使用默认名称)，
+-- 其他语言存放在翻译表中，按请求语言返回，缺失时回退到默认值
+
+-- 1. 默认语言的商品描述
+ALTER TABLE master_products ADD COLUMN description TEXT;
+
+-- 2. 各语言翻译 (locale 为小写语言代码，如 en / ja / zh)
+CREATE TABLE IF NOT EXISTS master_product_translations (
+    master_product_id INTEGER NOT NULL,
+    locale TEXT NOT NULL,
+    name TEXT,                         -- NULL 表示该语言沿用默认名称
+    description TEXT,
+
+    PRIMARY KEY (master_product_id, locale),
+    FOREIGN KEY (master_product_id) REFERENCES master_products(id) ON DELETE CASCADE
+);
@@ -0,0 +1,43 @@
+-- 多日展会：场次按天拆分为若干营业日 (Session)，订单归属到营业日，并记录每日开场/收摊库存
+
+-- 1. 营业日
+CREATE TABLE IF NOT EXISTS event_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id INTEGER NOT NULL,
+    session_date TEXT NOT NULL,        -- 格式 YYYY-MM-DD
+    opens_at TEXT,                     -- 营业时间 HH:MM，可为空
+    closes_at TEXT,
+
+    FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE,
+    UNIQUE (event_id, session_date)
+);
+
+-- 2. 订单归属的营业日 (历史订单按下单日期回填)
+ALTER TABLE orders ADD COLUMN session_id INTEGER REFERENCES event_sessions(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_orders_session_id ON orders(session_id);
+
+-- 3. 每日库存快照：开场库存在当日首笔订单前自动记录，也可手动记录；收摊库存手动记录
+-- 未记录的值由统计接口按销量推算
+CREATE TABLE IF NOT EXISTS event_session_stock (
+    session_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,       -- 指向 products 表 (库存表) 的 ID
+    opening_stock INTEGER,
+    closing_stock INTEGER,
+
+    PRIMARY KEY (session_id, product_id),
+    FOREIGN KEY (session_id) REFERENCES event_sessions(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+-- 4. 回填：每个已有场次以 event_date 建一个营业日，另按历史订单的下单日期补齐
+INSERT OR IGNORE INTO event_sessions (event_id, session_date)
+SELECT id, event_date FROM events;
+
+INSERT OR IGNORE INTO event_sessions (event_id, session_date)
+SELECT DISTINCT event_id, DATE(created_at) FROM orders;
+
+UPDATE orders SET session_id = (
+    SELECT s.id FROM event_sessions s
+    WHERE s.event_id = orders.event_id AND s.session_date = DATE(orders.created_at)
+);
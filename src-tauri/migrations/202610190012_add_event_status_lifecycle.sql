@@ -0,0 +1,22 @@
+-- 场次状态生命周期：记录实际开始/结束时间与每次状态变更
+
+-- 1. 最近一次开始营业 / 结束营业的时间 (NULL 表示尚未发生)
+ALTER TABLE events ADD COLUMN started_at DATETIME;
+ALTER TABLE events ADD COLUMN ended_at DATETIME;
+
+-- 2. 状态变更日志
+CREATE TABLE IF NOT EXISTS event_status_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id INTEGER NOT NULL,
+    from_status TEXT NOT NULL,
+    to_status TEXT NOT NULL,
+    forced BOOLEAN NOT NULL DEFAULT 0,  -- 管理员强制执行的非常规变更
+    changed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_status_changes_event_id ON event_status_changes(event_id);
+
+-- 3. 状态改为强类型后，清理历史遗留的非法值
+UPDATE events SET status = '未进行' WHERE status NOT IN ('未进行', '进行中', '已结束');
@@ -0,0 +1,24 @@
+-- 定时开闭场：到点自动切换场次状态
+
+-- 1. 定时任务
+-- action: open = 开始营业 (→ 进行中)，pause = 当日收摊 (→ 未进行，多日展会非最后一天)，
+--         close = 结束营业 (→ 已结束)
+-- run_at 为本地时间 YYYY-MM-DD HH:MM；session_id 非空表示由营业日的营业时间生成
+CREATE TABLE IF NOT EXISTS event_schedules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id INTEGER NOT NULL,
+    session_id INTEGER,
+    action TEXT NOT NULL,
+    run_at TEXT NOT NULL,
+    state TEXT NOT NULL DEFAULT 'pending', -- 'pending', 'applied', 'skipped'
+    note TEXT,                             -- 跳过原因
+    processed_at DATETIME,
+
+    FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE,
+    FOREIGN KEY (session_id) REFERENCES event_sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_schedules_pending ON event_schedules(state, run_at);
+
+-- 2. 状态变更来源：manual = 管理员手动，schedule = 定时任务
+ALTER TABLE event_status_changes ADD COLUMN source TEXT NOT NULL DEFAULT 'manual';
@@ -0,0 +1,9 @@
+-- 场次配置：按场次保存的可选项 (货币符号、自助下单、待付款超时、小票文字等)
+-- settings 为 JSON，缺省字段按默认值处理；售罄策略仍以 events.soldout_policy 为准
+CREATE TABLE IF NOT EXISTS event_settings (
+    event_id INTEGER PRIMARY KEY,
+    settings TEXT NOT NULL DEFAULT '{}',
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE
+);
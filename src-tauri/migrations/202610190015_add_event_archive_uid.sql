@@ -0,0 +1,5 @@
+-- 展会归档 (.boothevent)：每个场次首次导出时分配唯一标识，导入时写入同一标识，
+-- 用于识别重复导入 (包括同一场次多次导出的归档)
+ALTER TABLE events ADD COLUMN archive_uid TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_events_archive_uid ON events(archive_uid);
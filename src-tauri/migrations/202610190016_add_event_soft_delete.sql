@@ -0,0 +1,4 @@
+-- 展会回收站：删除场次先移入回收站 (deleted_at 非空)，可恢复；彻底删除时才清除数据
+ALTER TABLE events ADD COLUMN deleted_at DATETIME;
+
+CREATE INDEX IF NOT EXISTS idx_events_deleted_at ON events(deleted_at);
@@ -0,0 +1,29 @@
+-- 多种收款方式：每个场次可配置多个带名称的收款码 (微信、支付宝、PayPay 等)
+-- events.payment_qr_code_path 保留，始终同步为排在第一位的收款码 (兼容旧客户端)
+
+-- 1. 收款方式
+CREATE TABLE IF NOT EXISTS event_payment_methods (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id INTEGER NOT NULL,
+    label TEXT NOT NULL,               -- 显示名称，如 "微信支付"
+    qr_code_path TEXT,                 -- /uploads/events/xxx.jpg，可为空 (如现金)
+    sort_order INTEGER NOT NULL DEFAULT 0, -- 越小越靠前
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (event_id) REFERENCES events(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_payment_methods_event_id ON event_payment_methods(event_id);
+
+-- 2. 回填：已有的单个收款码转为一个收款方式
+INSERT INTO event_payment_methods (event_id, label, qr_code_path, sort_order)
+SELECT id, '付款码',
+       CASE WHEN payment_qr_code_path LIKE '/uploads/%' THEN payment_qr_code_path
+            ELSE '/uploads/' || payment_qr_code_path END,
+       0
+FROM events
+WHERE payment_qr_code_path IS NOT NULL AND payment_qr_code_path != '';
+
+-- 3. 订单记录顾客使用的收款方式；名称为快照，收款方式删除后仍可查看
+ALTER TABLE orders ADD COLUMN payment_method_id INTEGER REFERENCES event_payment_methods(id) ON DELETE SET NULL;
+ALTER TABLE orders ADD COLUMN payment_method TEXT;
@@ -0,0 +1,11 @@
+-- 同一场次同时只允许一个进行中的盘点，由唯一索引保证 (并发开始盘点时只有一个能写入)
+
+-- 1. 清理历史遗留的重复盘点：每个场次只保留最新的一个，其余标记为已取消
+UPDATE stocktakes
+SET status = 'cancelled', finished_at = CURRENT_TIMESTAMP
+WHERE status = 'open'
+  AND id NOT IN (SELECT MAX(id) FROM stocktakes WHERE status = 'open' GROUP BY event_id);
+
+-- 2. 部分唯一索引：只约束 status = 'open' 的行
+CREATE UNIQUE INDEX IF NOT EXISTS idx_stocktakes_one_open
+ON stocktakes(event_id) WHERE status = 'open';
@@ -186,11 +186,24 @@ async fn reset_database_handler(State(state): State<AppState>, _: AdminOnly) ->
     // 注意：不删除 settings 表中的管理员密码
 
     let tables_to_clear = vec![
-        "order_items",     // 订单明细表（子表）
-        "orders",          // 订单主表
-        "products",        // 场次库存商品表
-        "events",          // 展会场次表
-        "master_products", // 全局商品库表
+        "stocktake_items",             // 盘点明细表（子表）
+        "stocktakes",                  // 盘点会话表
+        "order_items",                 // 订单明细表（子表）
+        "orders",                      // 订单主表
+        "event_schedules",             // 定时开闭场任务表
+        "event_status_changes",        // 场次状态变更日志
+        "event_settings",              // 场次配置表
+        "event_payment_methods",       // 收款方式表
+        "event_session_stock",         // 每日库存快照表
+        "event_sessions",              // 营业日表
+        "products",                    // 场次库存商品表
+        "events",                      // 展会场次表
+        "master_product_tags",         // 商品-标签关联表
+        "tags",                        // 标签表
+        "master_product_images",       // 商品相册表
+        "master_product_translations", // 商品多语言表
+        "master_products",             // 全局商品库表
+        "product_search",              // 商品检索索引 (FTS5)
     ];
 
     for table in &tables_to_clear {
@@ -0,0 +1,733 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{query, query_as, FromRow, Sqlite, SqlitePool, Transaction};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{Cursor, Read, Write};
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    api::{
+        event::EventResponse,
+        event_settings::load_settings,
+        guard::AdminOnly,
+        master_product::{attach_images, attach_tags, fetch_translations, replace_translations},
+        payment::{fetch_methods, sync_primary_qr},
+        session::fetch_sessions,
+        sync::{extract_upload_files, merge_tags, replace_gallery, write_upload_files},
+    },
+    db::models::{
+        Event, EventSession, EventSettings, EventStatus, MasterProduct, Order, OrderItem,
+        PaymentMethod, Product, Stocktake, StocktakeItem,
+    },
+    state::AppState,
+    utils::{file::upload_url, search},
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 管理员：导出/导入完整展会归档 (.boothevent)
+        .route("/sync/export-event/:event_id", get(export_event))
+        .route("/sync/import-event", post(import_event))
+        .layer(DefaultBodyLimit::max(1000 * 1024 * 1024))
+}
+
+// 归档格式
+const ARCHIVE_FORMAT: &str = "boothevent";
+const ARCHIVE_VERSION: u32 = 1;
+const ARCHIVE_DATA_FILE: &str = "event.json";
+
+// ==========================================
+// DTOs (数据传输对象)
+// ==========================================
+
+// event.json 的结构；各表保留源数据库中的 id，导入时重新映射
+#[derive(Serialize, Deserialize)]
+struct EventArchive {
+    format: String,
+    version: u32,
+    archive_uid: String,
+    exported_at: String,
+    event: Event,
+    #[serde(default)]
+    settings: EventSettings,
+    #[serde(default)]
+    payment_methods: Vec<PaymentMethod>,
+    #[serde(default)]
+    sessions: Vec<EventSession>,
+    #[serde(default)]
+    session_stock: Vec<SessionStockRow>,
+    #[serde(default)]
+    status_changes: Vec<StatusChangeRow>,
+    #[serde(default)]
+    master_products: Vec<MasterProduct>,
+    #[serde(default)]
+    products: Vec<Product>,
+    #[serde(default)]
+    orders: Vec<Order>,
+    #[serde(default)]
+    order_items: Vec<OrderItem>,
+    #[serde(default)]
+    stocktakes: Vec<Stocktake>,
+    #[serde(default)]
+    stocktake_items: Vec<StocktakeItem>,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+struct SessionStockRow {
+    session_id: i64,
+    product_id: i64,
+    opening_stock: Option<i64>,
+    closing_stock: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, FromRow)]
+struct StatusChangeRow {
+    from_status: EventStatus,
+    to_status: EventStatus,
+    forced: bool,
+    source: String,
+    changed_at: NaiveDateTime,
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    // true：即使已导入过同一归档，也作为新场次再导入一份
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(Serialize, Default)]
+struct ImportCounts {
+    master_products_created: usize,
+    products: usize,
+    orders: usize,
+    order_items: usize,
+    stocktakes: usize,
+    images: usize,
+}
+
+// ==========================================
+// 1. 导出展会归档 (Export)
+// ==========================================
+// 生成的 ZIP 结构:
+// - event.json (场次、配置、营业日、商品、订单、盘点及引用到的全局商品)
+// - products/xxx.jpg, events/xxx.jpg (图片资源，相对于 uploads 的路径)
+async fn export_event(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(event_id): Path<i64>,
+) -> impl IntoResponse {
+    let archive = match collect_archive(&state.db, event_id).await {
+        Ok(Some(a)) => a,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Event not found").into_response(),
+        Err(e) => {
+            eprintln!("Export event error: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+        }
+    };
+
+    let json_str = match serde_json::to_string_pretty(&archive) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("JSON serialization error: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to serialize data",
+            )
+                .into_response();
+        }
+    };
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o755);
+
+    if let Err(e) = zip
+        .start_file(ARCHIVE_DATA_FILE, options)
+        .map_err(|e| e.to_string())
+        .and_then(|_| {
+            zip.write_all(json_str.as_bytes())
+                .map_err(|e| e.to_string())
+        })
+    {
+        eprintln!("ZIP write json error: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to write json to zip",
+        )
+            .into_response();
+    }
+
+    // 商品图片 (封面各尺寸 + 相册各尺寸) 与付款码，去重
+    let mut image_urls: BTreeSet<&String> = BTreeSet::new();
+    for prod in &archive.master_products {
+        image_urls.extend(prod.image_url.iter());
+        image_urls.extend(prod.image_md_url.iter());
+        image_urls.extend(prod.image_sm_url.iter());
+        for img in &prod.images {
+            image_urls.extend([&img.url, &img.url_md, &img.url_sm]);
+        }
+    }
+    image_urls.extend(archive.event.payment_qr_code_path.iter());
+    for method in &archive.payment_methods {
+        image_urls.extend(method.qr_code_path.iter());
+    }
+    write_upload_files(&mut zip, &state.upload_dir, image_urls, options);
+
+    let buf = match zip.finish() {
+        Ok(c) => c.into_inner(),
+        Err(e) => {
+            eprintln!("ZIP finish error: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to finalize zip").into_response();
+        }
+    };
+
+    let filename = format!(
+        "booth_event_{}_{}.boothevent",
+        event_id,
+        Local::now().format("%Y%m%d_%H%M")
+    );
+    let disposition = format!("attachment; filename=\"{}\"", filename);
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (header::CONTENT_DISPOSITION, disposition.as_str()),
+        ],
+        buf,
+    )
+        .into_response()
+}
+
+async fn collect_archive(
+    db: &SqlitePool,
+    event_id: i64,
+) -> Result<Option<EventArchive>, sqlx::Error> {
+    let event: Option<Event> = query_as("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
+        .bind(event_id)
+        .fetch_optional(db)
+        .await?;
+    let Some(event) = event else {
+        return Ok(None);
+    };
+
+    // 首次导出时分配归档标识，之后同一场次的归档都使用它
+    query("UPDATE events SET archive_uid = ? WHERE id = ? AND archive_uid IS NULL")
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(event_id)
+        .execute(db)
+        .await?;
+    let (archive_uid,): (String,) = query_as("SELECT archive_uid FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_one(db)
+        .await?;
+
+    let session_stock = query_as(
+        r#"
+        SELECT ss.* FROM event_session_stock ss
+        JOIN event_sessions s ON ss.session_id = s.id
+        WHERE s.event_id = ?
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await?;
+
+    let status_changes = query_as(
+        r#"
+        SELECT from_status, to_status, forced, source, changed_at FROM event_status_changes
+        WHERE event_id = ?
+        ORDER BY changed_at ASC, id ASC
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await?;
+
+    // 只导出本场次用到的全局商品，附带相册、标签与多语言
+    let mut master_products: Vec<MasterProduct> = query_as(
+        "SELECT * FROM master_products WHERE id IN (SELECT master_product_id FROM products WHERE event_id = ?)",
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await?;
+    attach_images(db, &mut master_products).await;
+    attach_tags(db, &mut master_products).await;
+    let mut translations = fetch_translations(db, None).await;
+    for product in master_products.iter_mut() {
+        product.translations = translations.remove(&product.id).unwrap_or_default();
+    }
+
+    let products = query_as("SELECT * FROM products WHERE event_id = ? ORDER BY id ASC")
+        .bind(event_id)
+        .fetch_all(db)
+        .await?;
+    let orders = query_as("SELECT * FROM orders WHERE event_id = ? ORDER BY id ASC")
+        .bind(event_id)
+        .fetch_all(db)
+        .await?;
+    let order_items = query_as(
+        r#"
+        SELECT oi.* FROM order_items oi
+        JOIN orders o ON oi.order_id = o.id
+        WHERE o.event_id = ?
+        ORDER BY oi.id ASC
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await?;
+    let stocktakes = query_as("SELECT * FROM stocktakes WHERE event_id = ? ORDER BY id ASC")
+        .bind(event_id)
+        .fetch_all(db)
+        .await?;
+    let stocktake_items = query_as(
+        r#"
+        SELECT si.* FROM stocktake_items si
+        JOIN stocktakes st ON si.stocktake_id = st.id
+        WHERE st.event_id = ?
+        ORDER BY si.id ASC
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(Some(EventArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        archive_uid,
+        exported_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        settings: load_settings(db, event_id).await,
+        payment_methods: fetch_methods(db, event_id).await,
+        sessions: fetch_sessions(db, event_id).await,
+        event,
+        session_stock,
+        status_changes,
+        master_products,
+        products,
+        orders,
+        order_items,
+        stocktakes,
+        stocktake_items,
+    }))
+}
+
+// ==========================================
+// 2. 导入展会归档 (Import)
+// ==========================================
+// 作为新场次导入，所有 id 重新分配。全局商品按编号匹配本地已有商品 (不覆盖本地资料)，
+// 本地没有的才新建。同一归档已导入过时返回 409，除非 ?force=true。
+async fn import_event(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Query(params): Query<ImportQuery>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut data = None;
+    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
+        if field.name() == Some("file") {
+            match field.bytes().await {
+                Ok(d) => data = Some(d),
+                Err(e) => {
+                    return (StatusCode::BAD_REQUEST, format!("Upload error: {}", e))
+                        .into_response()
+                }
+            }
+            break;
+        }
+    }
+    let Some(data) = data else {
+        return (StatusCode::BAD_REQUEST, "No file found in request").into_response();
+    };
+
+    let bad_request =
+        |msg: String| (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response();
+
+    let mut archive = match ZipArchive::new(Cursor::new(data)) {
+        Ok(a) => a,
+        Err(_) => return bad_request("Invalid ZIP/Boothevent file".into()),
+    };
+
+    let mut json_content = String::new();
+    match archive.by_name(ARCHIVE_DATA_FILE) {
+        Ok(mut file) => {
+            if file.read_to_string(&mut json_content).is_err() {
+                return bad_request(format!("Failed to read {}", ARCHIVE_DATA_FILE));
+            }
+        }
+        Err(_) => return bad_request(format!("Missing {} in archive", ARCHIVE_DATA_FILE)),
+    }
+
+    let parsed: EventArchive = match serde_json::from_str(&json_content) {
+        Ok(a) => a,
+        Err(e) => return bad_request(format!("JSON Parse Error: {}", e)),
+    };
+    if parsed.format != ARCHIVE_FORMAT || parsed.version > ARCHIVE_VERSION {
+        return bad_request(format!(
+            "Unsupported archive: {} v{}",
+            parsed.format, parsed.version
+        ));
+    }
+
+    // 重复导入检测
+    let existing: Option<(i64, String)> =
+        query_as("SELECT id, name FROM events WHERE archive_uid = ?")
+            .bind(&parsed.archive_uid)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
+    if let Some((existing_id, existing_name)) = &existing {
+        if !params.force {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": format!("This archive has already been imported as \"{}\"", existing_name),
+                    "event_id": existing_id
+                })),
+            )
+                .into_response();
+        }
+    }
+
+    // 图片只补齐本地缺失的文件，不覆盖已有文件
+    let images =
+        match extract_upload_files(&mut archive, &state.upload_dir, ARCHIVE_DATA_FILE, false) {
+            Ok(n) => n,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        };
+
+    // 强制再导入的副本不带归档标识，避免与首次导入的场次冲突
+    let archive_uid = existing.is_none().then_some(parsed.archive_uid.as_str());
+
+    let result: Result<(i64, ImportCounts), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let imported = write_archive(&mut tx, &parsed, archive_uid).await?;
+        tx.commit().await?;
+        Ok(imported)
+    }
+    .await;
+
+    let (event_id, mut counts) = match result {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Import event error: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB Write Failed: {}", e),
+            )
+                .into_response();
+        }
+    };
+    counts.images = images;
+
+    if counts.master_products_created > 0 {
+        if let Err(e) = search::rebuild_index(&state.db).await {
+            eprintln!("Search index rebuild failed: {:?}", e);
+        }
+    }
+
+    let event: Option<Event> = query_as("SELECT * FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_optional(&state.db)
+        .await
+        .unwrap_or(None);
+    match event {
+        Some(event) => (
+            StatusCode::CREATED,
+            Json(json!({
+                "event": EventResponse::load(&state.db, event).await,
+                "source_event_id": parsed.event.id,
+                "counts": counts
+            })),
+        )
+            .into_response(),
+        None => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    }
+}
+
+// 按依赖顺序写入：全局商品 → 场次 (配置、收款方式) → 营业日 → 场次商品 → 库存快照 → 订单 → 盘点
+async fn write_archive(
+    tx: &mut Transaction<'_, Sqlite>,
+    archive: &EventArchive,
+    archive_uid: Option<&str>,
+) -> Result<(i64, ImportCounts), sqlx::Error> {
+    let mut counts = ImportCounts::default();
+
+    // 1. 全局商品：按编号匹配
+    let mut master_ids: HashMap<i64, i64> = HashMap::new();
+    for prod in &archive.master_products {
+        let local: Option<(i64,)> =
+            query_as("SELECT id FROM master_products WHERE product_code = ?")
+                .bind(&prod.product_code)
+                .fetch_optional(&mut **tx)
+                .await?;
+        let local_id = match local {
+            Some((id,)) => id,
+            None => {
+                let id = query(
+                    r#"
+                    INSERT INTO master_products
+                        (product_code, name, default_price, category, image_url, image_md_url, image_sm_url, is_active, description, archived_at)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                )
+                .bind(&prod.product_code)
+                .bind(&prod.name)
+                .bind(prod.default_price)
+                .bind(&prod.category)
+                .bind(&prod.image_url)
+                .bind(&prod.image_md_url)
+                .bind(&prod.image_sm_url)
+                .bind(prod.is_active)
+                .bind(&prod.description)
+                .bind(prod.archived_at)
+                .execute(&mut **tx)
+                .await?
+                .last_insert_rowid();
+                replace_gallery(tx, id, prod).await?;
+                merge_tags(tx, id, &prod.tags).await?;
+                replace_translations(tx, id, &prod.translations).await?;
+                counts.master_products_created += 1;
+                id
+            }
+        };
+        master_ids.insert(prod.id, local_id);
+    }
+
+    // 2. 场次 (不导入摊主密码)
+    let event = &archive.event;
+    let event_id = query(
+        r#"
+        INSERT INTO events (name, event_date, location, payment_qr_code_path, soldout_policy, status, started_at, ended_at, archive_uid)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&event.name)
+    .bind(&event.event_date)
+    .bind(&event.location)
+    .bind(&event.payment_qr_code_path)
+    .bind(&event.soldout_policy)
+    .bind(event.status)
+    .bind(event.started_at)
+    .bind(event.ended_at)
+    .bind(archive_uid)
+    .execute(&mut **tx)
+    .await?
+    .last_insert_rowid();
+
+    query("INSERT INTO event_settings (event_id, settings) VALUES (?, ?)")
+        .bind(event_id)
+        .bind(serde_json::to_string(&archive.settings).unwrap_or_else(|_| "{}".to_string()))
+        .execute(&mut **tx)
+        .await?;
+
+    // 收款方式；旧归档只有单个付款码时转为默认收款方式
+    let mut method_ids: HashMap<i64, i64> = HashMap::new();
+    for method in &archive.payment_methods {
+        let id = query(
+            "INSERT INTO event_payment_methods (event_id, label, qr_code_path, sort_order) VALUES (?, ?, ?, ?)",
+        )
+        .bind(event_id)
+        .bind(&method.label)
+        .bind(&method.qr_code_path)
+        .bind(method.sort_order)
+        .execute(&mut **tx)
+        .await?
+        .last_insert_rowid();
+        method_ids.insert(method.id, id);
+    }
+    if let (true, Some(path)) = (
+        archive.payment_methods.is_empty(),
+        &event.payment_qr_code_path,
+    ) {
+        query(
+            "INSERT INTO event_payment_methods (event_id, label, qr_code_path, sort_order) VALUES (?, '付款码', ?, 0)",
+        )
+        .bind(event_id)
+        .bind(upload_url(path))
+        .execute(&mut **tx)
+        .await?;
+    }
+    sync_primary_qr(tx, event_id).await?;
+
+    for change in &archive.status_changes {
+        query(
+            "INSERT INTO event_status_changes (event_id, from_status, to_status, forced, source, changed_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(event_id)
+        .bind(change.from_status)
+        .bind(change.to_status)
+        .bind(change.forced)
+        .bind(&change.source)
+        .bind(change.changed_at)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    // 3. 营业日
+    let mut session_ids: HashMap<i64, i64> = HashMap::new();
+    for session in &archive.sessions {
+        let id = query(
+            "INSERT INTO event_sessions (event_id, session_date, opens_at, closes_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(event_id)
+        .bind(&session.session_date)
+        .bind(&session.opens_at)
+        .bind(&session.closes_at)
+        .execute(&mut **tx)
+        .await?
+        .last_insert_rowid();
+        session_ids.insert(session.id, id);
+    }
+
+    // 4. 场次商品
+    let mut product_ids: HashMap<i64, i64> = HashMap::new();
+    for product in &archive.products {
+        let Some(master_id) = master_ids.get(&product.master_product_id) else {
+            continue;
+        };
+        let id = query(
+            r#"
+            INSERT INTO products
+                (event_id, master_product_id, product_code, name, price, initial_stock, current_stock, sort_order, is_featured, is_new)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(event_id)
+        .bind(master_id)
+        .bind(&product.product_code)
+        .bind(&product.name)
+        .bind(product.price)
+        .bind(product.initial_stock)
+        .bind(product.current_stock)
+        .bind(product.sort_order)
+        .bind(product.is_featured)
+        .bind(product.is_new)
+        .execute(&mut **tx)
+        .await?
+        .last_insert_rowid();
+        product_ids.insert(product.id, id);
+        counts.products += 1;
+    }
+
+    // 订单明细/盘点明细里的商品在源场次中可能已被删除：用原 id 的相反数占位，
+    // 保证不会指向本地其他商品，同时各已删除商品仍能分别统计
+    let map_product = |id: i64| product_ids.get(&id).copied().unwrap_or(-id);
+
+    // 5. 每日库存快照
+    for row in &archive.session_stock {
+        let (Some(session_id), Some(product_id)) = (
+            session_ids.get(&row.session_id),
+            product_ids.get(&row.product_id),
+        ) else {
+            continue;
+        };
+        query(
+            "INSERT INTO event_session_stock (session_id, product_id, opening_stock, closing_stock) VALUES (?, ?, ?, ?)",
+        )
+        .bind(session_id)
+        .bind(product_id)
+        .bind(row.opening_stock)
+        .bind(row.closing_stock)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    // 6. 订单与明细
+    let mut order_ids: HashMap<i64, i64> = HashMap::new();
+    for order in &archive.orders {
+        let session_id = order
+            .session_id
+            .and_then(|id| session_ids.get(&id).copied());
+        let payment_method_id = order
+            .payment_method_id
+            .and_then(|id| method_ids.get(&id).copied());
+        let id = query(
+            r#"
+            INSERT INTO orders (event_id, total_amount, status, created_at, session_id, payment_method_id, payment_method)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(event_id)
+        .bind(order.total_amount)
+        .bind(&order.status)
+        .bind(order.created_at)
+        .bind(session_id)
+        .bind(payment_method_id)
+        .bind(&order.payment_method)
+        .execute(&mut **tx)
+        .await?
+        .last_insert_rowid();
+        order_ids.insert(order.id, id);
+        counts.orders += 1;
+    }
+    for item in &archive.order_items {
+        let Some(order_id) = order_ids.get(&item.order_id) else {
+            continue;
+        };
+        query(
+            "INSERT INTO order_items (order_id, product_id, product_name, product_price, quantity) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(order_id)
+        .bind(map_product(item.product_id))
+        .bind(&item.product_name)
+        .bind(item.product_price)
+        .bind(item.quantity)
+        .execute(&mut **tx)
+        .await?;
+        counts.order_items += 1;
+    }
+
+    // 7. 盘点记录
+    let mut stocktake_ids: HashMap<i64, i64> = HashMap::new();
+    for stocktake in &archive.stocktakes {
+        let id = query(
+            "INSERT INTO stocktakes (event_id, status, note, applied, created_at, finished_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(event_id)
+        .bind(&stocktake.status)
+        .bind(&stocktake.note)
+        .bind(stocktake.applied)
+        .bind(stocktake.created_at)
+        .bind(stocktake.finished_at)
+        .execute(&mut **tx)
+        .await?
+        .last_insert_rowid();
+        stocktake_ids.insert(stocktake.id, id);
+        counts.stocktakes += 1;
+    }
+    for item in &archive.stocktake_items {
+        let Some(stocktake_id) = stocktake_ids.get(&item.stocktake_id) else {
+            continue;
+        };
+        query(
+            r#"
+            INSERT INTO stocktake_items
+                (stocktake_id, product_id, product_code, product_name, unit_price, expected_stock, counted_stock, adjustment)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(stocktake_id)
+        .bind(map_product(item.product_id))
+        .bind(&item.product_code)
+        .bind(&item.product_name)
+        .bind(item.unit_price)
+        .bind(item.expected_stock)
+        .bind(item.counted_stock)
+        .bind(item.adjustment)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok((event_id, counts))
+}
@@ -0,0 +1,445 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, SqlitePool};
+use std::collections::HashMap;
+
+use crate::{
+    api::{
+        event::event_exists,
+        guard::check_global_permission,
+        stats::{parse_event_ids, round4},
+    },
+    state::AppState,
+    utils::security::Claims,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 单场次：常一起买的商品组合
+        .route(
+            "/events/:event_id/stats/associations",
+            get(get_event_associations),
+        )
+        // 管理员/全场次摊主：跨场次汇总
+        .route("/stats/associations", get(get_global_associations))
+}
+
+// ==========================================
+// DTOs (数据传输对象)
+// ==========================================
+// 关联分析 (Market Basket)：每个未取消的订单视为一个购物篮，统计商品两两、三三组合。
+//   support    = 含该组合的订单数 / 订单总数
+//   confidence = 含该组合的订单数 / 含前项的订单数 (买了 A 的订单中有多少也买了 B)
+//   lift       = confidence / 后项的 support；> 1 表示一起购买的概率高于随机
+// 商品按编号对齐 (同款商品在不同场次合并)，无编号的已删除商品按名称对齐。
+
+// 阈值参数单独作为一个 Query 提取，两个接口共用
+#[derive(Deserialize)]
+struct Thresholds {
+    min_support: Option<f64>,    // 默认 0.01
+    min_confidence: Option<f64>, // 默认 0.2
+    min_lift: Option<f64>,       // 默认 1.0
+    min_orders: Option<i64>,     // 组合至少出现的订单数，默认 2
+    max_size: Option<usize>,     // 2 = 只统计两两组合；3 = 同时统计三件组合 (默认)
+    top: Option<usize>,          // 每类组合最多返回条数，默认 50，最多 500
+}
+
+#[derive(Deserialize)]
+struct EventAssociationQuery {
+    session_id: Option<i64>, // 只统计某个营业日
+}
+
+#[derive(Deserialize)]
+struct GlobalAssociationQuery {
+    event_ids: Option<String>,  // 逗号分隔；默认全部场次 (不含回收站)
+    start_date: Option<String>, // 按展会日期筛选 (YYYY-MM-DD)
+    end_date: Option<String>,
+}
+
+// 校验后的阈值，随结果一并返回
+#[derive(Serialize, Clone, Copy)]
+struct Settings {
+    min_support: f64,
+    min_confidence: f64,
+    min_lift: f64,
+    min_orders: i64,
+    max_size: usize,
+    top: usize,
+}
+
+#[derive(Serialize)]
+struct ProductRef {
+    product_key: String,
+    name: String,
+    orders: i64, // 含该商品的订单数
+    support: f64,
+}
+
+// 方向性规则：买了 antecedent 的顾客也会买 consequent
+#[derive(Serialize)]
+struct AssociationRule {
+    antecedent: Vec<String>, // product_key
+    consequent: String,
+    confidence: f64,
+    lift: f64,
+}
+
+#[derive(Serialize)]
+struct Itemset {
+    items: Vec<ProductRef>,
+    orders: i64,
+    support: f64,
+    // 组合整体的提升度 = support(组合) / 各商品 support 之积
+    lift: f64,
+    rules: Vec<AssociationRule>, // 满足置信度与提升度阈值的规则，按置信度降序
+}
+
+#[derive(Serialize)]
+struct AssociationReport {
+    orders: i64,            // 参与统计的订单数
+    multi_item_orders: i64, // 含两种及以上商品的订单数
+    products: usize,        // 出现过的商品种数
+    thresholds: Settings,
+    pairs: Vec<Itemset>,
+    triples: Vec<Itemset>,
+}
+
+// ==========================================
+// 1. 单场次关联分析
+// ==========================================
+async fn get_event_associations(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+    Query(params): Query<EventAssociationQuery>,
+    Query(thresholds): Query<Thresholds>,
+) -> Response {
+    if let Err(e) = check_read_permission(&claims, event_id) {
+        return e.into_response();
+    }
+    let settings = match validate(&thresholds) {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
+
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    let mut clause = String::from("o.event_id = ?");
+    let mut sql_params = vec![event_id.to_string()];
+    if let Some(session_id) = params.session_id {
+        clause.push_str(" AND o.session_id = ?");
+        sql_params.push(session_id.to_string());
+    }
+
+    match analyze(&state.db, &clause, &sql_params, settings).await {
+        Ok(report) => Json(report).into_response(),
+        Err(resp) => resp,
+    }
+}
+
+// ==========================================
+// 2. 跨场次关联分析
+// ==========================================
+async fn get_global_associations(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(params): Query<GlobalAssociationQuery>,
+    Query(thresholds): Query<Thresholds>,
+) -> Response {
+    if let Err(e) = check_global_permission(&claims) {
+        return e.into_response();
+    }
+    let settings = match validate(&thresholds) {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
+
+    let mut clause = String::from("o.event_id IN (SELECT id FROM events WHERE deleted_at IS NULL");
+    let mut sql_params: Vec<String> = Vec::new();
+    if let Some(raw) = params.event_ids.as_deref().filter(|s| !s.trim().is_empty()) {
+        let ids = match parse_event_ids(raw) {
+            Ok(ids) => ids,
+            Err(msg) => return bad_request(msg),
+        };
+        clause.push_str(&format!(
+            " AND id IN ({})",
+            ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+        ));
+        sql_params.extend(ids.iter().map(|id| id.to_string()));
+    }
+    if let Some(ref start) = params.start_date {
+        clause.push_str(" AND event_date >= ?");
+        sql_params.push(start.clone());
+    }
+    if let Some(ref end) = params.end_date {
+        clause.push_str(" AND event_date <= ?");
+        sql_params.push(end.clone());
+    }
+    clause.push(')');
+
+    match analyze(&state.db, &clause, &sql_params, settings).await {
+        Ok(report) => Json(report).into_response(),
+        Err(resp) => resp,
+    }
+}
+
+// ==========================================
+// 数据汇总
+// ==========================================
+
+#[derive(FromRow)]
+struct BasketLine {
+    order_id: i64,
+    product_key: String,
+    product_name: String,
+}
+
+fn validate(params: &Thresholds) -> Result<Settings, Response> {
+    let settings = Settings {
+        min_support: params.min_support.unwrap_or(0.01),
+        min_confidence: params.min_confidence.unwrap_or(0.2),
+        min_lift: params.min_lift.unwrap_or(1.0),
+        min_orders: params.min_orders.unwrap_or(2),
+        max_size: params.max_size.unwrap_or(3),
+        top: params.top.unwrap_or(50).clamp(1, 500),
+    };
+    if !(0.0..=1.0).contains(&settings.min_support) {
+        return Err(bad_request(
+            "min_support must be between 0 and 1".to_string(),
+        ));
+    }
+    if !(0.0..=1.0).contains(&settings.min_confidence) {
+        return Err(bad_request(
+            "min_confidence must be between 0 and 1".to_string(),
+        ));
+    }
+    if !settings.min_lift.is_finite() || settings.min_lift < 0.0 {
+        return Err(bad_request(
+            "min_lift must be a non-negative number".to_string(),
+        ));
+    }
+    if settings.min_orders < 1 {
+        return Err(bad_request("min_orders must be at least 1".to_string()));
+    }
+    if !(2..=3).contains(&settings.max_size) {
+        return Err(bad_request("max_size must be 2 or 3".to_string()));
+    }
+    Ok(settings)
+}
+
+// clause 为限定订单范围的条件 (引用别名 o)，params 按顺序绑定
+async fn analyze(
+    db: &SqlitePool,
+    clause: &str,
+    params: &[String],
+    settings: Settings,
+) -> Result<AssociationReport, Response> {
+    let db_error = |e: sqlx::Error| {
+        eprintln!("Association query error: {:?}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+    };
+
+    // 1. 每单的商品集合 (同一商品多件只算一次)
+    let lines_sql = format!(
+        r#"
+        SELECT oi.order_id,
+               COALESCE(mp.product_code, p.product_code, oi.product_name) AS product_key,
+               MAX(COALESCE(mp.name, oi.product_name)) AS product_name
+        FROM order_items oi
+        JOIN orders o ON oi.order_id = o.id
+        LEFT JOIN products p ON oi.product_id = p.id
+        LEFT JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE o.status != 'cancelled' AND {clause}
+        GROUP BY oi.order_id, product_key
+        ORDER BY oi.order_id ASC
+        "#
+    );
+    let mut q = sqlx::query_as::<_, BasketLine>(&lines_sql);
+    for param in params {
+        q = q.bind(param);
+    }
+    let lines = q.fetch_all(db).await.map_err(db_error)?;
+
+    // 2. 商品编号 → 下标；订单 → 商品下标列表
+    let mut keys: Vec<String> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut baskets: Vec<Vec<usize>> = Vec::new();
+    let mut current_order = None;
+    for line in lines {
+        let item = *index.entry(line.product_key.clone()).or_insert_with(|| {
+            keys.push(line.product_key);
+            names.push(line.product_name);
+            keys.len() - 1
+        });
+        if current_order != Some(line.order_id) {
+            current_order = Some(line.order_id);
+            baskets.push(Vec::new());
+        }
+        if let Some(basket) = baskets.last_mut() {
+            basket.push(item);
+        }
+    }
+
+    let total = baskets.len() as i64;
+    let multi_item_orders = baskets.iter().filter(|b| b.len() > 1).count() as i64;
+
+    // 组合需同时满足最小订单数与最小支持度
+    let min_count = settings
+        .min_orders
+        .max((settings.min_support * total as f64).ceil() as i64);
+
+    // 3. 单品计数，只保留频繁商品 (Apriori 剪枝)
+    let mut item_counts = vec![0i64; keys.len()];
+    for basket in &baskets {
+        for &item in basket {
+            item_counts[item] += 1;
+        }
+    }
+    for basket in baskets.iter_mut() {
+        basket.retain(|&item| item_counts[item] >= min_count);
+        basket.sort_unstable();
+    }
+
+    // 4. 两两组合计数
+    let mut pair_counts: HashMap<(usize, usize), i64> = HashMap::new();
+    for basket in &baskets {
+        for (i, &a) in basket.iter().enumerate() {
+            for &b in &basket[i + 1..] {
+                *pair_counts.entry((a, b)).or_insert(0) += 1;
+            }
+        }
+    }
+    pair_counts.retain(|_, count| *count >= min_count);
+
+    // 5. 三件组合计数：三个两两组合都频繁时才统计
+    let mut triple_counts: HashMap<(usize, usize, usize), i64> = HashMap::new();
+    if settings.max_size >= 3 {
+        for basket in &baskets {
+            for (i, &a) in basket.iter().enumerate() {
+                for (j, &b) in basket.iter().enumerate().skip(i + 1) {
+                    if !pair_counts.contains_key(&(a, b)) {
+                        continue;
+                    }
+                    for &c in &basket[j + 1..] {
+                        if pair_counts.contains_key(&(a, c)) && pair_counts.contains_key(&(b, c)) {
+                            *triple_counts.entry((a, b, c)).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+        triple_counts.retain(|_, count| *count >= min_count);
+    }
+
+    // 6. 计算指标并按阈值筛选
+    let support = |count: i64| count as f64 / total as f64;
+    let pair_count = |a: usize, b: usize| pair_counts.get(&(a.min(b), a.max(b))).copied();
+    let product_ref = |item: usize| ProductRef {
+        product_key: keys[item].clone(),
+        name: names[item].clone(),
+        orders: item_counts[item],
+        support: round4(support(item_counts[item])),
+    };
+    let rule = |antecedent: Vec<usize>, antecedent_count: i64, consequent: usize, count: i64| {
+        let confidence = count as f64 / antecedent_count as f64;
+        AssociationRule {
+            antecedent: antecedent.iter().map(|&i| keys[i].clone()).collect(),
+            consequent: keys[consequent].clone(),
+            confidence: round4(confidence),
+            lift: round4(confidence / support(item_counts[consequent])),
+        }
+    };
+    let finish = |items: Vec<usize>, count: i64, mut rules: Vec<AssociationRule>| {
+        rules.retain(|r| r.confidence >= settings.min_confidence && r.lift >= settings.min_lift);
+        if rules.is_empty() {
+            return None;
+        }
+        rules.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        let expected: f64 = items.iter().map(|&i| support(item_counts[i])).product();
+        Some(Itemset {
+            orders: count,
+            support: round4(support(count)),
+            lift: round4(support(count) / expected),
+            items: items.into_iter().map(product_ref).collect(),
+            rules,
+        })
+    };
+
+    let mut pairs: Vec<Itemset> = pair_counts
+        .iter()
+        .filter_map(|(&(a, b), &count)| {
+            let rules = vec![
+                rule(vec![a], item_counts[a], b, count),
+                rule(vec![b], item_counts[b], a, count),
+            ];
+            finish(vec![a, b], count, rules)
+        })
+        .collect();
+
+    let mut triples: Vec<Itemset> = triple_counts
+        .iter()
+        .filter_map(|(&(a, b, c), &count)| {
+            // 两件 → 第三件；前项的订单数即对应两两组合的订单数
+            let rules = [(a, b, c), (a, c, b), (b, c, a)]
+                .into_iter()
+                .filter_map(|(x, y, z)| {
+                    pair_count(x, y).map(|antecedent| rule(vec![x, y], antecedent, z, count))
+                })
+                .collect();
+            finish(vec![a, b, c], count, rules)
+        })
+        .collect();
+
+    for list in [&mut pairs, &mut triples] {
+        // 订单数多的组合优先，其次提升度；同分时按商品编号保证顺序稳定
+        list.sort_by(|x, y| {
+            y.orders
+                .cmp(&x.orders)
+                .then(y.lift.total_cmp(&x.lift))
+                .then_with(|| {
+                    let key = |s: &Itemset| {
+                        s.items
+                            .iter()
+                            .map(|i| i.product_key.clone())
+                            .collect::<Vec<_>>()
+                    };
+                    key(x).cmp(&key(y))
+                })
+        });
+        list.truncate(settings.top);
+    }
+
+    Ok(AssociationReport {
+        orders: total,
+        multi_item_orders,
+        products: keys.len(),
+        thresholds: settings,
+        pairs,
+        triples,
+    })
+}
+
+fn bad_request(msg: String) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response()
+}
+
+fn check_read_permission(claims: &Claims, event_id: i64) -> Result<(), (StatusCode, &'static str)> {
+    if claims.role == "admin"
+        || (claims.role == "vendor"
+            && (claims.access == "all" || claims.event_id == Some(event_id)))
+    {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "Access denied"))
+    }
+}
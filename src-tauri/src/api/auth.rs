@@ -11,6 +11,7 @@ use axum::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    api::event_settings::load_settings,
     state::AppState,
     utils::security::{self, AuthError},
 };
@@ -165,8 +166,20 @@ async fn login_handler(
             // );
 
             if security::verify_password(&payload.password, &stored_hash) {
-                let token = security::create_jwt("admin", "all", None, &state.jwt_secret)?;
-                return Ok(build_success_response("admin", "all", None, token));
+                let token = security::create_jwt(
+                    "admin",
+                    "all",
+                    None,
+                    security::DEFAULT_TOKEN_HOURS,
+                    &state.jwt_secret,
+                )?;
+                return Ok(build_success_response(
+                    "admin",
+                    "all",
+                    None,
+                    token,
+                    security::DEFAULT_TOKEN_HOURS,
+                ));
             }
         }
         "vendor" => {
@@ -194,8 +207,20 @@ async fn login_handler(
                 //     security::verify_password(&payload.password, &hash)
                 // );
                 if security::verify_password(&payload.password, &hash) {
-                    let token = security::create_jwt("vendor", "all", None, &state.jwt_secret)?;
-                    return Ok(build_success_response("vendor", "all", None, token));
+                    let token = security::create_jwt(
+                        "vendor",
+                        "all",
+                        None,
+                        security::DEFAULT_TOKEN_HOURS,
+                        &state.jwt_secret,
+                    )?;
+                    return Ok(build_success_response(
+                        "vendor",
+                        "all",
+                        None,
+                        token,
+                        security::DEFAULT_TOKEN_HOURS,
+                    ));
                 }
             }
 
@@ -213,8 +238,20 @@ async fn login_handler(
                 //     security::verify_password(&payload.password, &hash)
                 // );
                 if security::verify_password(&payload.password, &hash) {
-                    let token = security::create_jwt("vendor", "all", None, &state.jwt_secret)?;
-                    return Ok(build_success_response("vendor", "all", None, token));
+                    let token = security::create_jwt(
+                        "vendor",
+                        "all",
+                        None,
+                        security::DEFAULT_TOKEN_HOURS,
+                        &state.jwt_secret,
+                    )?;
+                    return Ok(build_success_response(
+                        "vendor",
+                        "all",
+                        None,
+                        token,
+                        security::DEFAULT_TOKEN_HOURS,
+                    ));
                 }
             }
 
@@ -222,7 +259,7 @@ async fn login_handler(
             if let Some(eid) = event_id {
                 // [修复] 验证 event 是否存在 ✓
                 let event_exists: Option<(i64,)> =
-                    sqlx::query_as("SELECT id FROM events WHERE id = ?")
+                    sqlx::query_as("SELECT id FROM events WHERE id = ? AND deleted_at IS NULL")
                         .bind(eid)
                         .fetch_optional(&state.db)
                         .await
@@ -243,9 +280,22 @@ async fn login_handler(
                 // 注意：vendor_password 在数据库里是 nullable 的
                 if let Some((Some(event_pass_hash),)) = event_row {
                     if security::verify_password(&payload.password, &event_pass_hash) {
-                        let token =
-                            security::create_jwt("vendor", "event", Some(eid), &state.jwt_secret)?;
-                        return Ok(build_success_response("vendor", "event", Some(eid), token));
+                        // 有效期按场次设置 (默认 24 小时)
+                        let ttl = load_settings(&state.db, eid).await.vendor_token_hours;
+                        let token = security::create_jwt(
+                            "vendor",
+                            "event",
+                            Some(eid),
+                            ttl,
+                            &state.jwt_secret,
+                        )?;
+                        return Ok(build_success_response(
+                            "vendor",
+                            "event",
+                            Some(eid),
+                            token,
+                            ttl,
+                        ));
                     }
                 }
             }
@@ -263,6 +313,7 @@ fn build_success_response(
     access: &str,
     event_id: Option<i64>,
     token: String,
+    ttl_hours: i64,
 ) -> Response {
     let body = LoginResponse {
         message: "Login successful".into(),
@@ -279,8 +330,9 @@ fn build_success_response(
     // 3. 移除 Secure: 因为你是局域网 HTTP，加上 Secure 浏览器反而会拒收 Cookie。
     // 4. HttpOnly: 防止 JS 读取，安全。
     let cookie_str = format!(
-        "access_token_cookie={}; HttpOnly; Path=/; SameSite=Lax; Max-Age=86400",
-        token
+        "access_token_cookie={}; HttpOnly; Path=/; SameSite=Lax; Max-Age={}",
+        token,
+        ttl_hours * 3600
     );
     // 注意：上面去掉了 "; Secure"
 
@@ -0,0 +1,666 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use chrono::Local;
+use rust_xlsxwriter::{Format, FormatAlign, Workbook};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, SqlitePool};
+use std::collections::HashMap;
+
+use crate::{
+    api::{
+        event_settings::load_settings,
+        guard::check_global_permission,
+        stats::{parse_event_ids, round2, round4, ReportFormats},
+    },
+    db::models::{Event, EventStatus},
+    state::AppState,
+    utils::security::Claims,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 管理员/全场次摊主：跨场次对比
+        .route("/stats/compare", get(get_comparison))
+        .route("/stats/compare/download", get(download_comparison))
+}
+
+// ==========================================
+// DTOs (数据传输对象)
+// ==========================================
+
+#[derive(Deserialize)]
+struct CompareQuery {
+    event_ids: Option<String>,  // 逗号分隔，如 "1,3,5"；默认全部场次
+    start_date: Option<String>, // 按展会日期筛选 (YYYY-MM-DD)
+    end_date: Option<String>,
+    category: Option<String>, // 只统计该分类的商品
+}
+
+#[derive(Serialize)]
+struct EventComparison {
+    event_id: i64,
+    name: String,
+    date: String,
+    location: Option<String>,
+    status: EventStatus,
+    currency_symbol: String,
+    revenue: f64,
+    orders: i64,
+    units: i64,
+    average_basket: f64,       // 客单价 = 销售额 / 订单数
+    initial_stock: i64,        // 上架商品的初始库存合计
+    sell_through: Option<f64>, // 售罄率 = 销售量 / 初始库存；没有库存记录时为 null
+}
+
+#[derive(Serialize, Default)]
+struct ComparisonTotals {
+    revenue: f64,
+    orders: i64,
+    units: i64,
+    average_basket: f64,
+}
+
+// 商品在某个场次的表现；未上架且无销售时为 null
+#[derive(Serialize, Clone, Default)]
+struct ProductCell {
+    units: i64,
+    revenue: f64,
+    initial_stock: i64,
+    sell_through: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct ProductComparison {
+    product_code: String,
+    name: String,
+    category: Option<String>,
+    cells: Vec<Option<ProductCell>>, // 与 events 顺序一致
+    total_units: i64,
+    total_revenue: f64,
+    events_listed: usize, // 上架过的场次数
+    events_sold: usize,   // 有销售的场次数
+    // 各场次售罄率的平均值与最低值，用于判断是否稳定好卖
+    average_sell_through: Option<f64>,
+    min_sell_through: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct ComparisonReport {
+    events: Vec<EventComparison>,
+    totals: ComparisonTotals,
+    products: Vec<ProductComparison>,
+}
+
+// ==========================================
+// 1. 跨场次对比 (JSON)
+// ==========================================
+async fn get_comparison(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(params): Query<CompareQuery>,
+) -> impl IntoResponse {
+    if let Err(e) = check_global_permission(&claims) {
+        return e.into_response();
+    }
+    match build_report(&state.db, &params).await {
+        Ok(report) => Json(report).into_response(),
+        Err(resp) => resp,
+    }
+}
+
+// ==========================================
+// 2. 导出 Excel (场次对比 + 商品矩阵)
+// ==========================================
+async fn download_comparison(
+    State(state): State<AppState>,
+    claims: Claims,
+    Query(params): Query<CompareQuery>,
+) -> Response {
+    if let Err(e) = check_global_permission(&claims) {
+        return e.into_response();
+    }
+    let report = match build_report(&state.db, &params).await {
+        Ok(r) => r,
+        Err(resp) => return resp,
+    };
+
+    // 各场次货币符号一致时金额带符号，否则只显示数字
+    let symbol = match report.events.first() {
+        Some(first)
+            if report
+                .events
+                .iter()
+                .all(|e| e.currency_symbol == first.currency_symbol) =>
+        {
+            first.currency_symbol.clone()
+        }
+        _ => String::new(),
+    };
+    let formats = ReportFormats::new(&symbol);
+
+    let mut workbook = Workbook::new();
+    write_events_sheet(&mut workbook, &report, &params, &formats);
+    write_matrix_sheet(&mut workbook, &report, &formats);
+
+    match workbook.save_to_buffer() {
+        Ok(buf) => {
+            let disposition = format!(
+                "attachment; filename=\"event_comparison_{}.xlsx\"",
+                Local::now().format("%Y%m%d_%H%M")
+            );
+            (
+                [
+                    (
+                        header::CONTENT_TYPE,
+                        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+                    ),
+                    (header::CONTENT_DISPOSITION, disposition.as_str()),
+                ],
+                buf,
+            )
+                .into_response()
+        }
+        Err(e) => {
+            eprintln!("Excel generation error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to generate excel",
+            )
+                .into_response()
+        }
+    }
+}
+
+// ==========================================
+// 数据汇总
+// ==========================================
+
+#[derive(FromRow)]
+struct SalesRow {
+    event_id: i64,
+    product_key: String,
+    product_name: String,
+    category: Option<String>,
+    units: i64,
+    revenue: f64,
+}
+
+#[derive(FromRow)]
+struct StockRow {
+    event_id: i64,
+    product_key: String,
+    product_name: String,
+    category: Option<String>,
+    initial_stock: i64,
+}
+
+async fn build_report(
+    db: &SqlitePool,
+    params: &CompareQuery,
+) -> Result<ComparisonReport, Response> {
+    let bad_request =
+        |msg: String| (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response();
+    let db_error = |e: sqlx::Error| {
+        eprintln!("Event comparison query error: {:?}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+    };
+
+    // 1. 参与对比的场次 (不含回收站)
+    let mut sql = String::from("SELECT * FROM events WHERE deleted_at IS NULL");
+    let mut sql_params: Vec<String> = Vec::new();
+    if let Some(raw) = params.event_ids.as_deref().filter(|s| !s.trim().is_empty()) {
+        let ids = parse_event_ids(raw).map_err(bad_request)?;
+        sql.push_str(&format!(
+            " AND id IN ({})",
+            ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+        ));
+        sql_params.extend(ids.iter().map(|id| id.to_string()));
+    }
+    if let Some(ref start) = params.start_date {
+        sql.push_str(" AND event_date >= ?");
+        sql_params.push(start.clone());
+    }
+    if let Some(ref end) = params.end_date {
+        sql.push_str(" AND event_date <= ?");
+        sql_params.push(end.clone());
+    }
+    sql.push_str(" ORDER BY event_date ASC, id ASC");
+
+    let mut q = sqlx::query_as::<_, Event>(&sql);
+    for param in &sql_params {
+        q = q.bind(param);
+    }
+    let events: Vec<Event> = q.fetch_all(db).await.map_err(db_error)?;
+    if events.is_empty() {
+        return Ok(ComparisonReport {
+            events: Vec::new(),
+            totals: ComparisonTotals::default(),
+            products: Vec::new(),
+        });
+    }
+
+    let event_ids: Vec<i64> = events.iter().map(|e| e.id).collect();
+    let placeholders = event_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    // 分类筛选与趋势图一致：未填写分类的商品归为 "未分类"
+    let category_clause = if params.category.is_some() {
+        " AND COALESCE(NULLIF(mp.category, ''), '未分类') = ?"
+    } else {
+        ""
+    };
+
+    // 2. 各场次各商品的销量；商品按全局编号对齐，已删除的场次商品按名称对齐
+    let sales_sql = format!(
+        r#"
+        SELECT o.event_id,
+               COALESCE(mp.product_code, p.product_code, oi.product_name) AS product_key,
+               MAX(COALESCE(mp.name, oi.product_name)) AS product_name,
+               MAX(NULLIF(mp.category, '')) AS category,
+               SUM(oi.quantity) AS units,
+               SUM(oi.product_price * oi.quantity) AS revenue
+        FROM order_items oi
+        JOIN orders o ON oi.order_id = o.id
+        LEFT JOIN products p ON oi.product_id = p.id
+        LEFT JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE o.status != 'cancelled' AND o.event_id IN ({placeholders}){category_clause}
+        GROUP BY o.event_id, product_key
+        "#
+    );
+    let mut q = sqlx::query_as::<_, SalesRow>(&sales_sql);
+    for id in &event_ids {
+        q = q.bind(id);
+    }
+    if let Some(ref category) = params.category {
+        q = q.bind(category);
+    }
+    let sales = q.fetch_all(db).await.map_err(db_error)?;
+
+    // 3. 订单数 (包含符合筛选条件的明细的订单)
+    let orders_sql = format!(
+        r#"
+        SELECT o.event_id, COUNT(DISTINCT o.id)
+        FROM order_items oi
+        JOIN orders o ON oi.order_id = o.id
+        LEFT JOIN products p ON oi.product_id = p.id
+        LEFT JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE o.status != 'cancelled' AND o.event_id IN ({placeholders}){category_clause}
+        GROUP BY o.event_id
+        "#
+    );
+    let mut q = sqlx::query_as::<_, (i64, i64)>(&orders_sql);
+    for id in &event_ids {
+        q = q.bind(id);
+    }
+    if let Some(ref category) = params.category {
+        q = q.bind(category);
+    }
+    let orders: HashMap<i64, i64> = q
+        .fetch_all(db)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .collect();
+
+    // 4. 上架商品的初始库存
+    let stock_sql = format!(
+        r#"
+        SELECT p.event_id,
+               mp.product_code AS product_key,
+               MAX(mp.name) AS product_name,
+               MAX(NULLIF(mp.category, '')) AS category,
+               SUM(p.initial_stock) AS initial_stock
+        FROM products p
+        JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE p.event_id IN ({placeholders}){category_clause}
+        GROUP BY p.event_id, mp.product_code
+        "#
+    );
+    let mut q = sqlx::query_as::<_, StockRow>(&stock_sql);
+    for id in &event_ids {
+        q = q.bind(id);
+    }
+    if let Some(ref category) = params.category {
+        q = q.bind(category);
+    }
+    let stock = q.fetch_all(db).await.map_err(db_error)?;
+
+    // 5. 组装商品矩阵
+    let column: HashMap<i64, usize> = event_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (*id, i))
+        .collect();
+    let mut products: Vec<ProductComparison> = Vec::new();
+    let mut row_of: HashMap<String, usize> = HashMap::new();
+    let mut cell_of = |key: &str, name: &str, category: &Option<String>, event_id: i64| {
+        let row = *row_of.entry(key.to_string()).or_insert_with(|| {
+            products.push(ProductComparison {
+                product_code: key.to_string(),
+                name: name.to_string(),
+                category: category.clone(),
+                cells: vec![None; event_ids.len()],
+                total_units: 0,
+                total_revenue: 0.0,
+                events_listed: 0,
+                events_sold: 0,
+                average_sell_through: None,
+                min_sell_through: None,
+            });
+            products.len() - 1
+        });
+        (row, column[&event_id])
+    };
+    let mut cells: Vec<(usize, usize, ProductCell)> = Vec::new();
+    for s in &stock {
+        let (row, col) = cell_of(&s.product_key, &s.product_name, &s.category, s.event_id);
+        cells.push((
+            row,
+            col,
+            ProductCell {
+                initial_stock: s.initial_stock,
+                ..Default::default()
+            },
+        ));
+    }
+    for s in &sales {
+        let (row, col) = cell_of(&s.product_key, &s.product_name, &s.category, s.event_id);
+        cells.push((
+            row,
+            col,
+            ProductCell {
+                units: s.units,
+                revenue: s.revenue,
+                ..Default::default()
+            },
+        ));
+    }
+    for (row, col, cell) in cells {
+        let target = products[row].cells[col].get_or_insert_with(ProductCell::default);
+        target.units += cell.units;
+        target.revenue += cell.revenue;
+        target.initial_stock += cell.initial_stock;
+    }
+
+    for product in products.iter_mut() {
+        let mut rates = Vec::new();
+        for cell in product.cells.iter_mut().flatten() {
+            cell.revenue = round2(cell.revenue);
+            cell.sell_through = sell_through(cell.units, cell.initial_stock);
+            product.total_units += cell.units;
+            product.total_revenue += cell.revenue;
+            if cell.initial_stock > 0 {
+                product.events_listed += 1;
+            }
+            if cell.units > 0 {
+                product.events_sold += 1;
+            }
+            rates.extend(cell.sell_through);
+        }
+        product.total_revenue = round2(product.total_revenue);
+        if !rates.is_empty() {
+            product.average_sell_through =
+                Some(round4(rates.iter().sum::<f64>() / rates.len() as f64));
+            product.min_sell_through = rates.iter().copied().reduce(f64::min).map(round4);
+        }
+    }
+    products.sort_by(|a, b| {
+        b.total_revenue
+            .total_cmp(&a.total_revenue)
+            .then_with(|| a.product_code.cmp(&b.product_code))
+    });
+
+    // 6. 场次汇总
+    let mut totals = ComparisonTotals::default();
+    let mut rows = Vec::with_capacity(events.len());
+    for event in events {
+        let col = column[&event.id];
+        let (mut revenue, mut units, mut initial_stock) = (0.0, 0, 0);
+        for cell in products.iter().filter_map(|p| p.cells[col].as_ref()) {
+            revenue += cell.revenue;
+            units += cell.units;
+            initial_stock += cell.initial_stock;
+        }
+        let order_count = orders.get(&event.id).copied().unwrap_or(0);
+        totals.revenue += revenue;
+        totals.orders += order_count;
+        totals.units += units;
+        rows.push(EventComparison {
+            currency_symbol: load_settings(db, event.id).await.currency_symbol,
+            event_id: event.id,
+            name: event.name,
+            date: event.event_date,
+            location: event.location,
+            status: event.status,
+            revenue: round2(revenue),
+            orders: order_count,
+            units,
+            average_basket: average(revenue, order_count),
+            initial_stock,
+            sell_through: sell_through(units, initial_stock),
+        });
+    }
+    totals.average_basket = average(totals.revenue, totals.orders);
+    totals.revenue = round2(totals.revenue);
+
+    Ok(ComparisonReport {
+        events: rows,
+        totals,
+        products,
+    })
+}
+
+fn sell_through(units: i64, initial_stock: i64) -> Option<f64> {
+    (initial_stock > 0).then(|| round4(units as f64 / initial_stock as f64))
+}
+
+fn average(revenue: f64, orders: i64) -> f64 {
+    if orders > 0 {
+        round2(revenue / orders as f64)
+    } else {
+        0.0
+    }
+}
+
+// ==========================================
+// Excel 工作表
+// ==========================================
+
+fn write_events_sheet(
+    workbook: &mut Workbook,
+    report: &ComparisonReport,
+    params: &CompareQuery,
+    formats: &ReportFormats,
+) {
+    let worksheet = workbook.add_worksheet();
+    let _ = worksheet.set_name("场次对比");
+    for (col, width) in [25, 12, 18, 14, 10, 10, 12, 12, 10].iter().enumerate() {
+        let _ = worksheet.set_column_width(col as u16, *width);
+    }
+    let _ = worksheet.merge_range(0, 0, 0, 8, "跨场次销售对比", &formats.title);
+
+    let mut filters = Vec::new();
+    if params.start_date.is_some() || params.end_date.is_some() {
+        filters.push(format!(
+            "日期: {} ~ {}",
+            params.start_date.as_deref().unwrap_or(""),
+            params.end_date.as_deref().unwrap_or("")
+        ));
+    }
+    if let Some(category) = &params.category {
+        filters.push(format!("分类: {}", category));
+    }
+    filters.push(format!(
+        "生成时间: {}",
+        Local::now().format("%Y-%m-%d %H:%M")
+    ));
+    let _ = worksheet.merge_range(
+        1,
+        0,
+        1,
+        8,
+        &filters.join("    "),
+        &Format::new().set_align(FormatAlign::Right),
+    );
+
+    let headers = [
+        "场次",
+        "日期",
+        "地点",
+        "销售额",
+        "订单数",
+        "销售量",
+        "客单价",
+        "初始库存",
+        "售罄率",
+    ];
+    for (col, text) in headers.iter().enumerate() {
+        let _ = worksheet.write_string_with_format(2, col as u16, *text, &formats.header);
+    }
+    let _ = worksheet.set_freeze_panes(3, 1);
+
+    let mut row = 3;
+    for event in &report.events {
+        let _ = worksheet.write_string_with_format(row, 0, &event.name, &formats.text);
+        let _ = worksheet.write_string_with_format(row, 1, &event.date, &formats.center);
+        let _ = worksheet.write_string_with_format(
+            row,
+            2,
+            event.location.as_deref().unwrap_or(""),
+            &formats.text,
+        );
+        let _ = worksheet.write_number_with_format(row, 3, event.revenue, &formats.currency);
+        let _ = worksheet.write_number_with_format(row, 4, event.orders as f64, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 5, event.units as f64, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 6, event.average_basket, &formats.currency);
+        let _ =
+            worksheet.write_number_with_format(row, 7, event.initial_stock as f64, &formats.center);
+        match event.sell_through {
+            Some(rate) => {
+                let _ = worksheet.write_number_with_format(row, 8, rate, &formats.percent);
+            }
+            None => {
+                let _ = worksheet.write_blank(row, 8, &formats.percent);
+            }
+        }
+        row += 1;
+    }
+
+    let _ = worksheet.write_string_with_format(row, 0, "合计", &formats.total_row);
+    for col in 1..=2 {
+        let _ = worksheet.write_blank(row, col, &formats.total_row);
+    }
+    let _ =
+        worksheet.write_number_with_format(row, 3, report.totals.revenue, &formats.total_currency);
+    let _ =
+        worksheet.write_number_with_format(row, 4, report.totals.orders as f64, &formats.total_row);
+    let _ =
+        worksheet.write_number_with_format(row, 5, report.totals.units as f64, &formats.total_row);
+    let _ = worksheet.write_number_with_format(
+        row,
+        6,
+        report.totals.average_basket,
+        &formats.total_currency,
+    );
+    for col in 7..=8 {
+        let _ = worksheet.write_blank(row, col, &formats.total_row);
+    }
+}
+
+// 商品矩阵：每个场次占 "销售量 / 售罄率" 两列
+fn write_matrix_sheet(workbook: &mut Workbook, report: &ComparisonReport, formats: &ReportFormats) {
+    let worksheet = workbook.add_worksheet();
+    let _ = worksheet.set_name("商品矩阵");
+    let fixed = ["制品编号", "制品名", "分类"];
+    let first_event_col = fixed.len() as u16;
+    let summary_col = first_event_col + 2 * report.events.len() as u16;
+
+    for (col, width) in [15, 25, 10].iter().enumerate() {
+        let _ = worksheet.set_column_width(col as u16, *width);
+    }
+    for (col, text) in fixed.iter().enumerate() {
+        let _ = worksheet.merge_range(0, col as u16, 1, col as u16, text, &formats.header);
+    }
+    for (i, event) in report.events.iter().enumerate() {
+        let col = first_event_col + 2 * i as u16;
+        let title = format!("{} ({})", event.name, event.date);
+        let _ = worksheet.merge_range(0, col, 0, col + 1, &title, &formats.header);
+        let _ = worksheet.write_string_with_format(1, col, "销售量", &formats.header);
+        let _ = worksheet.write_string_with_format(1, col + 1, "售罄率", &formats.header);
+        let _ = worksheet.set_column_width(col, 10);
+        let _ = worksheet.set_column_width(col + 1, 10);
+    }
+    let summary_headers = ["总销量", "总销售额", "上架场次", "平均售罄率", "最低售罄率"];
+    for (i, text) in summary_headers.iter().enumerate() {
+        let col = summary_col + i as u16;
+        let _ = worksheet.merge_range(0, col, 1, col, text, &formats.header);
+        let _ = worksheet.set_column_width(col, 12);
+    }
+    let _ = worksheet.set_freeze_panes(2, 2);
+
+    let write_rate =
+        |ws: &mut rust_xlsxwriter::Worksheet, row: u32, col: u16, rate: Option<f64>| {
+            let _ = match rate {
+                Some(r) => ws.write_number_with_format(row, col, r, &formats.percent),
+                None => ws.write_blank(row, col, &formats.percent),
+            };
+        };
+
+    for (row, product) in (2..).zip(report.products.iter()) {
+        let _ = worksheet.write_string_with_format(row, 0, &product.product_code, &formats.center);
+        let _ = worksheet.write_string_with_format(row, 1, &product.name, &formats.text);
+        let _ = worksheet.write_string_with_format(
+            row,
+            2,
+            product.category.as_deref().unwrap_or(""),
+            &formats.center,
+        );
+        for (i, cell) in product.cells.iter().enumerate() {
+            let col = first_event_col + 2 * i as u16;
+            match cell {
+                Some(cell) => {
+                    let _ = worksheet.write_number_with_format(
+                        row,
+                        col,
+                        cell.units as f64,
+                        &formats.center,
+                    );
+                    write_rate(worksheet, row, col + 1, cell.sell_through);
+                }
+                None => {
+                    let _ = worksheet.write_blank(row, col, &formats.center);
+                    let _ = worksheet.write_blank(row, col + 1, &formats.percent);
+                }
+            }
+        }
+        let _ = worksheet.write_number_with_format(
+            row,
+            summary_col,
+            product.total_units as f64,
+            &formats.center,
+        );
+        let _ = worksheet.write_number_with_format(
+            row,
+            summary_col + 1,
+            product.total_revenue,
+            &formats.currency,
+        );
+        let _ = worksheet.write_number_with_format(
+            row,
+            summary_col + 2,
+            product.events_listed as f64,
+            &formats.center,
+        );
+        write_rate(
+            worksheet,
+            row,
+            summary_col + 3,
+            product.average_sell_through,
+        );
+        write_rate(worksheet, row, summary_col + 4, product.min_sell_through);
+    }
+}
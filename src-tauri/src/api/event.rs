@@ -1,21 +1,22 @@
 use axum::{
     extract::{Multipart, Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Json},
+    response::{IntoResponse, Json, Response},
     routing::{delete, get, post, put},
     Router,
 };
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use sqlx::{query, query_as};
+use sqlx::{query, query_as, SqlitePool};
 
 use crate::{
-    api::guard::AdminOnly,
-    db::models::Event,
+    api::{guard::AdminOnly, payment, session},
+    db::models::{Event, EventStatus, PaymentMethod},
     state::AppState,
     utils::{
-        file::{delete_file, save_upload_file},
-        security::hash_password,
+        file::{copy_file, delete_file, save_upload_file, upload_url},
+        security::{hash_password, Claims},
     },
 };
 
@@ -28,7 +29,13 @@ pub fn router() -> Router<AppState> {
         .route("/", post(create_event))
         .route("/:id", post(update_event).put(update_event))
         .route("/:id/status", put(update_status))
-        .route("/:id", delete(delete_event))
+        .route("/:id/status/history", get(list_status_changes))
+        .route("/:id", delete(delete_event)) // 移入回收站
+        .route("/:id/clone", post(clone_event)) // 复制场次及其商品阵容
+        // 回收站
+        .route("/trash", get(list_trash).delete(empty_trash))
+        .route("/:id/restore", post(restore_event))
+        .route("/:id/purge", delete(purge_event))
 }
 
 // ==========================================
@@ -43,27 +50,27 @@ struct ListEventsQuery {
 
 // 2. 用于 API 响应的结构体 (解决 qrcode_url 问题，避免 flatten 导致的序列化问题)
 #[derive(Serialize)]
-struct EventResponse {
+pub(crate) struct EventResponse {
     pub id: i64,
     pub name: String,
     #[serde(rename = "date")]
     pub event_date: String,
     pub location: Option<String>,
-    pub status: String,
+    pub status: EventStatus,
     pub qrcode_url: Option<String>,
+    pub soldout_policy: String,
+    pub version: i64,
+    pub started_at: Option<NaiveDateTime>,
+    pub ended_at: Option<NaiveDateTime>,
+    // 按显示顺序排列；qrcode_url 即第一个带图的收款方式
+    pub payment_methods: Vec<PaymentMethod>,
 }
 
 impl EventResponse {
     // 转换函数：将 DB 模型转换为 API 响应模型
-    fn from_model(event: Event) -> Self {
+    pub(crate) fn from_model(event: Event) -> Self {
         // 数据库存的是 "events/xxx.jpg"，转换成 "/uploads/events/xxx.jpg"
-        let qrcode_url = event.payment_qr_code_path.as_ref().map(|path| {
-            if path.starts_with("/uploads/") {
-                path.clone()
-            } else {
-                format!("/uploads/{}", path)
-            }
-        });
+        let qrcode_url = event.payment_qr_code_path.as_deref().map(upload_url);
 
         Self {
             id: event.id,
@@ -72,10 +79,29 @@ impl EventResponse {
             location: event.location,
             status: event.status,
             qrcode_url,
+            soldout_policy: event.soldout_policy,
+            version: event.version,
+            started_at: event.started_at,
+            ended_at: event.ended_at,
+            payment_methods: Vec::new(),
+        }
+    }
+
+    // 转换并附带收款方式
+    pub(crate) async fn load(db: &SqlitePool, event: Event) -> Self {
+        let payment_methods = payment::fetch_methods(db, event.id).await;
+        Self {
+            payment_methods,
+            ..Self::from_model(event)
         }
     }
 }
 
+// 售罄展示策略只允许这三个值
+fn is_valid_soldout_policy(policy: &str) -> bool {
+    matches!(policy, "show" | "bottom" | "hide")
+}
+
 // ==========================================
 // 1. 获取漫展列表 (Public) [已修复过滤]
 // ==========================================
@@ -86,21 +112,35 @@ async fn list_events(
     // 根据是否传了 status 决定 SQL
     // 【关键】所有情况下都使用 unwrap_or_default() 确保返回空数组而不是 null
     let events: Vec<Event> = if let Some(status) = params.status {
-        query_as::<_, Event>("SELECT * FROM events WHERE status = ? ORDER BY event_date DESC")
-            .bind(status)
-            .fetch_all(&state.db)
-            .await
-            .unwrap_or_default()
+        query_as::<_, Event>(
+            "SELECT * FROM events WHERE status = ? AND deleted_at IS NULL ORDER BY event_date DESC",
+        )
+        .bind(status)
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default()
     } else {
-        query_as::<_, Event>("SELECT * FROM events ORDER BY event_date DESC")
-            .fetch_all(&state.db)
-            .await
-            .unwrap_or_default()
+        query_as::<_, Event>(
+            "SELECT * FROM events WHERE deleted_at IS NULL ORDER BY event_date DESC",
+        )
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default()
     };
 
     // [修复] 转换为包含 qrcode_url 的 Response 对象
     // 即使 events 为空，也会返回 [] (空数组) 而不是 null
-    let response: Vec<EventResponse> = events.into_iter().map(EventResponse::from_model).collect();
+    let mut methods = payment::methods_by_event(&state.db).await;
+    let response: Vec<EventResponse> = events
+        .into_iter()
+        .map(|event| {
+            let payment_methods = methods.remove(&event.id).unwrap_or_default();
+            EventResponse {
+                payment_methods,
+                ..EventResponse::from_model(event)
+            }
+        })
+        .collect();
 
     Json(response)
 }
@@ -109,16 +149,17 @@ async fn list_events(
 // 2. 获取单个漫展 (Public) [已修复响应]
 // ==========================================
 async fn get_event(State(state): State<AppState>, Path(id): Path<i64>) -> impl IntoResponse {
-    let event: Option<Event> = query_as::<_, Event>("SELECT * FROM events WHERE id = ?")
-        .bind(id)
-        .fetch_optional(&state.db)
-        .await
-        .unwrap_or(None);
+    let event: Option<Event> =
+        query_as::<_, Event>("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
 
     match event {
         // [修复] 转换响应结构
         Some(e) => {
-            let response = EventResponse::from_model(e);
+            let response = EventResponse::load(&state.db, e).await;
             (StatusCode::OK, Json(response)).into_response()
         }
         None => (
@@ -142,6 +183,7 @@ async fn create_event(
     let mut location = String::new();
     let mut vendor_password = None;
     let mut qr_code_path: Option<String> = None;
+    let mut soldout_policy = "show".to_string();
 
     while let Some(field) = multipart.next_field().await.unwrap_or(None) {
         let field_name = field.name().unwrap_or("").to_string();
@@ -169,6 +211,7 @@ async fn create_event(
                         vendor_password = Some(hash_password(&value));
                     }
                 }
+                "soldout_policy" => soldout_policy = value,
                 _ => {}
             }
         }
@@ -182,25 +225,52 @@ async fn create_event(
             .into_response();
     }
 
+    if !is_valid_soldout_policy(&soldout_policy) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Invalid soldout_policy. Must be one of: show, bottom, hide"})),
+        )
+            .into_response();
+    }
+
     // [修复] 使用 RETURNING 子句原子地获取插入的数据，避免并发问题和重复插入
-    let result = query_as::<_, Event>(
-        r#"
-        INSERT INTO events (name, event_date, location, vendor_password, payment_qr_code_path, status)
-        VALUES (?, ?, ?, ?, ?, '未进行')
-        RETURNING *
-        "#
-    )
-    .bind(&name)
-    .bind(&date)
-    .bind(if location.is_empty() { None } else { Some(&location) })
-    .bind(&vendor_password)
-    .bind(&qr_code_path)
-    .fetch_one(&state.db)
+    // 同一事务内创建默认营业日 (展会当天)
+    let result: Result<Event, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let event = query_as::<_, Event>(
+            r#"
+            INSERT INTO events (name, event_date, location, vendor_password, payment_qr_code_path, soldout_policy, status)
+            VALUES (?, ?, ?, ?, ?, ?, '未进行')
+            RETURNING *
+            "#
+        )
+        .bind(&name)
+        .bind(&date)
+        .bind(if location.is_empty() { None } else { Some(&location) })
+        .bind(&vendor_password)
+        .bind(&qr_code_path)
+        .bind(&soldout_policy)
+        .fetch_one(&mut *tx)
+        .await?;
+        session::create_default_session(&mut tx, event.id, &event.event_date).await?;
+        // 上传了收款码时同时建立默认收款方式
+        if let Some(path) = &qr_code_path {
+            query(
+                "INSERT INTO event_payment_methods (event_id, label, qr_code_path, sort_order) VALUES (?, '付款码', ?, 0)",
+            )
+            .bind(event.id)
+            .bind(path)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(event)
+    }
     .await;
 
     match result {
         Ok(event) => {
-            let response = EventResponse::from_model(event);
+            let response = EventResponse::load(&state.db, event).await;
             (StatusCode::CREATED, Json(response)).into_response()
         }
         Err(e) => {
@@ -219,33 +289,37 @@ async fn update_event(
     Path(id): Path<i64>,
     mut multipart: Multipart,
 ) -> impl IntoResponse {
-    let old_event: Event = match query_as("SELECT * FROM events WHERE id = ?")
-        .bind(id)
-        .fetch_optional(&state.db)
-        .await
-        .unwrap_or(None)
-    {
-        Some(e) => e,
-        None => return (StatusCode::NOT_FOUND, "Event not found").into_response(),
-    };
+    let old_event: Event =
+        match query_as("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None)
+        {
+            Some(e) => e,
+            None => return (StatusCode::NOT_FOUND, "Event not found").into_response(),
+        };
 
     let mut name = old_event.name;
     let mut date = old_event.event_date;
     let mut location = old_event.location;
     let mut vendor_password_hash = old_event.vendor_password;
-    let mut qr_code_path = old_event.payment_qr_code_path;
+    let old_qr_code_path = old_event.payment_qr_code_path;
+    let mut uploaded_qr: Option<String> = None;
+    let mut soldout_policy = old_event.soldout_policy;
     let mut should_remove_qr = false;
+    let mut expected_version: Option<i64> = None;
 
+    // 新收款码先落盘，旧文件要等数据库写入成功后才删除
     while let Some(field) = multipart.next_field().await.unwrap_or(None) {
         let field_name = field.name().unwrap_or("").to_string();
 
         if field_name == "payment_qr_code" {
             match save_upload_file(&state.upload_dir, field, Some("events")).await {
                 Ok(new_path) => {
-                    if let Some(old_path) = &qr_code_path {
-                        let _ = delete_file(&state.upload_dir, old_path).await;
+                    if let Some(previous) = uploaded_qr.replace(new_path) {
+                        let _ = delete_file(&state.upload_dir, &previous).await;
                     }
-                    qr_code_path = Some(new_path);
                 }
                 Err(e) => {
                     eprintln!("Update Upload Failed: {}", e);
@@ -269,41 +343,132 @@ async fn update_event(
                         should_remove_qr = true;
                     }
                 }
+                "soldout_policy" => soldout_policy = value,
+                "version" => expected_version = value.parse().ok(),
                 _ => {}
             }
         }
     }
 
-    if should_remove_qr {
-        if let Some(old_path) = &qr_code_path {
-            let _ = delete_file(&state.upload_dir, old_path).await;
+    let rejection = if !is_valid_soldout_policy(&soldout_policy) {
+        Some(
+            (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    json!({"error": "Invalid soldout_policy. Must be one of: show, bottom, hide"}),
+                ),
+            )
+                .into_response(),
+        )
+    } else {
+        // 乐观锁：必须携带读取时的版本号
+        match expected_version {
+            Some(v) if v == old_event.version => None,
+            Some(_) => Some(version_conflict(&state, id).await),
+            None => Some(
+                (
+                    StatusCode::PRECONDITION_REQUIRED,
+                    Json(json!({"error": "version is required"})),
+                )
+                    .into_response(),
+            ),
+        }
+    };
+
+    if let Some(resp) = rejection {
+        if let Some(path) = &uploaded_qr {
+            let _ = delete_file(&state.upload_dir, path).await;
         }
-        qr_code_path = None;
+        return resp;
     }
 
+    // 最终收款码：新上传 > 显式删除 > 保留原图
+    let qr_code_path = if uploaded_qr.is_some() {
+        uploaded_qr.clone()
+    } else if should_remove_qr {
+        None
+    } else {
+        old_qr_code_path.clone()
+    };
+
     // [修复] 使用 RETURNING 子句原子地获取更新后的数据
-    let result = query_as::<_, Event>(
-        r#"
-        UPDATE events 
-        SET name = ?, event_date = ?, location = ?, vendor_password = ?, payment_qr_code_path = ?
-        WHERE id = ?
-        RETURNING *
-        "#,
-    )
-    .bind(name)
-    .bind(date)
-    .bind(location)
-    .bind(vendor_password_hash)
-    .bind(qr_code_path)
-    .bind(id)
-    .fetch_one(&state.db)
+    // 收款码变化时同一事务内同步到收款方式 (替换/移除第一位收款方式的图片)
+    let qr_changed = qr_code_path != old_qr_code_path;
+    let result: Result<Option<Event>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let updated = query_as::<_, Event>(
+            r#"
+            UPDATE events 
+            SET name = ?, event_date = ?, location = ?, vendor_password = ?, payment_qr_code_path = ?,
+                soldout_policy = ?, version = version + 1
+            WHERE id = ? AND version = ? AND deleted_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(date)
+        .bind(location)
+        .bind(vendor_password_hash)
+        .bind(&qr_code_path)
+        .bind(soldout_policy)
+        .bind(id)
+        .bind(old_event.version)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(event) = updated else {
+            return Ok(None);
+        };
+        // 单日展会：营业日随展会日期一起修改；多日展会请通过营业日接口调整
+        query(
+            r#"
+            UPDATE event_sessions SET session_date = ?
+            WHERE event_id = ? AND (SELECT COUNT(*) FROM event_sessions WHERE event_id = ?) = 1
+            "#,
+        )
+        .bind(&event.event_date)
+        .bind(id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        if !qr_changed {
+            tx.commit().await?;
+            return Ok(Some(event));
+        }
+        payment::replace_primary_qr(
+            &mut tx,
+            id,
+            old_qr_code_path.as_deref(),
+            qr_code_path.as_deref(),
+        )
+        .await?;
+        let event = query_as::<_, Event>("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(event))
+    }
     .await;
 
+    // 写入失败时清理本次上传的新图
+    if !matches!(result, Ok(Some(_))) {
+        if let Some(path) = &uploaded_qr {
+            let _ = delete_file(&state.upload_dir, path).await;
+        }
+    }
+
     match result {
-        Ok(event) => {
-            let response = EventResponse::from_model(event);
+        Ok(Some(event)) => {
+            // 写入成功后再删除被替换/移除的旧收款码
+            if let Some(old_path) = &old_qr_code_path {
+                if qr_changed {
+                    payment::remove_unreferenced_file(&state.db, &state.upload_dir, old_path).await;
+                }
+            }
+            let response = EventResponse::load(&state.db, event).await;
             (StatusCode::OK, Json(response)).into_response()
         }
+        Ok(None) => version_conflict(&state, id).await,
         Err(e) => {
             eprintln!("Update DB Error: {:?}", e);
             (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
@@ -311,12 +476,38 @@ async fn update_event(
     }
 }
 
+// 409 Conflict：附带当前最新数据，前端可直接刷新后让用户重新确认
+async fn version_conflict(state: &AppState, id: i64) -> Response {
+    let current: Option<Event> =
+        query_as("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
+
+    match current {
+        Some(event) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Event has been modified by another device, please reload and retry",
+                "current": EventResponse::load(&state.db, event).await
+            })),
+        )
+            .into_response(),
+        None => (StatusCode::NOT_FOUND, "Event not found").into_response(),
+    }
+}
+
 // ==========================================
 // 5. 更新状态 (Admin Only - JSON) [已修复状态验证]
 // ==========================================
 #[derive(Deserialize)]
 struct UpdateStatusRequest {
     status: String,
+    // 跳过流转校验 (重新开放已结束的场次、回退为未进行等)
+    #[serde(default)]
+    force: bool,
+    version: Option<i64>, // 乐观锁：必须携带读取时的版本号
 }
 
 async fn update_status(
@@ -326,93 +517,672 @@ async fn update_status(
     Json(payload): Json<UpdateStatusRequest>,
 ) -> impl IntoResponse {
     // [修复] 验证状态值只能是允许的值 ✓
-    match payload.status.as_str() {
-        "未进行" | "进行中" | "已结束" => {
-            // 使用 RETURNING 子句原子地更新并获取完整数据
-            let result = query_as::<_, Event>(
-                r#"
-                UPDATE events 
-                SET status = ? 
-                WHERE id = ?
-                RETURNING *
-                "#,
+    let Some(next) = EventStatus::parse(&payload.status) else {
+        // 无效的状态值
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Invalid status. Must be one of: 未进行, 进行中, 已结束"})),
+        )
+            .into_response();
+    };
+
+    let event: Event = match query_as("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .unwrap_or(None)
+    {
+        Some(e) => e,
+        None => return (StatusCode::NOT_FOUND, "Event not found").into_response(),
+    };
+
+    match payload.version {
+        Some(v) if v == event.version => {}
+        Some(_) => return version_conflict(&state, id).await,
+        None => {
+            return (
+                StatusCode::PRECONDITION_REQUIRED,
+                Json(json!({"error": "version is required"})),
             )
-            .bind(&payload.status)
-            .bind(id)
-            .fetch_one(&state.db)
-            .await;
+                .into_response()
+        }
+    }
 
-            match result {
-                Ok(event) => {
-                    let response = EventResponse::from_model(event);
-                    (StatusCode::OK, Json(response)).into_response()
-                }
-                Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    // 状态未变化：不记录日志，直接返回
+    if event.status == next {
+        return (
+            StatusCode::OK,
+            Json(EventResponse::load(&state.db, event).await),
+        )
+            .into_response();
+    }
+
+    let allowed = event.status.can_transition_to(next);
+    if !allowed && !payload.force {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": format!(
+                    "Cannot change status from {} to {}",
+                    event.status.as_str(),
+                    next.as_str()
+                ),
+                "current": EventResponse::load(&state.db, event).await
+            })),
+        )
+            .into_response();
+    }
+
+    let result = change_status(&state.db, &event, next, !allowed, "manual").await;
+
+    match result {
+        Ok(Some(event)) => {
+            let response = EventResponse::load(&state.db, event).await;
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => version_conflict(&state, id).await,
+        Err(e) => {
+            eprintln!("Update status error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// 写入新状态并记录变更日志
+// 开始营业：保留首次开始时间，清空结束时间；结束营业：记录结束时间；
+// 回退为未进行：两者都清空 (定时收摊除外，多日展会次日继续营业)
+// 以 event.version 做比较写入，期间被其他设备修改过时返回 None
+pub(crate) async fn change_status(
+    db: &sqlx::SqlitePool,
+    event: &Event,
+    next: EventStatus,
+    forced: bool,
+    source: &str, // "manual" | "schedule"
+) -> Result<Option<Event>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let updated = query_as::<_, Event>(
+        r#"
+        UPDATE events
+        SET status = ?1,
+            started_at = CASE ?1
+                WHEN '进行中' THEN COALESCE(started_at, CURRENT_TIMESTAMP)
+                WHEN '未进行' THEN CASE WHEN ?3 = 'schedule' THEN started_at END
+                ELSE started_at END,
+            ended_at = CASE ?1 WHEN '已结束' THEN CURRENT_TIMESTAMP ELSE NULL END,
+            version = version + 1
+        WHERE id = ?2 AND version = ?4 AND deleted_at IS NULL
+        RETURNING *
+        "#,
+    )
+    .bind(next)
+    .bind(event.id)
+    .bind(source)
+    .bind(event.version)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(updated) = updated else {
+        return Ok(None);
+    };
+
+    query(
+        "INSERT INTO event_status_changes (event_id, from_status, to_status, forced, source) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(event.id)
+    .bind(event.status)
+    .bind(next)
+    .bind(forced)
+    .bind(source)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(updated))
+}
+
+// 状态变更历史 (Admin Only)，按时间先后排列
+#[derive(Serialize, sqlx::FromRow)]
+struct StatusChange {
+    from_status: EventStatus,
+    to_status: EventStatus,
+    forced: bool,
+    source: String,
+    changed_at: NaiveDateTime,
+}
+
+async fn list_status_changes(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let changes: Vec<StatusChange> = query_as(
+        r#"
+        SELECT from_status, to_status, forced, source, changed_at FROM event_status_changes
+        WHERE event_id = ?
+        ORDER BY changed_at ASC, id ASC
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .unwrap_or_default();
+
+    Json(changes).into_response()
+}
+
+// ==========================================
+// 6. 删除漫展 (Admin Only) [已修复级联删除]
+// ==========================================
+async fn delete_event(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let result = query(
+        "UPDATE events SET deleted_at = CURRENT_TIMESTAMP, version = version + 1 WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(r) if r.rows_affected() == 1 => (
+            StatusCode::OK,
+            Json(json!({"message": "Event moved to trash", "id": id})),
+        )
+            .into_response(),
+        Ok(_) => (StatusCode::NOT_FOUND, "Event not found").into_response(),
+        Err(e) => {
+            eprintln!("Delete event error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 回收站 (Admin Only)
+// ==========================================
+
+#[derive(Serialize)]
+struct TrashedEvent {
+    #[serde(flatten)]
+    event: EventResponse,
+    deleted_at: Option<NaiveDateTime>,
+    order_count: i64,
+    total_revenue: f64,
+}
+
+// 回收站列表：附带订单数与销售额，便于确认是否彻底删除
+async fn list_trash(State(state): State<AppState>, _: AdminOnly) -> impl IntoResponse {
+    let events: Vec<Event> =
+        query_as("SELECT * FROM events WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC")
+            .fetch_all(&state.db)
+            .await
+            .unwrap_or_default();
+
+    let mut trashed = Vec::with_capacity(events.len());
+    for event in events {
+        let (order_count, total_revenue): (i64, f64) = query_as(
+            "SELECT COUNT(*), COALESCE(SUM(total_amount), 0.0) FROM orders WHERE event_id = ? AND status != 'cancelled'",
+        )
+        .bind(event.id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap_or((0, 0.0));
+        trashed.push(TrashedEvent {
+            deleted_at: event.deleted_at,
+            event: EventResponse::from_model(event),
+            order_count,
+            total_revenue,
+        });
+    }
+
+    Json(trashed).into_response()
+}
+
+// 从回收站恢复
+async fn restore_event(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let result = query(
+        "UPDATE events SET deleted_at = NULL, version = version + 1 WHERE id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(id)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(r) if r.rows_affected() == 1 => {
+            let event: Option<Event> = query_as("SELECT * FROM events WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&state.db)
+                .await
+                .unwrap_or(None);
+            match event {
+                Some(e) => Json(EventResponse::load(&state.db, e).await).into_response(),
+                None => (StatusCode::NOT_FOUND, "Event not found").into_response(),
             }
         }
-        _ => {
-            // 无效的状态值
+        Ok(_) => (StatusCode::NOT_FOUND, "Event not found in trash").into_response(),
+        Err(e) => {
+            eprintln!("Restore event error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// 彻底删除回收站中的某个场次
+async fn purge_event(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let event: Option<Event> =
+        query_as("SELECT * FROM events WHERE id = ? AND deleted_at IS NOT NULL")
+            .bind(id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
+    let Some(event) = event else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Event not found in trash. Delete it first."})),
+        )
+            .into_response();
+    };
+
+    match purge(&state, &event).await {
+        Ok(qr_removed) => Json(json!({
+            "message": "Event purged",
+            "id": id,
+            "qr_removed": qr_removed
+        }))
+        .into_response(),
+        Err(e) => {
+            eprintln!("Purge event {} error: {:?}", id, e);
             (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Invalid status. Must be one of: 未进行, 进行中, 已结束"})),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to purge event: {}", e)})),
             )
                 .into_response()
         }
     }
 }
 
+// 清空回收站：逐个场次在各自的事务中删除，某个失败不影响其余
+async fn empty_trash(State(state): State<AppState>, _: AdminOnly) -> impl IntoResponse {
+    let events: Vec<Event> = query_as("SELECT * FROM events WHERE deleted_at IS NOT NULL")
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default();
+
+    let mut purged = Vec::new();
+    let mut failed = Vec::new();
+    for event in &events {
+        match purge(&state, event).await {
+            Ok(_) => purged.push(event.id),
+            Err(e) => {
+                eprintln!("Purge event {} error: {:?}", event.id, e);
+                failed.push(json!({"id": event.id, "error": e.to_string()}));
+            }
+        }
+    }
+
+    let status = if failed.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    (status, Json(json!({"purged": purged, "failed": failed}))).into_response()
+}
+
+// 在一个事务中删除场次，提交后再删除付款码图片。
+// 连接已开启外键约束 (sqlx 默认 foreign_keys = ON)，场次的子表 (商品、订单、营业日、盘点、
+// 收款方式、定时任务等) 均声明了 ON DELETE CASCADE，删除场次即级联清理全部关联数据。
+// 返回是否删除了付款码图片 (没有图片或仍被其他场次引用时为 false)
+async fn purge(state: &AppState, event: &Event) -> Result<bool, sqlx::Error> {
+    let id = event.id;
+    let mut tx = state.db.begin().await?;
+
+    let mut qr_paths: Vec<String> = query_as::<_, (String,)>(
+        "SELECT qr_code_path FROM event_payment_methods WHERE event_id = ? AND qr_code_path IS NOT NULL",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|(path,)| path)
+    .collect();
+    if let Some(path) = &event.payment_qr_code_path {
+        if !qr_paths.iter().any(|p| *p == upload_url(path)) {
+            qr_paths.push(path.clone());
+        }
+    }
+
+    query("DELETE FROM events WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    // 数据已删除，图片删除失败只记录日志
+    let mut removed = false;
+    for path in &qr_paths {
+        removed |= payment::remove_unreferenced_file(&state.db, &state.upload_dir, path).await;
+    }
+    Ok(removed)
+}
+
 // ==========================================
-// 6. 删除漫展 (Admin Only) [已修复级联删除]
+// 7. 复制场次 (Admin Only - JSON)
 // ==========================================
-async fn delete_event(
+// 复制场次信息 (地点、收款码、摊主密码、展示设置) 与全部场次商品 (售价、排序、角标)。
+// 名称/编号取全局商品当前值；已归档的全局商品不再上架，在 skipped 中列出。
+#[derive(Deserialize)]
+struct CloneEventRequest {
+    name: Option<String>, // 默认 "原名称 (副本)"
+    date: String,
+    location: Option<String>, // 默认沿用原场次
+    // 库存：initial = 沿用原场次初始库存 (默认)，leftover = 结转剩余库存，
+    //       zero = 清零，fixed = 统一设为 stock
+    stock_mode: Option<String>,
+    stock: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct SkippedProduct {
+    product_code: String,
+    reason: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct SourceProduct {
+    master_product_id: i64,
+    product_code: String,
+    name: String,
+    price: f64,
+    initial_stock: i64,
+    current_stock: i64,
+    sort_order: i64,
+    is_featured: bool,
+    is_new: bool,
+    archived: bool,
+}
+
+async fn clone_event(
     State(state): State<AppState>,
     _: AdminOnly,
     Path(id): Path<i64>,
+    Json(payload): Json<CloneEventRequest>,
 ) -> impl IntoResponse {
-    let event: Option<Event> = query_as("SELECT * FROM events WHERE id = ?")
+    let source: Event = match query_as("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
         .bind(id)
         .fetch_optional(&state.db)
         .await
-        .unwrap_or(None);
+        .unwrap_or(None)
+    {
+        Some(e) => e,
+        None => return (StatusCode::NOT_FOUND, "Event not found").into_response(),
+    };
+
+    if payload.date.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Date is required"})),
+        )
+            .into_response();
+    }
+    let stock_mode = payload.stock_mode.as_deref().unwrap_or("initial");
+    let fixed_stock = match (stock_mode, payload.stock) {
+        ("initial" | "leftover" | "zero", _) => 0,
+        ("fixed", Some(n)) if n >= 0 => n,
+        ("fixed", _) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "stock must be a non-negative number when stock_mode is fixed"})),
+            )
+                .into_response()
+        }
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid stock_mode. Must be one of: initial, leftover, zero, fixed"})),
+            )
+                .into_response()
+        }
+    };
 
-    if let Some(e) = event {
-        // 1. 删除物理文件
-        if let Some(path) = &e.payment_qr_code_path {
-            let _ = delete_file(&state.upload_dir, &path).await;
+    let name = payload
+        .name
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| format!("{} (副本)", source.name));
+    let location = match payload.location {
+        Some(l) if l.is_empty() => None,
+        Some(l) => Some(l),
+        None => source.location.clone(),
+    };
+
+    // 收款码复制为独立文件，避免删除任一场次时误删另一场次的收款码
+    let mut methods: Vec<PaymentMethod> = payment::fetch_methods(&state.db, id).await;
+    for method in methods.iter_mut() {
+        if let Some(path) = method.qr_code_path.take() {
+            match copy_file(&state.upload_dir, &path).await {
+                Ok(new_path) => method.qr_code_path = Some(new_path),
+                Err(e) => eprintln!("Copy payment QR failed: {}", e),
+            }
         }
+    }
+    let copied_files: Vec<String> = methods
+        .iter()
+        .filter_map(|m| m.qr_code_path.clone())
+        .collect();
+
+    let result: Result<(i64, usize, i64, Vec<SkippedProduct>), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
 
-        // 2. [修复] 删除关联的 orders 和 products (级联删除) ✓
-        // 注意：SQLite 需要开启 PRAGMA foreign_keys = ON 才能自动级联删除
-        // 这里显式删除以确保不依赖 DB 配置
+        let new_id = query(
+            r#"
+            INSERT INTO events (name, event_date, location, vendor_password, soldout_policy, status)
+            VALUES (?, ?, ?, ?, ?, '未进行')
+            "#,
+        )
+        .bind(&name)
+        .bind(payload.date.trim())
+        .bind(&location)
+        .bind(&source.vendor_password)
+        .bind(&source.soldout_policy)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        session::create_default_session(&mut tx, new_id, payload.date.trim()).await?;
+        // 收款方式 (名称、顺序、图片副本)；主收款码由第一位收款方式决定
+        for method in &methods {
+            query(
+                "INSERT INTO event_payment_methods (event_id, label, qr_code_path, sort_order) VALUES (?, ?, ?, ?)",
+            )
+            .bind(new_id)
+            .bind(&method.label)
+            .bind(&method.qr_code_path)
+            .bind(method.sort_order)
+            .execute(&mut *tx)
+            .await?;
+        }
+        payment::sync_primary_qr(&mut tx, new_id).await?;
+        // 场次配置原样复制
+        query("INSERT INTO event_settings (event_id, settings) SELECT ?, settings FROM event_settings WHERE event_id = ?")
+            .bind(new_id)
+            .bind(source.id)
+            .execute(&mut *tx)
+            .await?;
 
-        // 先删除 orders 相关的 order_items
-        let _ = sqlx::query(
-            "DELETE FROM order_items WHERE order_id IN (SELECT id FROM orders WHERE event_id = ?)",
+        let products: Vec<SourceProduct> = query_as(
+            r#"
+            SELECT p.master_product_id, mp.product_code, mp.name, p.price,
+                   p.initial_stock, p.current_stock, p.sort_order, p.is_featured, p.is_new,
+                   (mp.archived_at IS NOT NULL) AS archived
+            FROM products p
+            JOIN master_products mp ON p.master_product_id = mp.id
+            WHERE p.event_id = ?
+            ORDER BY p.sort_order ASC, p.product_code ASC
+            "#,
         )
         .bind(id)
-        .execute(&state.db)
-        .await;
+        .fetch_all(&mut *tx)
+        .await?;
 
-        // 删除 orders
-        let _ = sqlx::query("DELETE FROM orders WHERE event_id = ?")
-            .bind(id)
-            .execute(&state.db)
-            .await;
+        let mut copied = 0;
+        let mut total_stock = 0;
+        let mut skipped = Vec::new();
+        for p in products {
+            if p.archived {
+                skipped.push(SkippedProduct {
+                    product_code: p.product_code,
+                    reason: "archived".to_string(),
+                });
+                continue;
+            }
+            let stock = match stock_mode {
+                "initial" => p.initial_stock,
+                "leftover" => p.current_stock.max(0),
+                _ => fixed_stock,
+            };
+            query(
+                r#"
+                INSERT INTO products
+                    (event_id, master_product_id, product_code, name, price,
+                     initial_stock, current_stock, sort_order, is_featured, is_new)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(new_id)
+            .bind(p.master_product_id)
+            .bind(&p.product_code)
+            .bind(&p.name)
+            .bind(p.price)
+            .bind(stock)
+            .bind(stock)
+            .bind(p.sort_order)
+            .bind(p.is_featured)
+            .bind(p.is_new)
+            .execute(&mut *tx)
+            .await?;
+            copied += 1;
+            total_stock += stock;
+        }
 
-        // 删除 products
-        let _ = sqlx::query("DELETE FROM products WHERE event_id = ?")
-            .bind(id)
-            .execute(&state.db)
-            .await;
+        tx.commit().await?;
+        Ok((new_id, copied, total_stock, skipped))
+    }
+    .await;
 
-        // 3. 最后删除 event
-        let _ = query("DELETE FROM events WHERE id = ?")
-            .bind(id)
-            .execute(&state.db)
-            .await;
+    let (new_id, copied, total_stock, skipped) = match result {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Clone event error: {:?}", e);
+            for path in &copied_files {
+                let _ = delete_file(&state.upload_dir, path).await;
+            }
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+        }
+    };
 
-        (StatusCode::OK, Json(json!({"message": "Event deleted"}))).into_response()
-    } else {
-        (StatusCode::NOT_FOUND, "Event not found").into_response()
+    let event: Event = match query_as("SELECT * FROM events WHERE id = ?")
+        .bind(new_id)
+        .fetch_one(&state.db)
+        .await
+    {
+        Ok(e) => e,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(json!({
+            "payment_qr_copied": event.payment_qr_code_path.is_some(),
+            "payment_methods_copied": methods.len(),
+            "event": EventResponse::load(&state.db, event).await,
+            "source_event_id": id,
+            "copied_products": copied,
+            "total_stock": total_stock,
+            "stock_mode": stock_mode,
+            "skipped": skipped
+        })),
+    )
+        .into_response()
+}
+
+// ==========================================
+// 状态守卫 (订单/商品接口复用)
+// ==========================================
+// 管理员可通过 ?force=true 绕过 (补录订单、结束后修正库存等)
+#[derive(Deserialize, Default)]
+pub(crate) struct OverrideQuery {
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum GuardedAction {
+    PlaceOrder,
+    EditProducts,
+}
+
+// 回收站中的场次视为不存在
+pub(crate) async fn event_exists(db: &sqlx::SqlitePool, event_id: i64) -> bool {
+    let row: Option<(i64,)> = query_as("SELECT id FROM events WHERE id = ? AND deleted_at IS NULL")
+        .bind(event_id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None);
+    row.is_some()
+}
+
+pub(crate) async fn ensure_status_allows(
+    db: &sqlx::SqlitePool,
+    event_id: i64,
+    action: GuardedAction,
+    claims: Option<&Claims>,
+    overrides: &OverrideQuery,
+) -> Result<(), Response> {
+    // 回收站中的场次视为不存在
+    let status: Option<(EventStatus,)> =
+        query_as("SELECT status FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(event_id)
+            .fetch_optional(db)
+            .await
+            .unwrap_or(None);
+    let Some((status,)) = status else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Event not found"})),
+        )
+            .into_response());
+    };
+
+    let allowed = match action {
+        GuardedAction::PlaceOrder => status.accepts_orders(),
+        GuardedAction::EditProducts => status.allows_product_edits(),
+    };
+    let admin_override = overrides.force && claims.is_some_and(|c| c.role == "admin");
+    if allowed || admin_override {
+        return Ok(());
     }
+
+    let error = match action {
+        GuardedAction::PlaceOrder => {
+            format!("Event is {}, orders are not accepted", status.as_str())
+        }
+        GuardedAction::EditProducts => {
+            format!(
+                "Event is {}, products can no longer be changed",
+                status.as_str()
+            )
+        }
+    };
+    Err((
+        StatusCode::CONFLICT,
+        Json(json!({"error": error, "status": status})),
+    )
+        .into_response())
 }
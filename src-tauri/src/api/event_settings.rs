@@ -0,0 +1,142 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+use sqlx::{query, query_as, SqlitePool};
+
+use crate::{
+    api::{event::event_exists, guard::AdminOnly},
+    db::models::EventSettings,
+    state::AppState,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 公开：读取场次配置 (顾客端需要货币符号、是否可自助下单等)；管理员：修改
+        .route(
+            "/events/:event_id/settings",
+            get(get_settings).put(update_settings),
+        )
+}
+
+// ==========================================
+// 1. 读取场次配置 (Public)
+// ==========================================
+async fn get_settings(
+    State(state): State<AppState>,
+    Path(event_id): Path<i64>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+    Json(load_settings(&state.db, event_id).await).into_response()
+}
+
+// ==========================================
+// 2. 修改场次配置 (Admin Only - JSON)
+// ==========================================
+// 部分更新：只覆盖请求中出现的字段，传 null 可清空可选项
+async fn update_settings(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(event_id): Path<i64>,
+    Json(payload): Json<Value>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    let bad_request =
+        |msg: String| (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response();
+
+    let Value::Object(changes) = payload else {
+        return bad_request("Settings must be a JSON object".into());
+    };
+
+    let current = load_settings(&state.db, event_id).await;
+    let mut merged = serde_json::to_value(&current).unwrap_or_else(|_| json!({}));
+    if let Value::Object(fields) = &mut merged {
+        for (key, value) in changes {
+            if !fields.contains_key(&key) {
+                return bad_request(format!("Unknown setting: {}", key));
+            }
+            fields.insert(key, value);
+        }
+    }
+
+    let mut settings: EventSettings = match serde_json::from_value(merged) {
+        Ok(s) => s,
+        Err(e) => return bad_request(format!("Invalid settings: {}", e)),
+    };
+    settings.currency_symbol = settings.currency_symbol.trim().to_string();
+    if let Err(msg) = settings.validate() {
+        return bad_request(msg);
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        query(
+            r#"
+            INSERT INTO event_settings (event_id, settings, updated_at)
+            VALUES (?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT (event_id)
+            DO UPDATE SET settings = excluded.settings, updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(event_id)
+        .bind(serde_json::to_string(&settings).unwrap_or_else(|_| "{}".to_string()))
+        .execute(&mut *tx)
+        .await?;
+        // 售罄策略仍存在 events 表，商品列表/展会编辑页直接读取
+        query(
+            "UPDATE events SET soldout_policy = ?, version = version + 1 WHERE id = ? AND soldout_policy != ?",
+        )
+        .bind(&settings.soldout_policy)
+        .bind(event_id)
+        .bind(&settings.soldout_policy)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => Json(settings).into_response(),
+        Err(e) => {
+            eprintln!("Update event settings error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 共享辅助函数 (订单/商品/统计/登录复用)
+// ==========================================
+
+// 读取场次配置；未保存过或 JSON 损坏时返回默认值。售罄策略以 events 表为准。
+pub(crate) async fn load_settings(db: &SqlitePool, event_id: i64) -> EventSettings {
+    let stored: Option<(String,)> =
+        query_as("SELECT settings FROM event_settings WHERE event_id = ?")
+            .bind(event_id)
+            .fetch_optional(db)
+            .await
+            .unwrap_or(None);
+
+    let mut settings: EventSettings = stored
+        .and_then(|(raw,)| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+
+    let policy: Option<(String,)> = query_as("SELECT soldout_policy FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None);
+    if let Some((policy,)) = policy {
+        settings.soldout_policy = policy;
+    }
+    settings
+}
@@ -0,0 +1,660 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use chrono::{Duration, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, SqlitePool};
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::{
+    api::{guard::check_global_permission, session::fetch_sessions, stats::round2},
+    db::models::Event,
+    state::AppState,
+    utils::security::Claims,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 管理员/全场次摊主：按历史销售速度预测需求、推荐备货量
+        .route("/events/:event_id/stats/forecast", get(get_forecast))
+        // 管理员/摊主：按当前销售速度估算现有库存的售罄时间
+        .route("/events/:event_id/stats/sellout", get(get_sellout))
+}
+
+// ==========================================
+// DTOs (数据传输对象)
+// ==========================================
+// 销售速度 = 销量 / 在售营业小时数。售罄的商品只计到售罄那一刻为止，
+// 否则售罄后的空档会把速度拉低，导致越卖得好的商品备货越少。
+// 营业时间取营业日的开场/收摊时间；未设置时用当日首/末笔订单时间代替。
+
+#[derive(Deserialize)]
+struct ForecastQuery {
+    safety_margin: Option<f64>, // 安全余量，默认 0.2 (多备 20%)，0-5
+    lookback: Option<usize>,    // 每个商品参考最近几场，默认 5，最多 50
+    hours: Option<f64>,         // 预计营业小时数；默认按本场营业日推算
+}
+
+#[derive(Deserialize)]
+struct SelloutQuery {
+    window_minutes: Option<i64>, // 计算当前速度的时间窗口，默认 60，15-720
+}
+
+#[derive(Serialize)]
+struct HistoryEntry {
+    event_id: i64,
+    event_name: String,
+    event_date: String,
+    units: i64,
+    initial_stock: i64,
+    hours_on_sale: f64, // 在售营业小时数 (售罄时截止到售罄时刻)
+    sold_out: bool,
+    velocity: Option<f64>, // 件/小时；没有营业时间记录时为 null
+}
+
+#[derive(Serialize)]
+struct ProductForecast {
+    product_id: i64,
+    product_code: String,
+    name: String,
+    category: Option<String>,
+    initial_stock: i64, // 本场当前设置的初始库存
+    current_stock: i64,
+    history: Vec<HistoryEntry>, // 最近的场次在前
+    velocity: Option<f64>,      // 历史合计销量 / 合计在售小时数
+    sold_out_events: usize,
+    forecast_units: Option<f64>,    // 速度 × 预计营业小时数
+    recommended_stock: Option<i64>, // 预测销量 × (1 + 安全余量)，向上取整
+    stock_gap: Option<i64>,         // 推荐备货 - 当前初始库存；正数表示需要加印
+}
+
+#[derive(Serialize, Default)]
+struct ForecastTotals {
+    forecast_units: f64,
+    recommended_stock: i64,
+    initial_stock: i64,
+}
+
+#[derive(Serialize)]
+struct ForecastReport {
+    event_id: i64,
+    expected_hours: f64,
+    hours_source: &'static str, // "query" | "sessions" | "estimated"
+    safety_margin: f64,
+    lookback: usize,
+    without_history: usize, // 没有历史数据、无法预测的商品数
+    totals: ForecastTotals, // 只合计有预测的商品
+    products: Vec<ProductForecast>,
+}
+
+#[derive(Serialize)]
+struct SelloutEstimate {
+    product_id: i64,
+    product_code: String,
+    name: String,
+    current_stock: i64,
+    recent_units: i64,
+    rate_per_hour: Option<f64>,
+    basis: &'static str, // "recent" = 最近窗口；"event" = 本场累计；"none" = 尚无销售
+    hours_to_sellout: Option<f64>,
+    estimated_sellout_at: Option<String>, // 本地时间 "YYYY-MM-DD HH:MM"
+    sells_out_before_close: Option<bool>, // 今日设置了收摊时间时才有值
+}
+
+#[derive(Serialize)]
+struct SelloutReport {
+    event_id: i64,
+    generated_at: String,
+    window_minutes: i64,
+    closes_at: Option<String>,      // 今日收摊时间
+    products: Vec<SelloutEstimate>, // 预计售罄早的在前
+}
+
+// 未设置营业时间、又无法从订单推算时，每个营业日按 8 小时估算
+const DEFAULT_SESSION_HOURS: f64 = 8.0;
+// 仅凭订单推算的营业时间至少按 1 小时计，避免只有一笔订单时速度无穷大
+const MIN_DERIVED_HOURS: f64 = 1.0;
+
+// ==========================================
+// 1. 备货预测 (Forecast)
+// ==========================================
+async fn get_forecast(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+    Query(params): Query<ForecastQuery>,
+) -> Response {
+    // 预测会读取其他场次的销售数据
+    if let Err(e) = check_global_permission(&claims) {
+        return e.into_response();
+    }
+
+    let safety_margin = params.safety_margin.unwrap_or(0.2);
+    if !(0.0..=5.0).contains(&safety_margin) {
+        return bad_request("safety_margin must be between 0 and 5");
+    }
+    if let Some(hours) = params.hours {
+        if !(hours > 0.0 && hours <= 24.0 * 30.0) {
+            return bad_request("hours must be between 0 and 720");
+        }
+    }
+    let lookback = params.lookback.unwrap_or(5).clamp(1, 50);
+
+    let event: Event =
+        match sqlx::query_as("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(event_id)
+            .fetch_optional(&state.db)
+            .await
+        {
+            Ok(Some(e)) => e,
+            Ok(None) => return (StatusCode::NOT_FOUND, "Event not found").into_response(),
+            Err(e) => return db_error(e),
+        };
+
+    match build_forecast(&state.db, &event, &params, safety_margin, lookback).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => db_error(e),
+    }
+}
+
+#[derive(FromRow)]
+struct LineupRow {
+    id: i64,
+    master_product_id: i64,
+    product_code: String,
+    name: String,
+    category: Option<String>,
+    initial_stock: i64,
+    current_stock: i64,
+}
+
+#[derive(FromRow)]
+struct ListingRow {
+    product_id: i64,
+    master_product_id: i64,
+    event_id: i64,
+    event_name: String,
+    event_date: String,
+    initial_stock: i64,
+}
+
+#[derive(FromRow)]
+struct SaleRow {
+    product_id: i64,
+    quantity: i64,
+    local_time: String,
+}
+
+async fn build_forecast(
+    db: &SqlitePool,
+    event: &Event,
+    params: &ForecastQuery,
+    safety_margin: f64,
+    lookback: usize,
+) -> Result<ForecastReport, sqlx::Error> {
+    // 1. 本场商品
+    let lineup: Vec<LineupRow> = sqlx::query_as(
+        r#"
+        SELECT p.id, p.master_product_id, p.product_code, p.name,
+               NULLIF(mp.category, '') AS category, p.initial_stock, p.current_stock
+        FROM products p
+        JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE p.event_id = ?
+        ORDER BY p.sort_order ASC, p.product_code ASC
+        "#,
+    )
+    .bind(event.id)
+    .fetch_all(db)
+    .await?;
+
+    // 2. 历史场次中同款商品的上架记录 (不含本场、回收站和更晚的场次)，最近的在前
+    let history_scope = r#"
+        FROM products p
+        JOIN events e ON p.event_id = e.id
+        WHERE e.deleted_at IS NULL AND e.id != ? AND e.event_date <= ?
+          AND p.master_product_id IN (SELECT master_product_id FROM products WHERE event_id = ?)
+    "#;
+    let listings: Vec<ListingRow> = sqlx::query_as(&format!(
+        r#"
+        SELECT p.id AS product_id, p.master_product_id, e.id AS event_id,
+               e.name AS event_name, e.event_date, p.initial_stock
+        {history_scope}
+        ORDER BY e.event_date DESC, e.id DESC
+        "#
+    ))
+    .bind(event.id)
+    .bind(&event.event_date)
+    .bind(event.id)
+    .fetch_all(db)
+    .await?;
+
+    // 3. 这些上架记录的销售明细，按时间顺序，用于找出售罄时刻
+    let sales: Vec<SaleRow> = sqlx::query_as(&format!(
+        r#"
+        SELECT oi.product_id, oi.quantity, datetime(o.created_at, 'localtime') AS local_time
+        FROM order_items oi
+        JOIN orders o ON oi.order_id = o.id
+        WHERE o.status != 'cancelled'
+          AND oi.product_id IN (SELECT p.id {history_scope})
+        ORDER BY o.created_at ASC, o.id ASC
+        "#
+    ))
+    .bind(event.id)
+    .bind(&event.event_date)
+    .bind(event.id)
+    .fetch_all(db)
+    .await?;
+    let mut sales_by_product: HashMap<i64, Vec<SaleRow>> = HashMap::new();
+    for sale in sales {
+        sales_by_product
+            .entry(sale.product_id)
+            .or_default()
+            .push(sale);
+    }
+
+    // 4. 各历史场次的营业时间段
+    let mut windows: HashMap<i64, Vec<OpenWindow>> = HashMap::new();
+    for listing in &listings {
+        if let Entry::Vacant(slot) = windows.entry(listing.event_id) {
+            slot.insert(open_windows(db, listing.event_id, None).await?);
+        }
+    }
+
+    // 5. 每条上架记录的销量、售罄时刻与在售小时数
+    let mut history: HashMap<i64, Vec<HistoryEntry>> = HashMap::new();
+    for listing in listings {
+        let entries = history.entry(listing.master_product_id).or_default();
+        if entries.len() >= lookback {
+            continue;
+        }
+        let mut units = 0;
+        let mut sold_out_at = None;
+        for sale in sales_by_product
+            .get(&listing.product_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            units += sale.quantity;
+            if sold_out_at.is_none() && listing.initial_stock > 0 && units >= listing.initial_stock
+            {
+                sold_out_at = parse_local(&sale.local_time);
+            }
+        }
+        let event_windows = windows
+            .get(&listing.event_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let hours_on_sale = hours_until(event_windows, sold_out_at);
+        entries.push(HistoryEntry {
+            event_id: listing.event_id,
+            event_name: listing.event_name,
+            event_date: listing.event_date,
+            units,
+            initial_stock: listing.initial_stock,
+            hours_on_sale: round2(hours_on_sale),
+            sold_out: sold_out_at.is_some(),
+            velocity: (hours_on_sale > 0.0).then(|| round2(units as f64 / hours_on_sale)),
+        });
+    }
+
+    // 6. 本场预计营业小时数
+    let (expected_hours, hours_source) = match params.hours {
+        Some(hours) => (hours, "query"),
+        None => {
+            let sessions = fetch_sessions(db, event.id).await;
+            let mut known = 0.0;
+            let mut unknown = 0;
+            for s in &sessions {
+                match session_window(
+                    &s.session_date,
+                    s.opens_at.as_deref(),
+                    s.closes_at.as_deref(),
+                ) {
+                    Some((start, end)) => known += hours_between(start, end),
+                    None => unknown += 1,
+                }
+            }
+            if unknown == 0 && !sessions.is_empty() {
+                (known, "sessions")
+            } else {
+                // 未设置营业时间的营业日按历史场次的平均营业日时长估算
+                let past: Vec<f64> = windows
+                    .values()
+                    .flatten()
+                    .map(|w| hours_between(w.start, w.end))
+                    .collect();
+                let per_session = if past.is_empty() {
+                    DEFAULT_SESSION_HOURS
+                } else {
+                    past.iter().sum::<f64>() / past.len() as f64
+                };
+                (known + per_session * unknown.max(1) as f64, "estimated")
+            }
+        }
+    };
+
+    // 7. 汇总预测
+    let mut totals = ForecastTotals::default();
+    let mut without_history = 0;
+    let mut products = Vec::with_capacity(lineup.len());
+    for row in lineup {
+        let entries = history.remove(&row.master_product_id).unwrap_or_default();
+        let (units, hours) = entries
+            .iter()
+            .filter(|h| h.hours_on_sale > 0.0)
+            .fold((0i64, 0.0), |(u, h), e| (u + e.units, h + e.hours_on_sale));
+        let velocity = (hours > 0.0).then(|| units as f64 / hours);
+        let forecast_units = velocity.map(|v| v * expected_hours);
+        let recommended_stock = forecast_units.map(|f| (f * (1.0 + safety_margin)).ceil() as i64);
+
+        match (forecast_units, recommended_stock) {
+            (Some(f), Some(r)) => {
+                totals.forecast_units += f;
+                totals.recommended_stock += r;
+                totals.initial_stock += row.initial_stock;
+            }
+            _ => without_history += 1,
+        }
+
+        products.push(ProductForecast {
+            product_id: row.id,
+            product_code: row.product_code,
+            name: row.name,
+            category: row.category,
+            initial_stock: row.initial_stock,
+            current_stock: row.current_stock,
+            sold_out_events: entries.iter().filter(|h| h.sold_out).count(),
+            history: entries,
+            velocity: velocity.map(round2),
+            forecast_units: forecast_units.map(round2),
+            recommended_stock,
+            stock_gap: recommended_stock.map(|r| r - row.initial_stock),
+        });
+    }
+    totals.forecast_units = round2(totals.forecast_units);
+
+    Ok(ForecastReport {
+        event_id: event.id,
+        expected_hours: round2(expected_hours),
+        hours_source,
+        safety_margin,
+        lookback,
+        without_history,
+        totals,
+        products,
+    })
+}
+
+// ==========================================
+// 2. 售罄时间预估 (Live Sell-out)
+// ==========================================
+async fn get_sellout(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+    Query(params): Query<SelloutQuery>,
+) -> Response {
+    if let Err(e) = check_read_permission(&claims, event_id) {
+        return e.into_response();
+    }
+    let window_minutes = params.window_minutes.unwrap_or(60);
+    if !(15..=720).contains(&window_minutes) {
+        return bad_request("window_minutes must be between 15 and 720");
+    }
+
+    let exists: Option<(i64,)> =
+        sqlx::query_as("SELECT id FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(event_id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
+    if exists.is_none() {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    match build_sellout(&state.db, event_id, window_minutes).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => db_error(e),
+    }
+}
+
+#[derive(FromRow)]
+struct StockRow {
+    id: i64,
+    product_code: String,
+    name: String,
+    current_stock: i64,
+}
+
+async fn build_sellout(
+    db: &SqlitePool,
+    event_id: i64,
+    window_minutes: i64,
+) -> Result<SelloutReport, sqlx::Error> {
+    let now = Local::now().naive_local();
+    let today = now.format("%Y-%m-%d").to_string();
+
+    // 今日营业时间：窗口不早于开场时间，售罄时间与收摊时间比较
+    let sessions = fetch_sessions(db, event_id).await;
+    let session = sessions.iter().find(|s| s.session_date == today);
+    let opens = session
+        .and_then(|s| s.opens_at.as_deref())
+        .and_then(|t| parse_local(&format!("{today} {t}")));
+    let closes = session
+        .and_then(|s| s.closes_at.as_deref())
+        .and_then(|t| parse_local(&format!("{today} {t}")));
+    let mut window_start = now - Duration::minutes(window_minutes);
+    if let Some(open) = opens.filter(|o| *o > window_start && *o < now) {
+        window_start = open;
+    }
+    let window_hours = hours_between(window_start, now);
+
+    let products: Vec<StockRow> = sqlx::query_as(
+        "SELECT id, product_code, name, current_stock FROM products WHERE event_id = ? ORDER BY sort_order ASC, product_code ASC",
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await?;
+
+    // 最近窗口内与本场累计的销量
+    let units: Vec<(i64, i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT oi.product_id,
+               SUM(CASE WHEN datetime(o.created_at, 'localtime') >= ? THEN oi.quantity ELSE 0 END),
+               SUM(oi.quantity)
+        FROM order_items oi
+        JOIN orders o ON oi.order_id = o.id
+        WHERE o.event_id = ? AND o.status != 'cancelled'
+        GROUP BY oi.product_id
+        "#,
+    )
+    .bind(window_start.format("%Y-%m-%d %H:%M:%S").to_string())
+    .bind(event_id)
+    .fetch_all(db)
+    .await?;
+    let units: HashMap<i64, (i64, i64)> = units
+        .into_iter()
+        .map(|(id, recent, total)| (id, (recent, total)))
+        .collect();
+
+    // 最近窗口内没有销售时，退回到本场截至目前的平均速度
+    let event_hours = hours_until(&open_windows(db, event_id, Some(now)).await?, Some(now));
+
+    let mut estimates: Vec<SelloutEstimate> = products
+        .into_iter()
+        .map(|p| {
+            let (recent, total) = units.get(&p.id).copied().unwrap_or((0, 0));
+            let (rate, basis) = if recent > 0 && window_hours > 0.0 {
+                (Some(recent as f64 / window_hours), "recent")
+            } else if total > 0 && event_hours > 0.0 {
+                (Some(total as f64 / event_hours), "event")
+            } else {
+                (None, "none")
+            };
+            let hours_to_sellout = rate.map(|r| p.current_stock.max(0) as f64 / r);
+            let sellout_at =
+                hours_to_sellout.map(|h| now + Duration::seconds((h * 3600.0).round() as i64));
+            SelloutEstimate {
+                product_id: p.id,
+                product_code: p.product_code,
+                name: p.name,
+                current_stock: p.current_stock,
+                recent_units: recent,
+                rate_per_hour: rate.map(round2),
+                basis,
+                hours_to_sellout: hours_to_sellout.map(round2),
+                estimated_sellout_at: sellout_at.map(|t| t.format("%Y-%m-%d %H:%M").to_string()),
+                sells_out_before_close: match (sellout_at, closes) {
+                    (Some(at), Some(close)) => Some(at <= close),
+                    (None, Some(_)) => Some(false),
+                    _ => None,
+                },
+            }
+        })
+        .collect();
+    // 预计售罄早的在前，无法估算的排最后
+    estimates.sort_by(|a, b| match (a.hours_to_sellout, b.hours_to_sellout) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+
+    Ok(SelloutReport {
+        event_id,
+        generated_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+        window_minutes,
+        closes_at: session.and_then(|s| s.closes_at.clone()),
+        products: estimates,
+    })
+}
+
+// ==========================================
+// 营业时间
+// ==========================================
+
+struct OpenWindow {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+}
+
+// 场次每个营业日的营业时间段 (本地时间)。开场/收摊时间未设置时，
+// 用当日首/末笔订单时间代替；既无营业时间也无订单的营业日不计。
+// until 不为空时，尚未开始的营业日不计，进行中的营业日截止到 until。
+async fn open_windows(
+    db: &SqlitePool,
+    event_id: i64,
+    until: Option<NaiveDateTime>,
+) -> Result<Vec<OpenWindow>, sqlx::Error> {
+    let order_span: Vec<(i64, String, String)> = sqlx::query_as(
+        r#"
+        SELECT session_id,
+               MIN(datetime(created_at, 'localtime')),
+               MAX(datetime(created_at, 'localtime'))
+        FROM orders
+        WHERE event_id = ? AND status != 'cancelled' AND session_id IS NOT NULL
+        GROUP BY session_id
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await?;
+    let order_span: HashMap<i64, (String, String)> = order_span
+        .into_iter()
+        .map(|(id, first, last)| (id, (first, last)))
+        .collect();
+
+    let mut windows = Vec::new();
+    for s in fetch_sessions(db, event_id).await {
+        let span = order_span.get(&s.id);
+        let start = s
+            .opens_at
+            .as_deref()
+            .and_then(|t| parse_local(&format!("{} {}", s.session_date, t)))
+            .or_else(|| span.and_then(|(first, _)| parse_local(first)));
+        let end = s
+            .closes_at
+            .as_deref()
+            .and_then(|t| parse_local(&format!("{} {}", s.session_date, t)))
+            .or_else(|| {
+                span.and_then(|(_, last)| parse_local(last))
+                    .map(|last| match until {
+                        // 进行中的营业日：末笔订单之后仍在营业
+                        Some(now) if last.date() == now.date() => now,
+                        _ => last,
+                    })
+            });
+        let (Some(start), Some(mut end)) = (start, end) else {
+            continue;
+        };
+        if s.closes_at.is_none() && hours_between(start, end) < MIN_DERIVED_HOURS {
+            end = start + Duration::minutes((MIN_DERIVED_HOURS * 60.0) as i64);
+        }
+        if let Some(now) = until {
+            if start >= now {
+                continue;
+            }
+            end = end.min(now);
+        }
+        windows.push(OpenWindow { start, end });
+    }
+    Ok(windows)
+}
+
+// 营业日设置了开场与收摊时间时的时间段
+fn session_window(
+    date: &str,
+    opens_at: Option<&str>,
+    closes_at: Option<&str>,
+) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let start = parse_local(&format!("{} {}", date, opens_at?))?;
+    let end = parse_local(&format!("{} {}", date, closes_at?))?;
+    (end > start).then_some((start, end))
+}
+
+// 截止到 at 为止的营业小时数；at 为空时为全部营业时间
+fn hours_until(windows: &[OpenWindow], at: Option<NaiveDateTime>) -> f64 {
+    windows
+        .iter()
+        .map(|w| {
+            let end = at.map_or(w.end, |at| at.min(w.end));
+            hours_between(w.start, end)
+        })
+        .sum()
+}
+
+fn hours_between(start: NaiveDateTime, end: NaiveDateTime) -> f64 {
+    ((end - start).num_seconds() as f64 / 3600.0).max(0.0)
+}
+
+// 接受 "YYYY-MM-DD HH:MM:SS" 与 "YYYY-MM-DD HH:MM"
+fn parse_local(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"))
+        .ok()
+}
+
+fn bad_request(msg: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response()
+}
+
+fn db_error(e: sqlx::Error) -> Response {
+    eprintln!("Forecast query error: {:?}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+}
+
+fn check_read_permission(claims: &Claims, event_id: i64) -> Result<(), (StatusCode, &'static str)> {
+    if claims.role == "admin"
+        || (claims.role == "vendor"
+            && (claims.access == "all" || claims.event_id == Some(event_id)))
+    {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "Access denied"))
+    }
+}
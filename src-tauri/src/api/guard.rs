@@ -7,7 +7,7 @@ use crate::{
 use axum::{
     async_trait,
     extract::FromRequestParts,
-    http::{header, request::Parts},
+    http::{header, request::Parts, StatusCode},
 };
 use jsonwebtoken::{decode, DecodingKey, Validation};
 
@@ -96,3 +96,12 @@ impl FromRequestParts<AppState> for AdminOnly {
         }
     }
 }
+
+// 跨场次数据：管理员或可访问全部场次的摊主
+pub(crate) fn check_global_permission(claims: &Claims) -> Result<(), (StatusCode, &'static str)> {
+    if claims.role == "admin" || (claims.role == "vendor" && claims.access == "all") {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "Access denied"))
+    }
+}
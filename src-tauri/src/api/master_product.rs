@@ -1,29 +1,56 @@
 use axum::{
-    extract::{Multipart, Path, Query, State},
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Json},
-    routing::{get, post, put}, // 注意：axum 中 update multipart 通常用 post 兼容性更好
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post, put}, // 注意：axum 中 update multipart 通常用 post 兼容性更好
     Router,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use sqlx::{query, query_as};
+use sqlx::{query, query_as, SqlitePool};
+use std::collections::HashMap;
 
 use crate::{
-    api::guard::AdminOnly,
-    db::models::MasterProduct,
+    api::{
+        guard::AdminOnly,
+        tag::{parse_tag_ids, tag_filter, tags_by_product},
+    },
+    db::models::{MasterProduct, MasterProductImage, ProductTranslation},
     state::AppState,
-    utils::file::{delete_file, save_upload_file},
+    utils::{
+        file::delete_file,
+        i18n::{localize, normalize_locale, RequestLocale},
+        image::{save_upload_image, StoredImage, MAX_UPLOAD_BYTES},
+        label::{build_sheet, LabelItem, LabelOptions},
+        search::{build_match_query, index_master_product},
+    },
 };
 
 pub fn router() -> Router<AppState> {
+    // 含图片的 multipart 接口：请求体上限放宽到单张图片上限 (axum 默认 2MB)
+    let uploads = Router::new()
+        .route("/", post(create_product))
+        .route("/:id", post(update_product).put(update_product)) // 更新商品详情 (含图片)
+        .route("/:id/images", post(add_images)) // 商品相册
+        .layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES));
+
     Router::new()
         // 公开接口
         .route("/", get(list_products))
+        .route("/search", get(search_products)) // 全文检索 (拼音/罗马音/前缀)
+        .route("/:id/images", get(list_images)) // 商品相册
         // 管理员接口
-        .route("/", post(create_product))
-        .route("/:id", post(update_product).put(update_product)) // 更新商品详情 (含图片)
+        .merge(uploads)
         .route("/:id/status", put(update_status)) // 上下架
+        .route("/:id/images/order", put(reorder_images))
+        .route("/:id/images/:image_id", delete(delete_image))
+        .route("/:id/tags", put(set_product_tags)) // 设置商品标签
+        .route("/:id/translations", put(set_translations)) // 设置多语言名称/描述
+        .route("/labels", post(generate_labels)) // 打印条码/二维码标签
+        .route("/:id", delete(delete_product)) // 删除 (仅限未被任何场次引用)
+        .route("/:id/archive", put(archive_product)) // 归档/取消归档
+        .route("/:id/merge", post(merge_product)) // 合并重复商品到当前商品
+        .route("/propagate", post(propagate_changes)) // 将商品修改同步到未结束场次 (可预览)
 }
 
 // ==========================================
@@ -31,27 +58,109 @@ pub fn router() -> Router<AppState> {
 // ==========================================
 #[derive(Deserialize)]
 struct ListQuery {
-    all: Option<bool>, // ?all=true 显示所有，否则只显示 is_active=true
+    all: Option<bool>,      // ?all=true 显示所有，否则只显示 is_active=true
+    archived: Option<bool>, // ?archived=true 只显示已归档商品 (回收/恢复用)
+    tags: Option<String>,   // ?tags=1,2,5 按标签筛选：同组任一命中，跨组全部命中
 }
 
 async fn list_products(
     State(state): State<AppState>,
+    RequestLocale(locales): RequestLocale,
     Query(params): Query<ListQuery>,
 ) -> impl IntoResponse {
     let show_all = params.all.unwrap_or(false);
+    let active_filter = if params.archived.unwrap_or(false) {
+        "AND mp.archived_at IS NOT NULL"
+    } else if show_all {
+        "AND mp.archived_at IS NULL"
+    } else {
+        "AND mp.is_active = 1 AND mp.archived_at IS NULL"
+    };
+
+    let tag_ids = parse_tag_ids(params.tags.as_deref());
+    let (tag_clause, tag_binds) = tag_filter(&state.db, &tag_ids, "mp.id").await;
 
-    let sql = if show_all {
-        "SELECT * FROM master_products ORDER BY product_code ASC"
+    let sql = format!(
+        "SELECT mp.* FROM master_products mp WHERE 1 = 1 {} {} ORDER BY mp.product_code ASC",
+        active_filter, tag_clause
+    );
+
+    let mut q = query_as::<_, MasterProduct>(&sql);
+    for id in &tag_binds {
+        q = q.bind(id);
+    }
+    let mut products: Vec<MasterProduct> = q.fetch_all(&state.db).await.unwrap_or_default();
+    attach_images(&state.db, &mut products).await;
+    attach_tags(&state.db, &mut products).await;
+    attach_translations(&state.db, &mut products, &locales).await;
+
+    Json(products)
+}
+
+// ==========================================
+// 1.1 检索商品 (Public)
+// ==========================================
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    all: Option<bool>,  // 同列表接口：默认只搜上架商品
+    limit: Option<i64>, // 默认 20，最多 100
+}
+
+async fn search_products(
+    State(state): State<AppState>,
+    RequestLocale(locales): RequestLocale,
+    Query(params): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let match_query = match build_match_query(&params.q) {
+        Some(q) => q,
+        None => return Json(Vec::<MasterProduct>::new()).into_response(),
+    };
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+    let active_filter = if params.all.unwrap_or(false) {
+        "AND mp.archived_at IS NULL"
     } else {
-        "SELECT * FROM master_products WHERE is_active = 1 ORDER BY product_code ASC"
+        "AND mp.is_active = 1 AND mp.archived_at IS NULL"
     };
 
-    let products: Vec<MasterProduct> = query_as::<_, MasterProduct>(sql)
+    // 排序：编号完全一致 > 编号前缀 > bm25 相关度 (编号、名称权重更高)
+    let sql = format!(
+        r#"
+        SELECT mp.*
+        FROM product_search
+        JOIN master_products mp ON mp.id = product_search.master_product_id
+        WHERE product_search MATCH ? {}
+        ORDER BY
+            (lower(mp.product_code) = lower(?)) DESC,
+            (lower(mp.product_code) LIKE lower(?) || '%') DESC,
+            bm25(product_search, 0.0, 10.0, 5.0, 2.0, 2.0, 1.0) ASC,
+            mp.product_code ASC
+        LIMIT ?
+        "#,
+        active_filter
+    );
+
+    let keyword = params.q.trim();
+    let result = query_as::<_, MasterProduct>(&sql)
+        .bind(&match_query)
+        .bind(keyword)
+        .bind(keyword)
+        .bind(limit)
         .fetch_all(&state.db)
-        .await
-        .unwrap_or_default();
+        .await;
 
-    Json(products)
+    match result {
+        Ok(mut products) => {
+            attach_images(&state.db, &mut products).await;
+            attach_tags(&state.db, &mut products).await;
+            attach_translations(&state.db, &mut products, &locales).await;
+            Json(products).into_response()
+        }
+        Err(e) => {
+            eprintln!("Search error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
 }
 
 // ==========================================
@@ -66,19 +175,21 @@ async fn create_product(
     let mut name = String::new();
     let mut default_price: f64 = 0.0;
     let mut category: Option<String> = None;
-    let mut image_path: Option<String> = None;
+    let mut description: Option<String> = None;
+    let mut translations: Vec<ProductTranslation> = Vec::new();
+    let mut images: Vec<StoredImage> = Vec::new();
 
     while let Some(field) = multipart.next_field().await.unwrap_or(None) {
         let field_name = field.name().unwrap_or("").to_string();
 
         if field_name == "image" {
-            // [文件处理] 存入 products 子目录
-            match save_upload_file(&state.upload_dir, field, Some("products")).await {
-                Ok(path) => image_path = Some(path),
+            // [文件处理] 校验并生成各尺寸，存入 products 子目录；可上传多张，第一张为封面
+            match save_upload_image(&state.upload_dir, field, "products").await {
+                Ok(image) => images.push(image),
                 Err(e) => {
                     eprintln!("Upload error: {}", e);
-                    return (StatusCode::INTERNAL_SERVER_ERROR, "File upload failed")
-                        .into_response();
+                    delete_stored_images(&state, &images).await;
+                    return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response();
                 }
             }
         } else {
@@ -91,6 +202,16 @@ async fn create_product(
                     default_price = value.parse().unwrap_or(0.0);
                 }
                 "category" => category = if value.is_empty() { None } else { Some(value) },
+                "description" => description = if value.is_empty() { None } else { Some(value) },
+                // JSON 数组: [{"locale": "en", "name": "...", "description": "..."}]
+                "translations" => match parse_translations(&value) {
+                    Ok(t) => translations = t,
+                    Err(e) => {
+                        delete_stored_images(&state, &images).await;
+                        return (StatusCode::BAD_REQUEST, Json(json!({"error": e})))
+                            .into_response();
+                    }
+                },
                 _ => {}
             }
         }
@@ -98,6 +219,7 @@ async fn create_product(
 
     // 必填项检查
     if product_code.is_empty() || name.is_empty() {
+        delete_stored_images(&state, &images).await;
         return (
             StatusCode::BAD_REQUEST,
             Json(json!({"error": "Code and Name are required"})),
@@ -105,25 +227,51 @@ async fn create_product(
             .into_response();
     }
 
-    // [修复] 使用 INSERT ... RETURNING * 原子地获取插入后的完整数据
-    let result = query_as::<_, MasterProduct>(
-        r#"
-        INSERT INTO master_products (product_code, name, default_price, category, image_url)
-        VALUES (?, ?, ?, ?, ?)
-        RETURNING *
-        "#,
-    )
-    .bind(product_code)
-    .bind(name)
-    .bind(default_price)
-    .bind(category)
-    .bind(image_path)
-    .fetch_one(&state.db)
+    // 商品与相册在同一事务中写入
+    let result: Result<i64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let cover = images.first();
+        let (id,): (i64,) = query_as(
+            r#"
+            INSERT INTO master_products
+                (product_code, name, default_price, category, description,
+                 image_url, image_md_url, image_sm_url)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING id
+            "#,
+        )
+        .bind(&product_code)
+        .bind(&name)
+        .bind(default_price)
+        .bind(&category)
+        .bind(&description)
+        .bind(cover.map(|i| &i.url))
+        .bind(cover.map(|i| &i.url_md))
+        .bind(cover.map(|i| &i.url_sm))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (position, image) in images.iter().enumerate() {
+            insert_image(&mut *tx, id, position as i64, image).await?;
+        }
+        replace_translations(&mut tx, id, &translations).await?;
+        tx.commit().await?;
+        Ok(id)
+    }
     .await;
 
     match result {
-        Ok(product) => (StatusCode::CREATED, Json(product)).into_response(),
+        Ok(id) => {
+            if let Err(e) = index_master_product(&state.db, id).await {
+                eprintln!("Search index error: {:?}", e);
+            }
+            match fetch_product_detail(&state, id).await {
+                Some(product) => (StatusCode::CREATED, Json(product)).into_response(),
+                None => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+            }
+        }
         Err(e) => {
+            delete_stored_images(&state, &images).await;
             // [错误处理] 检查是否是唯一性约束冲突
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed") {
@@ -165,24 +313,30 @@ async fn update_product(
     let mut name = old_product.name;
     let mut default_price = old_product.default_price;
     let mut category = old_product.category;
-    let mut image_path = old_product.image_url;
+    let mut description = old_product.description;
+    let mut translations: Option<Vec<ProductTranslation>> = None; // 未传表示不修改
+    let mut uploaded_image: Option<StoredImage> = None;
     let mut should_remove_image = false;
+    let mut expected_version: Option<i64> = None;
 
     // 3. 解析 Multipart
+    // "image" 字段替换封面；新图先落盘，旧图要等数据库写入成功后才删除，避免版本冲突时图片丢失
     while let Some(field) = multipart.next_field().await.unwrap_or(None) {
         let field_name = field.name().unwrap_or("").to_string();
 
         if field_name == "image" {
             // 上传新图
-            match save_upload_file(&state.upload_dir, field, Some("products")).await {
-                Ok(new_path) => {
-                    // 标记旧图删除
-                    if let Some(old_path) = &image_path {
-                        let _ = delete_file(&state.upload_dir, old_path).await;
+            match save_upload_image(&state.upload_dir, field, "products").await {
+                Ok(image) => {
+                    if let Some(previous) = uploaded_image.replace(image) {
+                        delete_stored_images(&state, &[previous]).await;
                     }
-                    image_path = Some(new_path);
                 }
-                Err(e) => eprintln!("Update upload error: {}", e),
+                Err(e) => {
+                    eprintln!("Update upload error: {}", e);
+                    delete_stored_images(&state, uploaded_image.as_slice()).await;
+                    return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response();
+                }
             }
         } else {
             let value = field.text().await.unwrap_or_default();
@@ -195,58 +349,121 @@ async fn update_product(
                     }
                 }
                 "category" => category = if value.is_empty() { None } else { Some(value) },
+                "description" => description = if value.is_empty() { None } else { Some(value) },
+                "translations" => match parse_translations(&value) {
+                    Ok(t) => translations = Some(t),
+                    Err(e) => {
+                        delete_stored_images(&state, uploaded_image.as_slice()).await;
+                        return (StatusCode::BAD_REQUEST, Json(json!({"error": e})))
+                            .into_response();
+                    }
+                },
                 "remove_image" => {
                     if value == "true" {
                         should_remove_image = true;
                     }
                 }
+                "version" => expected_version = value.parse().ok(),
                 _ => {}
             }
         }
     }
 
-    // 4. 处理显式删除图片
-    if should_remove_image {
-        if let Some(old_path) = &image_path {
-            let _ = delete_file(&state.upload_dir, old_path).await;
+    // 乐观锁：必须携带读取时的版本号，不一致说明期间被其他设备修改过
+    let expected_version = match expected_version {
+        Some(v) if v == old_product.version => v,
+        Some(_) => {
+            delete_stored_images(&state, uploaded_image.as_slice()).await;
+            return version_conflict(&state, id).await;
         }
-        image_path = None;
-    }
+        None => {
+            delete_stored_images(&state, uploaded_image.as_slice()).await;
+            return (
+                StatusCode::PRECONDITION_REQUIRED,
+                Json(json!({"error": "version is required"})),
+            )
+                .into_response();
+        }
+    };
 
-    // 5. 数据库更新
-    let result = query_as::<_, MasterProduct>(
-        r#"
-        UPDATE master_products 
-        SET product_code = ?, name = ?, default_price = ?, category = ?, image_url = ?
-        WHERE id = ?
-        RETURNING *
-        "#,
-    )
-    .bind(product_code)
-    .bind(name)
-    .bind(default_price)
-    .bind(category)
-    .bind(image_path)
-    .bind(id)
-    .fetch_one(&state.db)
+    // 4. 数据库更新 (封面字段由相册派生，见下方 refresh_cover)
+    // 商品字段与多语言内容在同一事务中写入，任一失败整体回滚
+    let result: Result<Option<MasterProduct>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let updated = query_as::<_, MasterProduct>(
+            r#"
+            UPDATE master_products 
+            SET product_code = ?, name = ?, default_price = ?, category = ?, description = ?,
+                version = version + 1
+            WHERE id = ? AND version = ?
+            RETURNING *
+            "#,
+        )
+        .bind(product_code)
+        .bind(name)
+        .bind(default_price)
+        .bind(category)
+        .bind(description)
+        .bind(id)
+        .bind(expected_version)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if updated.is_none() {
+            return Ok(None);
+        }
+        if let Some(translations) = &translations {
+            replace_translations(&mut tx, id, translations).await?;
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
     .await;
 
     match result {
-        Ok(product) => (StatusCode::OK, Json(product)).into_response(),
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            delete_stored_images(&state, uploaded_image.as_slice()).await;
+            return version_conflict(&state, id).await;
+        }
         Err(e) => {
+            delete_stored_images(&state, uploaded_image.as_slice()).await;
             // 同样也要检查唯一性冲突（如果修改了 product_code）
             let error_msg = e.to_string();
-            if error_msg.contains("UNIQUE constraint failed") {
+            return if error_msg.contains("UNIQUE constraint failed") {
                 (
                     StatusCode::CONFLICT,
                     Json(json!({"error": "Product code already exists"})),
                 )
                     .into_response()
             } else {
+                eprintln!("Update product error: {:?}", e);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
-            }
+            };
         }
     }
+
+    // 5. 写入成功后再处理封面：新上传替换封面 / 显式删除封面 (下一张自动成为封面)
+    if let Some(image) = &uploaded_image {
+        replace_cover_image(&state, id, image).await;
+    } else if should_remove_image {
+        if let Some(cover) = fetch_images(&state.db, id).await.first() {
+            let _ = query("DELETE FROM master_product_images WHERE id = ?")
+                .bind(cover.id)
+                .execute(&state.db)
+                .await;
+            delete_image_files(&state, cover).await;
+        }
+        refresh_cover(&state.db, id).await;
+    }
+
+    if let Err(e) = index_master_product(&state.db, id).await {
+        eprintln!("Search index error: {:?}", e);
+    }
+
+    match fetch_product_detail(&state, id).await {
+        Some(product) => (StatusCode::OK, Json(product)).into_response(),
+        None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+    }
 }
 
 // ==========================================
@@ -255,6 +472,7 @@ async fn update_product(
 #[derive(Deserialize)]
 struct UpdateStatusRequest {
     is_active: bool,
+    version: Option<i64>, // 乐观锁：必须携带读取时的版本号
 }
 
 async fn update_status(
@@ -263,21 +481,1201 @@ async fn update_status(
     Path(id): Path<i64>,
     Json(payload): Json<UpdateStatusRequest>,
 ) -> impl IntoResponse {
+    let Some(expected_version) = payload.version else {
+        return (
+            StatusCode::PRECONDITION_REQUIRED,
+            Json(json!({"error": "version is required"})),
+        )
+            .into_response();
+    };
     let result = query_as::<_, MasterProduct>(
         r#"
         UPDATE master_products 
-        SET is_active = ? 
-        WHERE id = ?
+        SET is_active = ?, version = version + 1
+        WHERE id = ? AND version = ?
         RETURNING *
         "#,
     )
     .bind(payload.is_active)
     .bind(id)
-    .fetch_one(&state.db)
+    .bind(expected_version)
+    .fetch_optional(&state.db)
     .await;
 
     match result {
-        Ok(product) => (StatusCode::OK, Json(product)).into_response(),
+        Ok(Some(_)) => match fetch_product_detail(&state, id).await {
+            Some(product) => (StatusCode::OK, Json(product)).into_response(),
+            None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+        },
+        Ok(None) => version_conflict(&state, id).await,
         Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
     }
 }
+
+// 409 Conflict：附带当前最新数据；商品不存在时返回 404
+async fn version_conflict(state: &AppState, id: i64) -> Response {
+    match fetch_product_detail(state, id).await {
+        Some(current) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Product has been modified by another device, please reload and retry",
+                "current": current
+            })),
+        )
+            .into_response(),
+        None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+    }
+}
+
+// ==========================================
+// 5. 商品相册 (Gallery)
+// ==========================================
+// 相册按 position 排序，第一张即封面；封面各尺寸冗余在 master_products 上
+
+async fn list_images(State(state): State<AppState>, Path(id): Path<i64>) -> impl IntoResponse {
+    Json(fetch_images(&state.db, id).await)
+}
+
+// 追加图片 (可一次上传多张 "image" 字段)
+async fn add_images(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let exists: Option<(i64,)> = query_as("SELECT id FROM master_products WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .unwrap_or(None);
+    if exists.is_none() {
+        return (StatusCode::NOT_FOUND, "Product not found").into_response();
+    }
+
+    let mut images: Vec<StoredImage> = Vec::new();
+    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
+        if field.name() != Some("image") {
+            continue;
+        }
+        match save_upload_image(&state.upload_dir, field, "products").await {
+            Ok(image) => images.push(image),
+            Err(e) => {
+                delete_stored_images(&state, &images).await;
+                return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response();
+            }
+        }
+    }
+
+    if images.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "No image in request"})),
+        )
+            .into_response();
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let (next_position,): (i64,) = query_as(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM master_product_images WHERE master_product_id = ?",
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        for (offset, image) in images.iter().enumerate() {
+            insert_image(&mut *tx, id, next_position + offset as i64, image).await?;
+        }
+        query("UPDATE master_products SET version = version + 1 WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Add images error: {:?}", e);
+        delete_stored_images(&state, &images).await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+    }
+
+    refresh_cover(&state.db, id).await;
+    match fetch_product_detail(&state, id).await {
+        Some(product) => (StatusCode::CREATED, Json(product)).into_response(),
+        None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ReorderImagesRequest {
+    image_ids: Vec<i64>, // 新顺序，第一张成为封面；未列出的图片保持原相对顺序排在后面
+}
+
+async fn reorder_images(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+    Json(payload): Json<ReorderImagesRequest>,
+) -> impl IntoResponse {
+    let current = fetch_images(&state.db, id).await;
+    if payload
+        .image_ids
+        .iter()
+        .any(|image_id| !current.iter().any(|img| img.id == *image_id))
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Image does not belong to this product"})),
+        )
+            .into_response();
+    }
+
+    let mut ordered: Vec<i64> = Vec::with_capacity(current.len());
+    for image_id in payload
+        .image_ids
+        .iter()
+        .chain(current.iter().map(|img| &img.id))
+    {
+        if !ordered.contains(image_id) {
+            ordered.push(*image_id);
+        }
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        for (position, image_id) in ordered.iter().enumerate() {
+            query("UPDATE master_product_images SET position = ? WHERE id = ?")
+                .bind(position as i64)
+                .bind(image_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        query("UPDATE master_products SET version = version + 1 WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Reorder images error: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+    }
+
+    refresh_cover(&state.db, id).await;
+    match fetch_product_detail(&state, id).await {
+        Some(product) => (StatusCode::OK, Json(product)).into_response(),
+        None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+    }
+}
+
+async fn delete_image(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path((id, image_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let image = match fetch_images(&state.db, id)
+        .await
+        .into_iter()
+        .find(|img| img.id == image_id)
+    {
+        Some(img) => img,
+        None => return (StatusCode::NOT_FOUND, "Image not found").into_response(),
+    };
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        query("DELETE FROM master_product_images WHERE id = ?")
+            .bind(image_id)
+            .execute(&mut *tx)
+            .await?;
+        query("UPDATE master_products SET version = version + 1 WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+    if let Err(e) = result {
+        eprintln!("Delete image error: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+    }
+    delete_image_files(&state, &image).await;
+
+    refresh_cover(&state.db, id).await;
+    match fetch_product_detail(&state, id).await {
+        Some(product) => (StatusCode::OK, Json(product)).into_response(),
+        None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+    }
+}
+
+// ==========================================
+// 6. 设置商品标签 (Admin Only - JSON)
+// ==========================================
+#[derive(Deserialize)]
+struct SetTagsRequest {
+    tag_ids: Vec<i64>, // 覆盖式设置，传空数组即清空
+}
+
+async fn set_product_tags(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+    Json(payload): Json<SetTagsRequest>,
+) -> impl IntoResponse {
+    let result: Result<bool, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let exists: Option<(i64,)> = query_as("SELECT id FROM master_products WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if exists.is_none() {
+            return Ok(false);
+        }
+
+        query("DELETE FROM master_product_tags WHERE master_product_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        for tag_id in &payload.tag_ids {
+            // 不存在的标签直接忽略
+            query(
+                r#"
+                INSERT OR IGNORE INTO master_product_tags (master_product_id, tag_id)
+                SELECT ?, id FROM tags WHERE id = ?
+                "#,
+            )
+            .bind(id)
+            .bind(tag_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        query("UPDATE master_products SET version = version + 1 WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {
+            if let Err(e) = index_master_product(&state.db, id).await {
+                eprintln!("Search index error: {:?}", e);
+            }
+            match fetch_product_detail(&state, id).await {
+                Some(product) => (StatusCode::OK, Json(product)).into_response(),
+                None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+            }
+        }
+        Ok(false) => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+        Err(e) => {
+            eprintln!("Set tags error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 7. 生成商品标签页 (Admin Only - JSON → PDF/SVG)
+// ==========================================
+#[derive(Deserialize)]
+struct LabelRequest {
+    #[serde(default)]
+    product_ids: Vec<i64>, // 按传入顺序排版；为空时打印全部上架商品
+    quantities: Option<HashMap<i64, usize>>, // 按商品指定份数，未指定的用 copies
+    #[serde(flatten)]
+    options: LabelOptions,
+}
+
+async fn generate_labels(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Json(payload): Json<LabelRequest>,
+) -> impl IntoResponse {
+    let products: Vec<MasterProduct> = if payload.product_ids.is_empty() {
+        query_as(
+            "SELECT * FROM master_products WHERE is_active = 1 AND archived_at IS NULL ORDER BY product_code ASC",
+        )
+            .fetch_all(&state.db)
+            .await
+            .unwrap_or_default()
+    } else {
+        let placeholders = vec!["?"; payload.product_ids.len()].join(",");
+        let sql = format!(
+            "SELECT * FROM master_products WHERE id IN ({})",
+            placeholders
+        );
+        let mut q = query_as::<_, MasterProduct>(&sql);
+        for id in &payload.product_ids {
+            q = q.bind(id);
+        }
+        let found = q.fetch_all(&state.db).await.unwrap_or_default();
+        payload
+            .product_ids
+            .iter()
+            .filter_map(|id| found.iter().find(|p| p.id == *id).cloned())
+            .collect()
+    };
+
+    let default_copies = payload.options.copies.unwrap_or(1);
+    let items: Vec<LabelItem> = products
+        .into_iter()
+        .map(|p| LabelItem {
+            copies: payload
+                .quantities
+                .as_ref()
+                .and_then(|q| q.get(&p.id).copied())
+                .unwrap_or(default_copies),
+            code: p.product_code,
+            name: p.name,
+            price: p.default_price,
+        })
+        .collect();
+
+    match build_sheet(&payload.options, &items) {
+        Ok(sheet) => sheet.into_download("labels"),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    }
+}
+
+// ==========================================
+// 8. 归档 / 取消归档 (Admin Only - JSON)
+// ==========================================
+// 归档商品从商品库、检索和上架选择中隐藏，历史场次与订单不受影响
+#[derive(Deserialize)]
+struct ArchiveRequest {
+    archived: bool,
+    version: Option<i64>, // 乐观锁：必须携带读取时的版本号
+}
+
+async fn archive_product(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+    Json(payload): Json<ArchiveRequest>,
+) -> impl IntoResponse {
+    let Some(expected_version) = payload.version else {
+        return (
+            StatusCode::PRECONDITION_REQUIRED,
+            Json(json!({"error": "version is required"})),
+        )
+            .into_response();
+    };
+    let result = query(
+        r#"
+        UPDATE master_products
+        SET archived_at = CASE WHEN ? THEN COALESCE(archived_at, CURRENT_TIMESTAMP) ELSE NULL END,
+            version = version + 1
+        WHERE id = ? AND version = ?
+        "#,
+    )
+    .bind(payload.archived)
+    .bind(id)
+    .bind(expected_version)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(r) if r.rows_affected() > 0 => match fetch_product_detail(&state, id).await {
+            Some(product) => (StatusCode::OK, Json(product)).into_response(),
+            None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+        },
+        Ok(_) => version_conflict(&state, id).await,
+        Err(e) => {
+            eprintln!("Archive product error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 9. 删除商品 (Admin Only)
+// ==========================================
+// 已被场次引用的商品不能删除 (订单明细依赖场次商品)，返回 409 并列出引用的场次，
+// 前端可引导用户改为归档或合并
+async fn delete_product(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let images = fetch_images(&state.db, id).await;
+
+    let result: Result<Result<(), Vec<String>>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let events: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT e.name FROM products p
+            JOIN events e ON p.event_id = e.id
+            WHERE p.master_product_id = ?
+            ORDER BY e.event_date DESC
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if !events.is_empty() {
+            return Ok(Err(events));
+        }
+
+        // 相册与标签关联随外键级联删除
+        let deleted = query("DELETE FROM master_products WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        query("DELETE FROM product_search WHERE master_product_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Ok(()))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(())) => {
+            for image in &images {
+                delete_image_files(&state, image).await;
+            }
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(Err(events)) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Product is used by events, archive or merge it instead",
+                "events": events
+            })),
+        )
+            .into_response(),
+        Err(sqlx::Error::RowNotFound) => {
+            (StatusCode::NOT_FOUND, "Product not found").into_response()
+        }
+        Err(e) => {
+            eprintln!("Delete product error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 10. 合并重复商品 (Admin Only - JSON)
+// ==========================================
+// 把 duplicate_id 合并到路径中的商品 (保留方)，单个事务内完成：
+// - 场次商品改指向保留方；同一场次两者都已上架时库存相加，订单/盘点明细改指向保留方的场次商品
+// - 标签取并集；保留方没有图片时接收重复商品的相册，否则删除重复商品的图片
+// - 删除重复商品
+#[derive(Deserialize)]
+struct MergeRequest {
+    duplicate_id: i64,
+    version: Option<i64>, // 保留方的版本号，必填 (缺失返回 428)
+}
+
+#[derive(Default, Serialize)]
+struct MergeSummary {
+    moved_event_products: u64,    // 直接改指向的场次商品数
+    combined_event_products: u64, // 同场次合并库存的场次商品数
+    moved_order_items: u64,       // 改指向的订单明细数
+}
+
+async fn merge_product(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+    Json(payload): Json<MergeRequest>,
+) -> impl IntoResponse {
+    let duplicate_id = payload.duplicate_id;
+    let Some(expected_version) = payload.version else {
+        return (
+            StatusCode::PRECONDITION_REQUIRED,
+            Json(json!({"error": "version is required"})),
+        )
+            .into_response();
+    };
+    if duplicate_id == id {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Cannot merge a product into itself"})),
+        )
+            .into_response();
+    }
+
+    let keep = match fetch_product_detail(&state, id).await {
+        Some(p) => p,
+        None => return (StatusCode::NOT_FOUND, "Product not found").into_response(),
+    };
+    if expected_version != keep.version {
+        return version_conflict(&state, id).await;
+    }
+    let duplicate = match fetch_product_detail(&state, duplicate_id).await {
+        Some(p) => p,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "Duplicate product not found"})),
+            )
+                .into_response()
+        }
+    };
+    // 保留方没有图片时接收重复商品的相册
+    let adopt_images = keep.images.is_empty();
+
+    let result: Result<Option<MergeSummary>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let mut summary = MergeSummary::default();
+
+        // 先在事务内按版本号递增保留方，期间被并发修改则不做任何合并
+        let bumped = query("UPDATE master_products SET version = version + 1 WHERE id = ? AND version = ?")
+            .bind(id)
+            .bind(expected_version)
+            .execute(&mut *tx)
+            .await?;
+        if bumped.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let dup_products: Vec<(i64, i64, i64, i64)> = query_as(
+            "SELECT id, event_id, initial_stock, current_stock FROM products WHERE master_product_id = ?",
+        )
+        .bind(duplicate_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for (dup_pid, event_id, initial_stock, current_stock) in dup_products {
+            let existing: Option<(i64,)> = query_as(
+                "SELECT id FROM products WHERE event_id = ? AND master_product_id = ? ORDER BY id LIMIT 1",
+            )
+            .bind(event_id)
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            match existing {
+                Some((keep_pid,)) => {
+                    query(
+                        r#"
+                        UPDATE products
+                        SET initial_stock = initial_stock + ?, current_stock = current_stock + ?,
+                            version = version + 1
+                        WHERE id = ?
+                        "#,
+                    )
+                    .bind(initial_stock)
+                    .bind(current_stock)
+                    .bind(keep_pid)
+                    .execute(&mut *tx)
+                    .await?;
+                    summary.moved_order_items +=
+                        query("UPDATE order_items SET product_id = ? WHERE product_id = ?")
+                            .bind(keep_pid)
+                            .bind(dup_pid)
+                            .execute(&mut *tx)
+                            .await?
+                            .rows_affected();
+                    // 同一次盘点里两者都有记录时保留原记录 (快照数据，不影响库存)
+                    query("UPDATE OR IGNORE stocktake_items SET product_id = ? WHERE product_id = ?")
+                        .bind(keep_pid)
+                        .bind(dup_pid)
+                        .execute(&mut *tx)
+                        .await?;
+                    // 每日开场/收摊库存随库存一起合并，两边都未记录的仍保持未记录
+                    query(
+                        r#"
+                        INSERT INTO event_session_stock (session_id, product_id, opening_stock, closing_stock)
+                        SELECT session_id, ?, opening_stock, closing_stock
+                        FROM event_session_stock WHERE product_id = ?
+                        ON CONFLICT (session_id, product_id) DO UPDATE SET
+                            opening_stock = CASE
+                                WHEN opening_stock IS NULL AND excluded.opening_stock IS NULL THEN NULL
+                                ELSE COALESCE(opening_stock, 0) + COALESCE(excluded.opening_stock, 0)
+                            END,
+                            closing_stock = CASE
+                                WHEN closing_stock IS NULL AND excluded.closing_stock IS NULL THEN NULL
+                                ELSE COALESCE(closing_stock, 0) + COALESCE(excluded.closing_stock, 0)
+                            END
+                        "#,
+                    )
+                    .bind(keep_pid)
+                    .bind(dup_pid)
+                    .execute(&mut *tx)
+                    .await?;
+                    query("DELETE FROM products WHERE id = ?")
+                        .bind(dup_pid)
+                        .execute(&mut *tx)
+                        .await?;
+                    summary.combined_event_products += 1;
+                }
+                None => {
+                    // 名称/编号是场次商品上的冗余字段，同步为保留方；售价属于场次设置，保持不变
+                    query(
+                        r#"
+                        UPDATE products
+                        SET master_product_id = ?, product_code = ?, name = ?, version = version + 1
+                        WHERE id = ?
+                        "#,
+                    )
+                    .bind(id)
+                    .bind(&keep.product_code)
+                    .bind(&keep.name)
+                    .bind(dup_pid)
+                    .execute(&mut *tx)
+                    .await?;
+                    summary.moved_event_products += 1;
+                }
+            }
+        }
+
+        query(
+            r#"
+            INSERT OR IGNORE INTO master_product_tags (master_product_id, tag_id)
+            SELECT ?, tag_id FROM master_product_tags WHERE master_product_id = ?
+            "#,
+        )
+        .bind(id)
+        .bind(duplicate_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // 保留方缺少的语言从重复商品补上
+        query(
+            r#"
+            INSERT OR IGNORE INTO master_product_translations
+                (master_product_id, locale, name, description)
+            SELECT ?, locale, name, description
+            FROM master_product_translations WHERE master_product_id = ?
+            "#,
+        )
+        .bind(id)
+        .bind(duplicate_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if adopt_images {
+            query("UPDATE master_product_images SET master_product_id = ? WHERE master_product_id = ?")
+                .bind(id)
+                .bind(duplicate_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        query("DELETE FROM master_products WHERE id = ?")
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+        query("DELETE FROM product_search WHERE master_product_id = ?")
+            .bind(duplicate_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(summary))
+    }
+    .await;
+
+    let summary = match result {
+        Ok(Some(s)) => s,
+        Ok(None) => return version_conflict(&state, id).await,
+        Err(e) => {
+            eprintln!("Merge product error: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+        }
+    };
+
+    // 事务提交后再处理文件：未被接收的图片成为孤儿，直接删除
+    if adopt_images {
+        refresh_cover(&state.db, id).await;
+    } else {
+        for image in &duplicate.images {
+            delete_image_files(&state, image).await;
+        }
+    }
+    if let Err(e) = index_master_product(&state.db, id).await {
+        eprintln!("Search index error: {:?}", e);
+    }
+
+    match fetch_product_detail(&state, id).await {
+        Some(product) => (
+            StatusCode::OK,
+            Json(json!({
+                "product": product,
+                "merged_from": duplicate.product_code,
+                "summary": summary
+            })),
+        )
+            .into_response(),
+        None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+    }
+}
+
+// ==========================================
+// 11. 同步商品修改到场次商品 (Admin Only - JSON)
+// ==========================================
+// 场次商品的名称/编号/售价是上架时的快照。这里把全局商品的当前值推送到
+// 未结束 (status != '已结束') 且不在回收站中的场次商品；订单明细有自己的快照，不受影响。
+// dry_run = true 时只返回差异预览。
+#[derive(Deserialize)]
+struct PropagateRequest {
+    #[serde(default)]
+    product_ids: Vec<i64>, // 全局商品 ID；为空表示全部
+    #[serde(default)]
+    event_ids: Vec<i64>, // 只同步这些场次；为空表示全部未结束场次
+    #[serde(default)]
+    include_price: bool, // 是否同时把默认价格同步为场次售价
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct PropagationChange {
+    product_id: i64, // 场次商品 ID
+    event_id: i64,
+    event_name: String,
+    master_product_id: i64,
+    old_code: String,
+    new_code: String,
+    old_name: String,
+    new_name: String,
+    old_price: f64,
+    new_price: f64,
+}
+
+async fn propagate_changes(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Json(payload): Json<PropagateRequest>,
+) -> impl IntoResponse {
+    let mut filters = String::new();
+    if !payload.product_ids.is_empty() {
+        filters.push_str(&format!(
+            " AND mp.id IN ({})",
+            vec!["?"; payload.product_ids.len()].join(",")
+        ));
+    }
+    if !payload.event_ids.is_empty() {
+        filters.push_str(&format!(
+            " AND e.id IN ({})",
+            vec!["?"; payload.event_ids.len()].join(",")
+        ));
+    }
+    let price_diff = if payload.include_price {
+        "OR p.price != mp.default_price"
+    } else {
+        ""
+    };
+    let sql = format!(
+        r#"
+        SELECT p.id AS product_id, e.id AS event_id, e.name AS event_name,
+               mp.id AS master_product_id,
+               p.product_code AS old_code, mp.product_code AS new_code,
+               p.name AS old_name, mp.name AS new_name,
+               p.price AS old_price,
+               CASE WHEN ? THEN mp.default_price ELSE p.price END AS new_price
+        FROM products p
+        JOIN events e ON p.event_id = e.id
+        JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE e.status != '已结束' AND e.deleted_at IS NULL
+          AND (p.name != mp.name OR p.product_code != mp.product_code {})
+          {}
+        ORDER BY e.event_date ASC, e.id ASC, p.sort_order ASC, p.id ASC
+        "#,
+        price_diff, filters
+    );
+
+    let result: Result<Vec<PropagationChange>, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let mut q = query_as::<_, PropagationChange>(&sql).bind(payload.include_price);
+        for id in payload.product_ids.iter().chain(&payload.event_ids) {
+            q = q.bind(id);
+        }
+        let changes = q.fetch_all(&mut *tx).await?;
+
+        if !payload.dry_run {
+            for change in &changes {
+                query(
+                    r#"
+                    UPDATE products
+                    SET product_code = ?, name = ?, price = ?, version = version + 1
+                    WHERE id = ?
+                    "#,
+                )
+                .bind(&change.new_code)
+                .bind(&change.new_name)
+                .bind(change.new_price)
+                .bind(change.product_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+        }
+        Ok(changes)
+    }
+    .await;
+
+    match result {
+        Ok(changes) => Json(json!({
+            "dry_run": payload.dry_run,
+            "updated": if payload.dry_run { 0 } else { changes.len() },
+            "changes": changes
+        }))
+        .into_response(),
+        Err(e) => {
+            eprintln!("Propagate changes error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 12. 设置多语言名称/描述 (Admin Only - JSON)
+// ==========================================
+#[derive(Deserialize)]
+struct SetTranslationsRequest {
+    translations: Vec<ProductTranslation>, // 覆盖式设置，传空数组即清空
+}
+
+async fn set_translations(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+    Json(payload): Json<SetTranslationsRequest>,
+) -> impl IntoResponse {
+    let translations = match normalize_translations(payload.translations) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+
+    let result: Result<bool, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let exists: Option<(i64,)> = query_as("SELECT id FROM master_products WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if exists.is_none() {
+            return Ok(false);
+        }
+        replace_translations(&mut tx, id, &translations).await?;
+        query("UPDATE master_products SET version = version + 1 WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {
+            if let Err(e) = index_master_product(&state.db, id).await {
+                eprintln!("Search index error: {:?}", e);
+            }
+            match fetch_product_detail(&state, id).await {
+                Some(product) => (StatusCode::OK, Json(product)).into_response(),
+                None => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+            }
+        }
+        Ok(false) => (StatusCode::NOT_FOUND, "Product not found").into_response(),
+        Err(e) => {
+            eprintln!("Set translations error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 辅助：多语言
+// ==========================================
+fn parse_translations(value: &str) -> Result<Vec<ProductTranslation>, String> {
+    if value.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let translations: Vec<ProductTranslation> =
+        serde_json::from_str(value).map_err(|_| "Invalid translations JSON".to_string())?;
+    normalize_translations(translations)
+}
+
+// 规范语言代码、去掉空白项；同一语言重复时报错
+fn normalize_translations(
+    translations: Vec<ProductTranslation>,
+) -> Result<Vec<ProductTranslation>, String> {
+    let mut result: Vec<ProductTranslation> = Vec::new();
+    for mut t in translations {
+        t.locale =
+            normalize_locale(&t.locale).ok_or_else(|| format!("Invalid locale: {}", t.locale))?;
+        t.name = t.name.filter(|v| !v.trim().is_empty());
+        t.description = t.description.filter(|v| !v.trim().is_empty());
+        if t.name.is_none() && t.description.is_none() {
+            continue;
+        }
+        if result.iter().any(|r| r.locale == t.locale) {
+            return Err(format!("Duplicate locale: {}", t.locale));
+        }
+        result.push(t);
+    }
+    Ok(result)
+}
+
+pub(crate) async fn replace_translations(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    product_id: i64,
+    translations: &[ProductTranslation],
+) -> Result<(), sqlx::Error> {
+    query("DELETE FROM master_product_translations WHERE master_product_id = ?")
+        .bind(product_id)
+        .execute(&mut **tx)
+        .await?;
+    for t in translations {
+        query(
+            r#"
+            INSERT INTO master_product_translations (master_product_id, locale, name, description)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(product_id)
+        .bind(&t.locale)
+        .bind(&t.name)
+        .bind(&t.description)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+// product_ids 为 None 时读取全部商品的翻译
+pub(crate) async fn fetch_translations(
+    db: &SqlitePool,
+    product_ids: Option<&[i64]>,
+) -> HashMap<i64, Vec<ProductTranslation>> {
+    let id_filter = match product_ids {
+        Some([]) => return HashMap::new(),
+        Some(ids) => format!(
+            "WHERE master_product_id IN ({})",
+            vec!["?"; ids.len()].join(",")
+        ),
+        None => String::new(),
+    };
+    let sql = format!(
+        r#"
+        SELECT master_product_id, locale, name, description
+        FROM master_product_translations
+        {}
+        ORDER BY locale ASC
+        "#,
+        id_filter
+    );
+    let mut q = query_as::<_, ProductTranslation>(&sql);
+    for id in product_ids.unwrap_or_default() {
+        q = q.bind(id);
+    }
+    let rows: Vec<ProductTranslation> = q.fetch_all(db).await.unwrap_or_default();
+
+    let mut map: HashMap<i64, Vec<ProductTranslation>> = HashMap::new();
+    for t in rows {
+        map.entry(t.master_product_id).or_default().push(t);
+    }
+    map
+}
+
+// 填充翻译列表，并按请求语言解析展示名称/描述
+pub(crate) async fn attach_translations(
+    db: &SqlitePool,
+    products: &mut [MasterProduct],
+    locales: &[String],
+) {
+    let ids: Vec<i64> = products.iter().map(|p| p.id).collect();
+    let mut translations = fetch_translations(db, Some(&ids)).await;
+    for product in products.iter_mut() {
+        product.translations = translations.remove(&product.id).unwrap_or_default();
+        let (name, description) = localize(
+            locales,
+            &product.translations,
+            &product.name,
+            product.description.as_deref(),
+        );
+        product.display_name = Some(name);
+        product.display_description = description;
+    }
+}
+
+// ==========================================
+// 辅助：相册读写
+// ==========================================
+async fn fetch_images(db: &SqlitePool, product_id: i64) -> Vec<MasterProductImage> {
+    query_as::<_, MasterProductImage>(
+        r#"
+        SELECT id, master_product_id, position, url, url_md, url_sm, width, height
+        FROM master_product_images
+        WHERE master_product_id = ?
+        ORDER BY position ASC, id ASC
+        "#,
+    )
+    .bind(product_id)
+    .fetch_all(db)
+    .await
+    .unwrap_or_default()
+}
+
+// 批量填充相册，一次查询避免 N+1
+pub(crate) async fn attach_images(db: &SqlitePool, products: &mut [MasterProduct]) {
+    if products.is_empty() {
+        return;
+    }
+    let ids: Vec<i64> = products.iter().map(|p| p.id).collect();
+    let sql = format!(
+        r#"
+        SELECT id, master_product_id, position, url, url_md, url_sm, width, height
+        FROM master_product_images
+        WHERE master_product_id IN ({})
+        ORDER BY master_product_id ASC, position ASC, id ASC
+        "#,
+        ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+    );
+    let mut q = query_as::<_, MasterProductImage>(&sql);
+    for id in &ids {
+        q = q.bind(id);
+    }
+    let images: Vec<MasterProductImage> = q.fetch_all(db).await.unwrap_or_default();
+
+    let mut map: HashMap<i64, Vec<MasterProductImage>> = HashMap::new();
+    for image in images {
+        map.entry(image.master_product_id).or_default().push(image);
+    }
+    for product in products.iter_mut() {
+        product.images = map.remove(&product.id).unwrap_or_default();
+    }
+}
+
+// 查询单个商品，附带相册与标签
+async fn fetch_product_detail(state: &AppState, id: i64) -> Option<MasterProduct> {
+    let mut product: MasterProduct = query_as("SELECT * FROM master_products WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .unwrap_or(None)?;
+    product.images = fetch_images(&state.db, id).await;
+    product.tags = tags_by_product(&state.db, Some(&[id]))
+        .await
+        .remove(&id)
+        .unwrap_or_default();
+    product.translations = fetch_translations(&state.db, Some(&[id]))
+        .await
+        .remove(&id)
+        .unwrap_or_default();
+    Some(product)
+}
+
+pub(crate) async fn attach_tags(db: &SqlitePool, products: &mut [MasterProduct]) {
+    let ids: Vec<i64> = products.iter().map(|p| p.id).collect();
+    let mut tags = tags_by_product(db, Some(&ids)).await;
+    for product in products.iter_mut() {
+        product.tags = tags.remove(&product.id).unwrap_or_default();
+    }
+}
+
+pub(crate) async fn insert_image<'e, E>(
+    executor: E,
+    product_id: i64,
+    position: i64,
+    image: &StoredImage,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    query(
+        r#"
+        INSERT INTO master_product_images
+            (master_product_id, position, url, url_md, url_sm, width, height)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(product_id)
+    .bind(position)
+    .bind(&image.url)
+    .bind(&image.url_md)
+    .bind(&image.url_sm)
+    .bind(image.width)
+    .bind(image.height)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+// 用新图替换封面 (相册为空时作为第一张)，成功后删除旧封面文件；失败时清理新图
+pub(crate) async fn replace_cover_image(state: &AppState, product_id: i64, image: &StoredImage) {
+    let cover = fetch_images(&state.db, product_id).await.into_iter().next();
+    let res = match &cover {
+        Some(cover) => query(
+            r#"
+            UPDATE master_product_images
+            SET url = ?, url_md = ?, url_sm = ?, width = ?, height = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&image.url)
+        .bind(&image.url_md)
+        .bind(&image.url_sm)
+        .bind(image.width)
+        .bind(image.height)
+        .bind(cover.id)
+        .execute(&state.db)
+        .await
+        .map(|_| ()),
+        None => insert_image(&state.db, product_id, 0, image).await,
+    };
+
+    match res {
+        Ok(()) => {
+            if let Some(cover) = &cover {
+                delete_image_files(state, cover).await;
+            }
+        }
+        Err(e) => {
+            eprintln!("Update cover image error: {:?}", e);
+            delete_stored_images(state, std::slice::from_ref(image)).await;
+        }
+    }
+    refresh_cover(&state.db, product_id).await;
+}
+
+// 将相册第一张同步为商品封面 (相册为空时清空封面)
+pub(crate) async fn refresh_cover(db: &SqlitePool, product_id: i64) {
+    let res = query(
+        r#"
+        UPDATE master_products
+        SET image_url = (SELECT url FROM master_product_images
+                         WHERE master_product_id = ?1 ORDER BY position, id LIMIT 1),
+            image_md_url = (SELECT url_md FROM master_product_images
+                            WHERE master_product_id = ?1 ORDER BY position, id LIMIT 1),
+            image_sm_url = (SELECT url_sm FROM master_product_images
+                            WHERE master_product_id = ?1 ORDER BY position, id LIMIT 1)
+        WHERE id = ?1
+        "#,
+    )
+    .bind(product_id)
+    .execute(db)
+    .await;
+
+    if let Err(e) = res {
+        eprintln!("Refresh cover error: {:?}", e);
+    }
+}
+
+// 删除刚上传但未入库的图片 (各尺寸)
+async fn delete_stored_images(state: &AppState, images: &[StoredImage]) {
+    for image in images {
+        for url in image.all_urls() {
+            let _ = delete_file(&state.upload_dir, url).await;
+        }
+    }
+}
+
+// 删除相册图片的物理文件 (旧数据各尺寸可能指向同一文件)
+async fn delete_image_files(state: &AppState, image: &MasterProductImage) {
+    let mut urls = vec![&image.url, &image.url_md, &image.url_sm];
+    urls.dedup();
+    for url in urls {
+        let _ = delete_file(&state.upload_dir, url).await;
+    }
+}
@@ -2,15 +2,26 @@ use crate::state::AppState;
 use axum::Router;
 
 mod admin;
+mod archive;
+mod association;
 mod auth;
+mod compare;
 mod event;
+mod event_settings;
+mod forecast;
 pub mod guard;
 mod info;
 mod master_product;
-mod order;
+pub mod order;
+mod payment;
 mod product;
+pub mod schedule;
+mod session;
+mod spreadsheet;
 mod stats;
+mod stocktake;
 mod sync;
+mod tag;
 
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -20,7 +31,18 @@ pub fn router() -> Router<AppState> {
         .nest("/master-products", master_product::router())
         .nest("/admin", admin::router()) // /api/admin/...
         .merge(sync::router())
+        .merge(archive::router()) // /api/sync/export-event/:id, /api/sync/import-event
+        .merge(spreadsheet::router()) // /api/sync/export-catalog, /api/sync/import-catalog
         .merge(info::router()) // /api/server-info
+        .merge(compare::router()) // /api/stats/compare
+        .merge(association::router()) // /api/events/:id/stats/associations, /api/stats/associations
+        .merge(forecast::router()) // /api/events/:id/stats/forecast, /api/events/:id/stats/sellout
         .merge(product::router()) // /api/events/:id/products
         .merge(order::router()) // /api/events/:id/orders
+        .merge(event_settings::router()) // /api/events/:id/settings
+        .merge(payment::router()) // /api/events/:id/payment-methods
+        .merge(session::router()) // /api/events/:id/sessions
+        .merge(schedule::router()) // /api/events/:id/schedule, /api/schedules/upcoming
+        .merge(stocktake::router()) // /api/events/:id/stocktakes, /api/stocktakes/:id
+        .merge(tag::router()) // /api/tag-groups, /api/tags
 }
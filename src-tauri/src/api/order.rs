@@ -1,16 +1,25 @@
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Json},
+    response::{IntoResponse, Json, Response},
     routing::{get, post, put},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use sqlx::{query, query_as};
+use sqlx::{query, query_as, Sqlite, SqlitePool, Transaction};
 use std::collections::HashMap;
 
-use crate::{db::models::Order, state::AppState, utils::security::Claims};
+use crate::{
+    api::{
+        event::{ensure_status_allows, event_exists, GuardedAction, OverrideQuery},
+        event_settings::load_settings,
+        payment, session,
+    },
+    db::models::{Order, PaymentMethod},
+    state::AppState,
+    utils::security::Claims,
+};
 
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -38,11 +47,15 @@ struct CreateOrderItemRequest {
 #[derive(Deserialize)]
 struct CreateOrderRequest {
     items: Vec<CreateOrderItemRequest>,
+    // 顾客选择的收款方式 (可选，摊主确认收款时也可补填)
+    payment_method_id: Option<i64>,
 }
 
 #[derive(Deserialize)]
 struct UpdateStatusRequest {
     status: String,
+    // 记录/更正实际使用的收款方式
+    payment_method_id: Option<i64>,
 }
 
 #[derive(Deserialize)]
@@ -73,7 +86,9 @@ struct OrderItemResponse {
 // ==========================================
 async fn create_order(
     State(state): State<AppState>,
+    claims: Option<Claims>, // 仅用于识别管理员补录
     Path(event_id): Path<i64>,
+    Query(overrides): Query<OverrideQuery>,
     Json(payload): Json<CreateOrderRequest>,
 ) -> impl IntoResponse {
     if payload.items.is_empty() {
@@ -83,6 +98,62 @@ async fn create_order(
         )
             .into_response();
     }
+    // 数量必须为正，否则负数行会抵消件数上限并反向增加库存
+    if payload.items.iter().any(|i| i.quantity <= 0) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Item quantity must be positive"})),
+        )
+            .into_response();
+    }
+
+    // 只有进行中的场次接受下单
+    if let Err(resp) = ensure_status_allows(
+        &state.db,
+        event_id,
+        GuardedAction::PlaceOrder,
+        claims.as_ref(),
+        &overrides,
+    )
+    .await
+    {
+        return resp;
+    }
+
+    // 场次配置：是否允许顾客自助下单、单笔件数上限
+    let settings = load_settings(&state.db, event_id).await;
+    let is_staff = claims
+        .as_ref()
+        .is_some_and(|c| check_write_permission(c, event_id).is_ok());
+    if !settings.self_ordering && !is_staff {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "Self-ordering is disabled for this event"})),
+        )
+            .into_response();
+    }
+    if let Some(max_items) = settings.max_items_per_order {
+        let total_items = payload
+            .items
+            .iter()
+            .try_fold(0i64, |total, i| total.checked_add(i.quantity));
+        if total_items.is_none_or(|t| t > max_items) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!("Order exceeds the limit of {} items", max_items),
+                    "max_items_per_order": max_items
+                })),
+            )
+                .into_response();
+        }
+    }
+
+    let payment_method =
+        match resolve_payment_method(&state.db, event_id, payload.payment_method_id).await {
+            Ok(m) => m,
+            Err(resp) => return resp,
+        };
 
     let mut tx = match state.db.begin().await {
         Ok(tx) => tx,
@@ -95,6 +166,18 @@ async fn create_order(
         }
     };
 
+    // 归属营业日，并在扣减库存前记录当日开场库存
+    let session_id = match session::attribute_order(&mut tx, event_id).await {
+        Ok(id) => id,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    };
+
     let mut total_amount = 0.0;
     // 临时存储：(product_id, name, price, quantity, raw_image_path)
     let mut items_to_insert = Vec::new();
@@ -112,7 +195,8 @@ async fn create_order(
 
         let product_opt = sqlx::query_as::<_, ProductRow>(
             r#"
-            SELECT p.id, p.current_stock, p.price, p.name, mp.image_url 
+            SELECT p.id, p.current_stock, p.price, p.name,
+                   COALESCE(mp.image_sm_url, mp.image_url) AS image_url
             FROM products p
             JOIN master_products mp ON p.master_product_id = mp.id
             WHERE p.id = ? AND p.event_id = ?
@@ -136,11 +220,13 @@ async fn create_order(
                 }
                 // 扣减库存
                 let new_stock = prod.current_stock - item_req.quantity;
-                if let Err(_e) = sqlx::query("UPDATE products SET current_stock = ? WHERE id = ?")
-                    .bind(new_stock)
-                    .bind(prod.id)
-                    .execute(&mut *tx)
-                    .await
+                if let Err(_e) = sqlx::query(
+                    "UPDATE products SET current_stock = ?, version = version + 1 WHERE id = ?",
+                )
+                .bind(new_stock)
+                .bind(prod.id)
+                .execute(&mut *tx)
+                .await
                 {
                     return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update stock")
                         .into_response();
@@ -174,10 +260,13 @@ async fn create_order(
     }
 
     let order_id = match sqlx::query(
-        "INSERT INTO orders (event_id, total_amount, status) VALUES (?, ?, 'pending') RETURNING id",
+        "INSERT INTO orders (event_id, total_amount, status, session_id, payment_method_id, payment_method) VALUES (?, ?, 'pending', ?, ?, ?) RETURNING id",
     )
     .bind(event_id)
     .bind(total_amount)
+    .bind(session_id)
+    .bind(payment_method.as_ref().map(|m| m.id))
+    .bind(payment_method.as_ref().map(|m| &m.label))
     .fetch_one(&mut *tx)
     .await
     {
@@ -269,6 +358,9 @@ async fn list_orders(
     if let Err(e) = check_read_permission(&claims, event_id) {
         return e.into_response();
     }
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
 
     // 1. 查询所有符合条件的 Orders
     let mut sql = "SELECT * FROM orders WHERE event_id = ?".to_string();
@@ -307,7 +399,7 @@ async fn list_orders(
     let placeholders = order_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
     let items_sql = format!(
         r#"
-        SELECT oi.*, mp.image_url as product_image_url
+        SELECT oi.*, COALESCE(mp.image_sm_url, mp.image_url) as product_image_url
         FROM order_items oi
         JOIN products p ON oi.product_id = p.id
         JOIN master_products mp ON p.master_product_id = mp.id
@@ -370,15 +462,25 @@ async fn update_order_status(
     if let Err(e) = check_write_permission(&claims, event_id) {
         return e.into_response();
     }
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
 
+    let payment_method =
+        match resolve_payment_method(&state.db, event_id, payload.payment_method_id).await {
+            Ok(m) => m,
+            Err(resp) => return resp,
+        };
     // 执行更新
     // 思考：如果状态变为 cancelled，是否需要恢复库存？
     // 通常漫展现场，取消订单意味着东西没卖出去，应该恢复。这里简单实现恢复逻辑。
+    // 状态变更与收款方式在同一事务中写入，状态更新失败时收款方式也不会改动
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    };
 
     if payload.status == "cancelled" {
-        // 开启事务处理取消逻辑
-        let mut tx = state.db.begin().await.unwrap();
-
         // 1. 检查订单当前状态，防止重复取消导致重复加库存
         let current_status: Option<String> =
             sqlx::query_scalar("SELECT status FROM orders WHERE id = ?")
@@ -393,7 +495,7 @@ async fn update_order_status(
                     // 已取消过，直接返回当前订单数据
                     let order = query_as::<_, Order>("SELECT * FROM orders WHERE id = ?")
                         .bind(order_id)
-                        .fetch_optional(&state.db)
+                        .fetch_optional(&mut *tx)
                         .await
                         .unwrap_or(None);
 
@@ -408,49 +510,29 @@ async fn update_order_status(
                     // 这支持摊主误操作修复的场景
                     // pending 订单扣减库存是为了防止有订单但没有货的情况出现
 
-                    // 2. 查出该订单所有商品和数量
-                    #[derive(sqlx::FromRow)]
-                    struct ItemQty {
-                        product_id: i64,
-                        quantity: i64,
-                    }
-                    let items = sqlx::query_as::<_, ItemQty>(
-                        "SELECT product_id, quantity FROM order_items WHERE order_id = ?",
-                    )
-                    .bind(order_id)
-                    .fetch_all(&mut *tx)
-                    .await
-                    .unwrap_or_default();
-
-                    // 3. 归还库存
-                    for item in items {
-                        let _ = sqlx::query(
-                            "UPDATE products SET current_stock = current_stock + ? WHERE id = ?",
-                        )
-                        .bind(item.quantity)
-                        .bind(item.product_id)
-                        .execute(&mut *tx)
-                        .await;
-                    }
+                    // 2~3. 查出该订单所有商品和数量并归还库存
+                    let _ = restore_stock(&mut tx, order_id).await;
                 }
                 _ => {}
             }
         }
 
         // 4. 更新状态
-        let _ = sqlx::query("UPDATE orders SET status = 'cancelled' WHERE id = ?")
+        let result = sqlx::query("UPDATE orders SET status = 'cancelled' WHERE id = ?")
             .bind(order_id)
             .execute(&mut *tx)
             .await;
 
-        tx.commit().await.unwrap();
+        if result.is_err() {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+        }
     } else {
         // 普通状态更新 (pending -> completed)
         let result = query("UPDATE orders SET status = ? WHERE id = ? AND event_id = ?")
             .bind(&payload.status)
             .bind(order_id)
             .bind(event_id)
-            .execute(&state.db)
+            .execute(&mut *tx)
             .await;
 
         if let Err(_) = result {
@@ -458,6 +540,26 @@ async fn update_order_status(
         }
     }
 
+    // 名称快照随 id 一起写入，收款方式之后改名/删除不影响历史订单
+    if let Some(method) = &payment_method {
+        let result = query(
+            "UPDATE orders SET payment_method_id = ?, payment_method = ? WHERE id = ? AND event_id = ?",
+        )
+        .bind(method.id)
+        .bind(&method.label)
+        .bind(order_id)
+        .bind(event_id)
+        .execute(&mut *tx)
+        .await;
+        if result.is_err() {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+        }
+    }
+
+    if tx.commit().await.is_err() {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+    }
+
     // 返回更新后的完整对象 (略，为简化起见返回成功消息，或调用 list_orders 的逻辑获取单个)
     // 按照文档 "Response (200): Updated Order object"，最好是返回 OrderResponse。
     // 这里偷懒返回简单的 Order 结构体，实际项目建议复用 fetch 逻辑。
@@ -469,6 +571,96 @@ async fn update_order_status(
     Json(updated_order).into_response()
 }
 
+// 校验收款方式属于该场次；未指定时返回 None
+async fn resolve_payment_method(
+    db: &SqlitePool,
+    event_id: i64,
+    method_id: Option<i64>,
+) -> Result<Option<PaymentMethod>, Response> {
+    let Some(method_id) = method_id else {
+        return Ok(None);
+    };
+    match payment::find_method(db, event_id, method_id).await {
+        Some(method) => Ok(Some(method)),
+        None => Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Invalid payment_method_id for this event"})),
+        )
+            .into_response()),
+    }
+}
+
+// 取消订单时归还该订单占用的库存
+async fn restore_stock(tx: &mut Transaction<'_, Sqlite>, order_id: i64) -> Result<(), sqlx::Error> {
+    #[derive(sqlx::FromRow)]
+    struct ItemQty {
+        product_id: i64,
+        quantity: i64,
+    }
+    let items = sqlx::query_as::<_, ItemQty>(
+        "SELECT product_id, quantity FROM order_items WHERE order_id = ?",
+    )
+    .bind(order_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    for item in items {
+        sqlx::query(
+            "UPDATE products SET current_stock = current_stock + ?, version = version + 1 WHERE id = ?",
+        )
+        .bind(item.quantity)
+        .bind(item.product_id)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+// ==========================================
+// 待付款超时自动取消 (后台调度器调用)
+// ==========================================
+// 按场次配置的 pending_order_timeout_minutes，取消超时未处理的待付款订单并归还库存
+pub async fn expire_pending_orders(db: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let rows: Vec<(i64,)> = query_as("SELECT event_id FROM event_settings")
+        .fetch_all(db)
+        .await?;
+
+    let mut expired = 0;
+    for (event_id,) in rows {
+        let Some(minutes) = load_settings(db, event_id)
+            .await
+            .pending_order_timeout_minutes
+        else {
+            continue;
+        };
+
+        // created_at 为 UTC (CURRENT_TIMESTAMP)
+        let stale: Vec<(i64,)> = query_as(
+            "SELECT id FROM orders WHERE event_id = ? AND status = 'pending' AND created_at <= datetime('now', ?)",
+        )
+        .bind(event_id)
+        .bind(format!("-{} minutes", minutes))
+        .fetch_all(db)
+        .await?;
+
+        for (order_id,) in stale {
+            let mut tx = db.begin().await?;
+            // 条件更新，避免与摊主同时操作时重复归还库存
+            let updated =
+                query("UPDATE orders SET status = 'cancelled' WHERE id = ? AND status = 'pending'")
+                    .bind(order_id)
+                    .execute(&mut *tx)
+                    .await?;
+            if updated.rows_affected() == 1 {
+                restore_stock(&mut tx, order_id).await?;
+                expired += 1;
+            }
+            tx.commit().await?;
+        }
+    }
+    Ok(expired)
+}
+
 // ==========================================
 // 权限检查辅助函数 (简单版)
 // ==========================================
@@ -0,0 +1,528 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post, put},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{query, query_as, Sqlite, SqlitePool, Transaction};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::{
+    api::{event::event_exists, guard::AdminOnly},
+    db::models::PaymentMethod,
+    state::AppState,
+    utils::file::{delete_file, save_upload_file, upload_url},
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 公开：查看收款方式；管理员：新增
+        .route(
+            "/events/:event_id/payment-methods",
+            get(list_methods).post(create_method),
+        )
+        // 管理员：调整显示顺序
+        .route(
+            "/events/:event_id/payment-methods/order",
+            put(reorder_methods),
+        )
+        // 管理员：修改 (Multipart) / 删除
+        .route(
+            "/events/:event_id/payment-methods/:method_id",
+            post(update_method).put(update_method).delete(delete_method),
+        )
+}
+
+// ==========================================
+// DTOs (数据传输对象)
+// ==========================================
+
+#[derive(Deserialize)]
+struct ReorderRequest {
+    ids: Vec<i64>,
+}
+
+// Multipart 表单中解析出的字段；None 表示未提交
+#[derive(Default)]
+struct MethodForm {
+    label: Option<String>,
+    sort_order: Option<i64>,
+    uploaded_qr: Option<String>,
+    remove_qr: bool,
+}
+
+// ==========================================
+// 1. 查看收款方式 (Public)
+// ==========================================
+async fn list_methods(
+    State(state): State<AppState>,
+    Path(event_id): Path<i64>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+    Json(fetch_methods(&state.db, event_id).await).into_response()
+}
+
+// ==========================================
+// 2. 新增收款方式 (Admin Only - Multipart)
+// ==========================================
+// 字段：label (必填), sort_order (默认排在最后), qr_code (图片，可选)
+async fn create_method(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(event_id): Path<i64>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    let form = match read_form(&state.upload_dir, multipart).await {
+        Ok(f) => f,
+        Err(resp) => return resp,
+    };
+    let label = match form.label.as_deref().map(validate_label) {
+        Some(Ok(label)) => label,
+        Some(Err(resp)) => {
+            discard_upload(&state.upload_dir, &form).await;
+            return resp;
+        }
+        None => {
+            discard_upload(&state.upload_dir, &form).await;
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "label is required"})),
+            )
+                .into_response();
+        }
+    };
+
+    let result: Result<i64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        let sort_order = match form.sort_order {
+            Some(n) => n,
+            None => {
+                let (next,): (i64,) = query_as(
+                    "SELECT COALESCE(MAX(sort_order) + 1, 0) FROM event_payment_methods WHERE event_id = ?",
+                )
+                .bind(event_id)
+                .fetch_one(&mut *tx)
+                .await?;
+                next
+            }
+        };
+        let id = query(
+            "INSERT INTO event_payment_methods (event_id, label, qr_code_path, sort_order) VALUES (?, ?, ?, ?)",
+        )
+        .bind(event_id)
+        .bind(&label)
+        .bind(&form.uploaded_qr)
+        .bind(sort_order)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        sync_primary_qr(&mut tx, event_id).await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+    .await;
+
+    match result {
+        Ok(id) => match find_method(&state.db, event_id, id).await {
+            Some(method) => (StatusCode::CREATED, Json(method)).into_response(),
+            None => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+        },
+        Err(e) => {
+            eprintln!("Create payment method error: {:?}", e);
+            discard_upload(&state.upload_dir, &form).await;
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 3. 修改收款方式 (Admin Only - Multipart)
+// ==========================================
+// 只修改提交的字段；qr_code 替换图片，remove_qr_code=true 移除图片
+async fn update_method(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path((event_id, method_id)): Path<(i64, i64)>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+    let Some(current) = find_method(&state.db, event_id, method_id).await else {
+        return (StatusCode::NOT_FOUND, "Payment method not found").into_response();
+    };
+
+    let form = match read_form(&state.upload_dir, multipart).await {
+        Ok(f) => f,
+        Err(resp) => return resp,
+    };
+    let label = match form.label.as_deref().map(validate_label) {
+        Some(Ok(label)) => label,
+        Some(Err(resp)) => {
+            discard_upload(&state.upload_dir, &form).await;
+            return resp;
+        }
+        None => current.label.clone(),
+    };
+
+    // 最终收款码：新上传 > 显式删除 > 保留原图
+    let qr_code_path = if form.uploaded_qr.is_some() {
+        form.uploaded_qr.clone()
+    } else if form.remove_qr {
+        None
+    } else {
+        current.qr_code_path.clone()
+    };
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        query(
+            "UPDATE event_payment_methods SET label = ?, qr_code_path = ?, sort_order = ? WHERE id = ?",
+        )
+        .bind(&label)
+        .bind(&qr_code_path)
+        .bind(form.sort_order.unwrap_or(current.sort_order))
+        .bind(method_id)
+        .execute(&mut *tx)
+        .await?;
+        sync_primary_qr(&mut tx, event_id).await?;
+        tx.commit().await
+    }
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Update payment method error: {:?}", e);
+        discard_upload(&state.upload_dir, &form).await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+    }
+
+    // 写入成功后再删除被替换/移除的旧图
+    if let Some(old_path) = &current.qr_code_path {
+        if qr_code_path.as_ref() != Some(old_path) {
+            remove_unreferenced_file(&state.db, &state.upload_dir, old_path).await;
+        }
+    }
+
+    match find_method(&state.db, event_id, method_id).await {
+        Some(method) => Json(method).into_response(),
+        None => (StatusCode::NOT_FOUND, "Payment method not found").into_response(),
+    }
+}
+
+// ==========================================
+// 4. 删除收款方式 (Admin Only)
+// ==========================================
+// 已使用该方式的订单保留名称快照
+async fn delete_method(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path((event_id, method_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+    let Some(current) = find_method(&state.db, event_id, method_id).await else {
+        return (StatusCode::NOT_FOUND, "Payment method not found").into_response();
+    };
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        query("DELETE FROM event_payment_methods WHERE id = ?")
+            .bind(method_id)
+            .execute(&mut *tx)
+            .await?;
+        sync_primary_qr(&mut tx, event_id).await?;
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            if let Some(path) = &current.qr_code_path {
+                remove_unreferenced_file(&state.db, &state.upload_dir, path).await;
+            }
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            eprintln!("Delete payment method error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 5. 调整显示顺序 (Admin Only - JSON)
+// ==========================================
+// ids 按新顺序排列；未列出的收款方式排在后面，保持原有相对顺序
+async fn reorder_methods(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(event_id): Path<i64>,
+    Json(payload): Json<ReorderRequest>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+    let methods = fetch_methods(&state.db, event_id).await;
+    if let Some(unknown) = payload
+        .ids
+        .iter()
+        .find(|id| !methods.iter().any(|m| m.id == **id))
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("Payment method {} does not belong to this event", unknown)})),
+        )
+            .into_response();
+    }
+
+    let mut ordered: Vec<i64> = Vec::with_capacity(methods.len());
+    for id in payload.ids.iter().chain(methods.iter().map(|m| &m.id)) {
+        if !ordered.contains(id) {
+            ordered.push(*id);
+        }
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        for (index, id) in ordered.iter().enumerate() {
+            query("UPDATE event_payment_methods SET sort_order = ? WHERE id = ?")
+                .bind(index as i64)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sync_primary_qr(&mut tx, event_id).await?;
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => Json(fetch_methods(&state.db, event_id).await).into_response(),
+        Err(e) => {
+            eprintln!("Reorder payment methods error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 表单解析与校验
+// ==========================================
+
+async fn read_form(upload_dir: &PathBuf, mut multipart: Multipart) -> Result<MethodForm, Response> {
+    let mut form = MethodForm::default();
+    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
+        let field_name = field.name().unwrap_or("").to_string();
+        if field_name == "qr_code" {
+            match save_upload_file(upload_dir, field, Some("events")).await {
+                Ok(path) => {
+                    if let Some(previous) = form.uploaded_qr.replace(path) {
+                        let _ = delete_file(upload_dir, &previous).await;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Upload Failed: {}", e);
+                    discard_upload(upload_dir, &form).await;
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({"error": "Failed to save file"})),
+                    )
+                        .into_response());
+                }
+            }
+        } else {
+            let value = field.text().await.unwrap_or_default();
+            match field_name.as_str() {
+                "label" => form.label = Some(value),
+                "sort_order" => form.sort_order = value.trim().parse().ok(),
+                "remove_qr_code" => form.remove_qr = value == "true",
+                _ => {}
+            }
+        }
+    }
+    Ok(form)
+}
+
+fn validate_label(label: &str) -> Result<String, Response> {
+    let label = label.trim();
+    let len = label.chars().count();
+    if len == 0 || len > 30 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "label must be 1-30 characters"})),
+        )
+            .into_response());
+    }
+    Ok(label.to_string())
+}
+
+// 请求失败时清理本次上传的图片
+async fn discard_upload(upload_dir: &PathBuf, form: &MethodForm) {
+    if let Some(path) = &form.uploaded_qr {
+        let _ = delete_file(upload_dir, path).await;
+    }
+}
+
+// ==========================================
+// 共享辅助函数 (场次/订单/归档复用)
+// ==========================================
+
+pub(crate) async fn fetch_methods(db: &SqlitePool, event_id: i64) -> Vec<PaymentMethod> {
+    query_as(
+        "SELECT id, event_id, label, qr_code_path, sort_order FROM event_payment_methods WHERE event_id = ? ORDER BY sort_order ASC, id ASC",
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await
+    .unwrap_or_default()
+}
+
+// 场次列表批量附带收款方式
+pub(crate) async fn methods_by_event(db: &SqlitePool) -> HashMap<i64, Vec<PaymentMethod>> {
+    let rows: Vec<PaymentMethod> = query_as(
+        "SELECT id, event_id, label, qr_code_path, sort_order FROM event_payment_methods ORDER BY sort_order ASC, id ASC",
+    )
+    .fetch_all(db)
+    .await
+    .unwrap_or_default();
+
+    let mut map: HashMap<i64, Vec<PaymentMethod>> = HashMap::new();
+    for row in rows {
+        map.entry(row.event_id).or_default().push(row);
+    }
+    map
+}
+
+pub(crate) async fn find_method(
+    db: &SqlitePool,
+    event_id: i64,
+    method_id: i64,
+) -> Option<PaymentMethod> {
+    query_as(
+        "SELECT id, event_id, label, qr_code_path, sort_order FROM event_payment_methods WHERE id = ? AND event_id = ?",
+    )
+    .bind(method_id)
+    .bind(event_id)
+    .fetch_optional(db)
+    .await
+    .unwrap_or(None)
+}
+
+// events.payment_qr_code_path 同步为排在第一位的收款码
+pub(crate) async fn sync_primary_qr(
+    tx: &mut Transaction<'_, Sqlite>,
+    event_id: i64,
+) -> Result<(), sqlx::Error> {
+    query(
+        r#"
+        UPDATE events SET payment_qr_code_path = (
+            SELECT qr_code_path FROM event_payment_methods
+            WHERE event_id = ? AND qr_code_path IS NOT NULL
+            ORDER BY sort_order ASC, id ASC
+            LIMIT 1
+        )
+        WHERE id = ?
+        "#,
+    )
+    .bind(event_id)
+    .bind(event_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+// 旧版单收款码接口 (展会编辑表单) 的兼容：替换或移除第一位收款方式的图片。
+// 还没有收款方式时，新建一个默认的 "付款码"
+pub(crate) async fn replace_primary_qr(
+    tx: &mut Transaction<'_, Sqlite>,
+    event_id: i64,
+    old_path: Option<&str>,
+    new_path: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let primary: Option<(i64,)> = match old_path {
+        Some(old) => {
+            query_as("SELECT id FROM event_payment_methods WHERE event_id = ? AND qr_code_path = ? ORDER BY sort_order ASC, id ASC LIMIT 1")
+                .bind(event_id)
+                .bind(upload_url(old))
+                .fetch_optional(&mut **tx)
+                .await?
+        }
+        None => None,
+    };
+
+    match (primary, new_path) {
+        (Some((id,)), Some(new)) => {
+            query("UPDATE event_payment_methods SET qr_code_path = ? WHERE id = ?")
+                .bind(upload_url(new))
+                .bind(id)
+                .execute(&mut **tx)
+                .await?;
+        }
+        (Some((id,)), None) => {
+            query("DELETE FROM event_payment_methods WHERE id = ?")
+                .bind(id)
+                .execute(&mut **tx)
+                .await?;
+        }
+        (None, Some(new)) => {
+            query(
+                r#"
+                INSERT INTO event_payment_methods (event_id, label, qr_code_path, sort_order)
+                SELECT ?, '付款码', ?, COALESCE(MIN(sort_order) - 1, 0)
+                FROM event_payment_methods WHERE event_id = ?
+                "#,
+            )
+            .bind(event_id)
+            .bind(upload_url(new))
+            .bind(event_id)
+            .execute(&mut **tx)
+            .await?;
+        }
+        (None, None) => {}
+    }
+
+    sync_primary_qr(tx, event_id).await
+}
+
+// 图片不再被任何场次/收款方式引用时才删除 (重复导入的归档可能共用同一张图)。
+// 返回是否已删除
+pub(crate) async fn remove_unreferenced_file(
+    db: &SqlitePool,
+    upload_dir: &PathBuf,
+    path: &str,
+) -> bool {
+    let url = upload_url(path);
+    let (refs,): (i64,) = query_as(
+        r#"
+        SELECT (SELECT COUNT(*) FROM event_payment_methods WHERE qr_code_path = ?)
+             + (SELECT COUNT(*) FROM events WHERE payment_qr_code_path IN (?, ?))
+        "#,
+    )
+    .bind(&url)
+    .bind(&url)
+    .bind(url.trim_start_matches("/uploads/"))
+    .fetch_one(db)
+    .await
+    .unwrap_or((1,));
+    if refs > 0 {
+        return false;
+    }
+    match delete_file(upload_dir, path).await {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Failed to delete QR image {}: {}", path, e);
+            false
+        }
+    }
+}
@@ -1,18 +1,30 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Json},
-    routing::{get, put},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post, put},
     Router,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use sqlx::{query, query_as, Row};
+use sqlx::{query, query_as, Row, SqlitePool};
+use std::collections::HashMap;
 
 use crate::{
+    api::{
+        event::{ensure_status_allows, event_exists, GuardedAction, OverrideQuery},
+        event_settings::load_settings,
+        master_product::fetch_translations,
+        tag::{parse_tag_ids, tag_filter, tags_by_product},
+    },
     db::models::{MasterProduct, Product},
     state::AppState,
-    utils::security::Claims,
+    utils::{
+        i18n::{localize, RequestLocale},
+        label::{build_sheet, LabelItem, LabelOptions},
+        search::build_match_query,
+        security::Claims,
+    },
 };
 
 pub fn router() -> Router<AppState> {
@@ -24,12 +36,74 @@ pub fn router() -> Router<AppState> {
             "/events/:event_id/products",
             get(list_event_products).post(add_product_to_event),
         )
+        .route("/events/:event_id/products/order", put(reorder_products))
+        .route("/events/:event_id/products/labels", post(generate_labels))
+        .route(
+            "/events/:event_id/products/search",
+            get(search_event_products),
+        )
         .route(
             "/products/:product_id",
             put(update_product).delete(delete_product),
         )
 }
 
+// 场次商品查询的公共列 (JOIN master_products 获取图片和分类)
+const PRODUCT_COLUMNS: &str = r#"
+    p.id, p.event_id, p.master_product_id, p.product_code, p.name, p.price,
+    p.initial_stock, p.current_stock, p.sort_order, p.is_featured, p.is_new, p.version,
+    mp.image_url, mp.image_md_url, mp.image_sm_url, mp.category, mp.description
+"#;
+
+async fn fetch_event_product(db: &SqlitePool, product_id: i64) -> Option<Product> {
+    let sql = format!(
+        r#"
+        SELECT {}
+        FROM products p
+        JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE p.id = ?
+        "#,
+        PRODUCT_COLUMNS
+    );
+
+    query_as::<_, Product>(&sql)
+        .bind(product_id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None)
+}
+
+// 标签挂在全局商品上，按 master_product_id 填充 (列表与检索共用)
+async fn attach_tags(db: &SqlitePool, products: &mut [Product]) {
+    let ids: Vec<i64> = products.iter().map(|p| p.master_product_id).collect();
+    let tags = tags_by_product(db, Some(&ids)).await;
+    for product in products.iter_mut() {
+        product.tags = tags
+            .get(&product.master_product_id)
+            .cloned()
+            .unwrap_or_default();
+    }
+}
+
+// 按请求语言填充展示名称/描述；没有翻译时回退到场次商品自己的名称快照
+async fn localize_products(db: &SqlitePool, products: &mut [Product], locales: &[String]) {
+    let ids: Vec<i64> = products.iter().map(|p| p.master_product_id).collect();
+    let translations = fetch_translations(db, Some(&ids)).await;
+    for product in products.iter_mut() {
+        let (name, description) = localize(
+            locales,
+            translations
+                .get(&product.master_product_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+            &product.name,
+            product.description.as_deref(),
+        );
+        product.display_name = Some(name);
+        product.display_description = description;
+    }
+}
+
 // ==========================================
 // 辅助：权限检查
 // ==========================================
@@ -56,28 +130,159 @@ fn check_write_permission(
 // ==========================================
 // 1. 获取场次库存列表 (Public)
 // ==========================================
+#[derive(Deserialize)]
+struct ListProductsQuery {
+    all: Option<bool>,        // ?all=true 忽略售罄策略显示全部 (摊主管理用)
+    group_by: Option<String>, // ?group_by=category 按分类分组返回
+    tags: Option<String>,     // ?tags=1,2,5 按标签筛选：同组任一命中，跨组全部命中
+}
+
+#[derive(Serialize)]
+struct CategoryGroup {
+    category: Option<String>,
+    products: Vec<Product>,
+}
+
 async fn list_event_products(
     State(state): State<AppState>,
+    RequestLocale(locales): RequestLocale,
     Path(event_id): Path<i64>,
+    Query(params): Query<ListProductsQuery>,
 ) -> impl IntoResponse {
-    // 关键点：JOIN master_products 获取图片和分类
-    let sql = r#"
-        SELECT 
-            p.id, p.event_id, p.master_product_id, p.product_code, p.name, p.price, 
-            p.initial_stock, p.current_stock, mp.image_url, mp.category
+    if !event_exists(&state.db, event_id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Event not found"})),
+        )
+            .into_response();
+    }
+    // 售罄展示策略：show (置灰，由前端根据 current_stock 处理) / bottom / hide
+    let policy: String = if params.all.unwrap_or(false) {
+        "show".to_string()
+    } else {
+        load_settings(&state.db, event_id).await.soldout_policy
+    };
+
+    let tag_ids = parse_tag_ids(params.tags.as_deref());
+    let (tag_clause, tag_binds) = tag_filter(&state.db, &tag_ids, "p.master_product_id").await;
+
+    let stock_filter = if policy == "hide" {
+        "AND p.current_stock > 0"
+    } else {
+        ""
+    };
+    let order_by = if policy == "bottom" {
+        "(p.current_stock <= 0) ASC, p.sort_order ASC, p.product_code ASC"
+    } else {
+        "p.sort_order ASC, p.product_code ASC"
+    };
+
+    let sql = format!(
+        r#"
+        SELECT {}
         FROM products p
         JOIN master_products mp ON p.master_product_id = mp.id
-        WHERE p.event_id = ?
-        ORDER BY p.product_code ASC
-    "#;
+        WHERE p.event_id = ? {} {}
+        ORDER BY {}
+        "#,
+        PRODUCT_COLUMNS, stock_filter, tag_clause, order_by
+    );
+
+    let mut q = query_as::<_, Product>(&sql).bind(event_id);
+    for id in &tag_binds {
+        q = q.bind(id);
+    }
+    let mut products: Vec<Product> = q.fetch_all(&state.db).await.unwrap_or_default();
+    attach_tags(&state.db, &mut products).await;
+    localize_products(&state.db, &mut products, &locales).await;
+
+    if params.group_by.as_deref() == Some("category") {
+        // 分组顺序以每个分类在展示顺序中第一次出现的位置为准
+        let mut groups: Vec<CategoryGroup> = Vec::new();
+        for product in products {
+            match groups.iter_mut().find(|g| g.category == product.category) {
+                Some(group) => group.products.push(product),
+                None => groups.push(CategoryGroup {
+                    category: product.category.clone(),
+                    products: vec![product],
+                }),
+            }
+        }
+        return Json(groups).into_response();
+    }
+
+    Json(products).into_response()
+}
+
+// ==========================================
+// 1.1 检索场次商品 (Public)
+// ==========================================
+#[derive(Deserialize)]
+struct SearchProductsQuery {
+    q: String,
+    limit: Option<i64>, // 默认 20，最多 100
+}
+
+async fn search_event_products(
+    State(state): State<AppState>,
+    RequestLocale(locales): RequestLocale,
+    Path(event_id): Path<i64>,
+    Query(params): Query<SearchProductsQuery>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Event not found"})),
+        )
+            .into_response();
+    }
+    let match_query = match build_match_query(&params.q) {
+        Some(q) => q,
+        None => return Json(Vec::<Product>::new()).into_response(),
+    };
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
 
-    let products: Vec<Product> = query_as::<_, Product>(sql)
+    // 索引按全局商品建立，通过 master_product_id 关联到本场次库存
+    // 排序：编号完全一致 > 编号前缀 > 有货 > bm25 相关度 > 展示顺序
+    let sql = format!(
+        r#"
+        SELECT {}
+        FROM product_search
+        JOIN products p ON p.master_product_id = product_search.master_product_id
+        JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE product_search MATCH ? AND p.event_id = ?
+        ORDER BY
+            (lower(p.product_code) = lower(?)) DESC,
+            (lower(p.product_code) LIKE lower(?) || '%') DESC,
+            (p.current_stock <= 0) ASC,
+            bm25(product_search, 0.0, 10.0, 5.0, 2.0, 2.0, 1.0) ASC,
+            p.sort_order ASC
+        LIMIT ?
+        "#,
+        PRODUCT_COLUMNS
+    );
+
+    let keyword = params.q.trim();
+    let result = query_as::<_, Product>(&sql)
+        .bind(&match_query)
         .bind(event_id)
+        .bind(keyword)
+        .bind(keyword)
+        .bind(limit)
         .fetch_all(&state.db)
-        .await
-        .unwrap_or_default();
+        .await;
 
-    Json(products)
+    match result {
+        Ok(mut products) => {
+            attach_tags(&state.db, &mut products).await;
+            localize_products(&state.db, &mut products, &locales).await;
+            Json(products).into_response()
+        }
+        Err(e) => {
+            eprintln!("Search error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
 }
 
 // ==========================================
@@ -94,19 +299,32 @@ async fn add_product_to_event(
     State(state): State<AppState>,
     claims: Claims, // 需要登录
     Path(event_id): Path<i64>,
+    Query(overrides): Query<OverrideQuery>,
     Json(payload): Json<AddProductRequest>,
 ) -> impl IntoResponse {
     // 1. 权限检查
     if let Err(e) = check_write_permission(&claims, event_id) {
         return e.into_response();
     }
+    if let Err(resp) = ensure_status_allows(
+        &state.db,
+        event_id,
+        GuardedAction::EditProducts,
+        Some(&claims),
+        &overrides,
+    )
+    .await
+    {
+        return resp;
+    }
 
     // [修复] 验证事件是否存在 ✓
-    let event_exists: Option<(i64,)> = sqlx::query_as("SELECT id FROM events WHERE id = ?")
-        .bind(event_id)
-        .fetch_optional(&state.db)
-        .await
-        .unwrap_or(None);
+    let event_exists: Option<(i64,)> =
+        sqlx::query_as("SELECT id FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(event_id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
 
     if event_exists.is_none() {
         return (
@@ -135,16 +353,27 @@ async fn add_product_to_event(
         }
     };
 
+    // 已归档商品不允许再上架到场次
+    if master.archived_at.is_some() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Product is archived"})),
+        )
+            .into_response();
+    }
+
     // 3. 确定价格 (如果 payload 没传，就用 default_price)
     let final_price = payload.price.unwrap_or(master.default_price);
 
     // 4. 插入库存表
     // 冗余存储 name 和 product_code 是为了快照，防止 master 删改后这里数据丢失
+    // 新加入的商品排在当前展示顺序的末尾
     let result = query(
         r#"
         INSERT INTO products 
-        (event_id, master_product_id, product_code, name, price, initial_stock, current_stock)
-        VALUES (?, ?, ?, ?, ?, ?, ?)
+        (event_id, master_product_id, product_code, name, price, initial_stock, current_stock, sort_order)
+        VALUES (?, ?, ?, ?, ?, ?, ?,
+            (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM products WHERE event_id = ?))
         "#,
     )
     .bind(event_id)
@@ -154,35 +383,18 @@ async fn add_product_to_event(
     .bind(final_price)
     .bind(payload.initial_stock)
     .bind(payload.initial_stock) // 初始 current = initial
+    .bind(event_id)
     .execute(&state.db)
     .await;
 
     match result {
-        Ok(_) => {
-            // 获取最后插入的行ID
-            let last_id: Option<(i64,)> = query_as("SELECT last_insert_rowid() as id")
-                .fetch_optional(&state.db)
-                .await
-                .unwrap_or(None);
-
-            if let Some((new_id,)) = last_id {
-                // 构建Product对象（直接使用已有的master信息）
-                let product = Product {
-                    id: new_id,
-                    event_id,
-                    master_product_id: master.id,
-                    product_code: master.product_code.clone(),
-                    name: master.name.clone(),
-                    price: final_price,
-                    initial_stock: payload.initial_stock,
-                    current_stock: payload.initial_stock,
-                    image_url: master.image_url.clone(),
-                    category: master.category.clone(),
-                };
-
-                (StatusCode::CREATED, Json(product)).into_response()
-            } else {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get insert ID").into_response()
+        Ok(done) => {
+            // 使用本次插入返回的行ID，重新查询完整的商品信息
+            match fetch_event_product(&state.db, done.last_insert_rowid()).await {
+                Some(product) => (StatusCode::CREATED, Json(product)).into_response(),
+                None => {
+                    (StatusCode::INTERNAL_SERVER_ERROR, "Failed to get insert ID").into_response()
+                }
             }
         }
         Err(e) => {
@@ -208,12 +420,16 @@ async fn add_product_to_event(
 struct UpdateProductRequest {
     price: Option<f64>,
     initial_stock: Option<i64>,
+    is_featured: Option<bool>,
+    is_new: Option<bool>,
+    version: Option<i64>, // 乐观锁：必须携带读取时的版本号
 }
 
 async fn update_product(
     State(state): State<AppState>,
     claims: Claims,
     Path(product_id): Path<i64>,
+    Query(overrides): Query<OverrideQuery>,
     Json(payload): Json<UpdateProductRequest>,
 ) -> impl IntoResponse {
     // 1. 先查这个 Product 属于哪个 Event，以便校验权限
@@ -232,6 +448,33 @@ async fn update_product(
     if let Err(e) = check_write_permission(&claims, product.event_id) {
         return e.into_response();
     }
+    if let Err(resp) = ensure_status_allows(
+        &state.db,
+        product.event_id,
+        GuardedAction::EditProducts,
+        Some(&claims),
+        &overrides,
+    )
+    .await
+    {
+        return resp;
+    }
+
+    // 乐观锁：版本号不一致说明期间有其他设备修改过 (含售出扣库存)，拒绝覆盖
+    let expected_version = match payload.version {
+        Some(v) => v,
+        None => {
+            return (
+                StatusCode::PRECONDITION_REQUIRED,
+                Json(json!({"error": "version is required"})),
+            )
+                .into_response()
+        }
+    };
+
+    if expected_version != product.version {
+        return version_conflict(&state.db, product_id).await;
+    }
 
     // 3. 计算新库存
     // 逻辑：current_stock 应该跟随 initial_stock 的变化而变化 (保持已售数量不变)
@@ -241,6 +484,8 @@ async fn update_product(
     let mut new_initial = product.initial_stock;
     let mut new_current = product.current_stock;
     let mut new_price = product.price;
+    let is_featured = payload.is_featured.unwrap_or(product.is_featured);
+    let is_new = payload.is_new.unwrap_or(product.is_new);
 
     if let Some(p) = payload.price {
         new_price = p;
@@ -264,33 +509,33 @@ async fn update_product(
     }
 
     // 4. 更新数据库
-    let result =
-        query("UPDATE products SET price = ?, initial_stock = ?, current_stock = ? WHERE id = ?")
-            .bind(new_price)
-            .bind(new_initial)
-            .bind(new_current)
-            .bind(product_id)
-            .execute(&state.db)
-            .await;
+    let result = query(
+        r#"
+        UPDATE products
+        SET price = ?, initial_stock = ?, current_stock = ?, is_featured = ?, is_new = ?,
+            version = version + 1
+        WHERE id = ? AND version = ?
+        "#,
+    )
+    .bind(new_price)
+    .bind(new_initial)
+    .bind(new_current)
+    .bind(is_featured)
+    .bind(is_new)
+    .bind(product_id)
+    .bind(expected_version)
+    .execute(&state.db)
+    .await;
 
     match result {
+        // 读取之后、写入之前有订单扣了库存
+        Ok(done) if done.rows_affected() == 0 => version_conflict(&state.db, product_id).await,
         Ok(_) => {
             // 返回更新后的对象（使用内连接，master_product必定存在）
-            let updated_product = query_as::<_, Product>(
-                r#"
-                SELECT p.id, p.event_id, p.master_product_id, p.product_code, p.name, p.price, 
-                       p.initial_stock, p.current_stock, mp.image_url, mp.category 
-                FROM products p 
-                JOIN master_products mp ON p.master_product_id = mp.id 
-                WHERE p.id = ?
-                "#,
-            )
-            .bind(product_id)
-            .fetch_one(&state.db)
-            .await
-            .unwrap();
-
-            (StatusCode::OK, Json(updated_product)).into_response()
+            match fetch_event_product(&state.db, product_id).await {
+                Some(updated_product) => (StatusCode::OK, Json(updated_product)).into_response(),
+                None => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+            }
         }
         Err(e) => {
             eprintln!("Update product error: {:?}", e);
@@ -299,6 +544,19 @@ async fn update_product(
     }
 }
 
+// 409 Conflict：附带当前最新数据，前端可直接刷新后让用户重新确认
+async fn version_conflict(db: &SqlitePool, product_id: i64) -> Response {
+    let current = fetch_event_product(db, product_id).await;
+    (
+        StatusCode::CONFLICT,
+        Json(json!({
+            "error": "Product has been modified by another device, please reload and retry",
+            "current": current
+        })),
+    )
+        .into_response()
+}
+
 // ==========================================
 // 4. 删除商品 (Admin/Vendor)
 // ==========================================
@@ -306,6 +564,7 @@ async fn delete_product(
     State(state): State<AppState>,
     claims: Claims,
     Path(product_id): Path<i64>,
+    Query(overrides): Query<OverrideQuery>,
 ) -> impl IntoResponse {
     // 1. 查 Event ID
     let row: Option<(i64,)> = sqlx::query_as("SELECT event_id FROM products WHERE id = ?")
@@ -323,6 +582,17 @@ async fn delete_product(
     if let Err(e) = check_write_permission(&claims, event_id) {
         return e.into_response();
     }
+    if let Err(resp) = ensure_status_allows(
+        &state.db,
+        event_id,
+        GuardedAction::EditProducts,
+        Some(&claims),
+        &overrides,
+    )
+    .await
+    {
+        return resp;
+    }
 
     // 3. 删除
     let _ = query("DELETE FROM products WHERE id = ?")
@@ -336,3 +606,197 @@ async fn delete_product(
     )
         .into_response()
 }
+
+// ==========================================
+// 5. 调整展示顺序 (Admin/Vendor) - 拖拽排序持久化
+// ==========================================
+#[derive(Deserialize)]
+struct ReorderProductsRequest {
+    // 按新的展示顺序排列的商品 ID；未列出的商品保持原有相对顺序排在后面
+    product_ids: Vec<i64>,
+}
+
+async fn reorder_products(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+    Query(overrides): Query<OverrideQuery>,
+    Json(payload): Json<ReorderProductsRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = check_write_permission(&claims, event_id) {
+        return e.into_response();
+    }
+    if let Err(resp) = ensure_status_allows(
+        &state.db,
+        event_id,
+        GuardedAction::EditProducts,
+        Some(&claims),
+        &overrides,
+    )
+    .await
+    {
+        return resp;
+    }
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    };
+
+    let existing: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM products WHERE event_id = ? ORDER BY sort_order ASC, product_code ASC",
+    )
+    .bind(event_id)
+    .fetch_all(&mut *tx)
+    .await
+    .unwrap_or_default();
+
+    if let Some(bad) = payload.product_ids.iter().find(|id| !existing.contains(id)) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("Product {} does not belong to this event", bad)})),
+        )
+            .into_response();
+    }
+
+    let mut ordered: Vec<i64> = Vec::with_capacity(existing.len());
+    for id in payload.product_ids.iter().chain(existing.iter()) {
+        if !ordered.contains(id) {
+            ordered.push(*id);
+        }
+    }
+
+    for (position, id) in ordered.iter().enumerate() {
+        if let Err(e) = query("UPDATE products SET sort_order = ? WHERE id = ?")
+            .bind(position as i64)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+        {
+            eprintln!("Reorder products error: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+        }
+    }
+
+    if let Err(e) = tx.commit().await {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Commit Failed: {}", e),
+        )
+            .into_response();
+    }
+
+    let sql = format!(
+        r#"
+        SELECT {}
+        FROM products p
+        JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE p.event_id = ?
+        ORDER BY p.sort_order ASC, p.product_code ASC
+        "#,
+        PRODUCT_COLUMNS
+    );
+
+    let products: Vec<Product> = query_as::<_, Product>(&sql)
+        .bind(event_id)
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default();
+
+    Json(products).into_response()
+}
+
+// ==========================================
+// 6. 生成商品标签页 (Admin/Vendor - JSON → PDF/SVG)
+// ==========================================
+#[derive(Deserialize)]
+struct LabelRequest {
+    #[serde(default)]
+    product_ids: Vec<i64>, // 场次商品 ID，按传入顺序排版；为空时按展示顺序打印全部
+    quantities: Option<HashMap<i64, usize>>, // 按商品指定份数
+    use_stock: Option<bool>,                 // true 时每件库存打印一张 (贴在实物上)
+    #[serde(flatten)]
+    options: LabelOptions,
+}
+
+async fn generate_labels(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+    Json(payload): Json<LabelRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = check_write_permission(&claims, event_id) {
+        return e.into_response();
+    }
+    if !event_exists(&state.db, event_id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Event not found"})),
+        )
+            .into_response();
+    }
+
+    let sql = format!(
+        r#"
+        SELECT {}
+        FROM products p
+        JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE p.event_id = ?
+        ORDER BY p.sort_order ASC, p.product_code ASC
+        "#,
+        PRODUCT_COLUMNS
+    );
+    let all: Vec<Product> = query_as::<_, Product>(&sql)
+        .bind(event_id)
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default();
+
+    let products: Vec<Product> = if payload.product_ids.is_empty() {
+        all
+    } else {
+        if let Some(bad) = payload
+            .product_ids
+            .iter()
+            .find(|id| !all.iter().any(|p| p.id == **id))
+        {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("Product {} does not belong to this event", bad)})),
+            )
+                .into_response();
+        }
+        payload
+            .product_ids
+            .iter()
+            .filter_map(|id| all.iter().find(|p| p.id == *id).cloned())
+            .collect()
+    };
+
+    let default_copies = payload.options.copies.unwrap_or(1);
+    let use_stock = payload.use_stock.unwrap_or(false);
+    let items: Vec<LabelItem> = products
+        .into_iter()
+        .map(|p| LabelItem {
+            copies: match payload.quantities.as_ref().and_then(|q| q.get(&p.id)) {
+                Some(n) => *n,
+                None if use_stock => p.current_stock.max(0) as usize,
+                None => default_copies,
+            },
+            code: p.product_code,
+            name: p.name,
+            price: p.price,
+        })
+        .collect();
+
+    match build_sheet(&payload.options, &items) {
+        Ok(sheet) => sheet.into_download(&format!("labels_event{}", event_id)),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    }
+}
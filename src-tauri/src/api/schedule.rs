@@ -0,0 +1,378 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    routing::get,
+    Router,
+};
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{query, query_as, FromRow, Sqlite, SqlitePool, Transaction};
+
+use crate::{
+    api::{
+        event::{change_status, event_exists},
+        guard::AdminOnly,
+        session::fetch_sessions,
+    },
+    db::models::{Event, EventSchedule, EventStatus},
+    state::AppState,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 管理员：查看/设置/清空某场次的定时开闭场
+        .route(
+            "/events/:event_id/schedule",
+            get(get_schedule).put(set_schedule).delete(clear_schedule),
+        )
+        // 管理员：所有场次即将执行的定时任务
+        .route("/schedules/upcoming", get(list_upcoming))
+}
+
+// run_at 统一格式 (本地时间)，字符串比较即时间先后
+const RUN_AT_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+// ==========================================
+// DTOs (数据传输对象)
+// ==========================================
+
+#[derive(Deserialize)]
+struct SetScheduleRequest {
+    open_at: Option<String>,  // YYYY-MM-DD HH:MM
+    close_at: Option<String>, // YYYY-MM-DD HH:MM
+    // true：按各营业日的营业时间每天开场/收摊，最后一天收摊时结束场次
+    #[serde(default)]
+    use_sessions: bool,
+}
+
+#[derive(Deserialize)]
+struct UpcomingQuery {
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, FromRow)]
+struct UpcomingSchedule {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    schedule: EventSchedule,
+    event_name: String,
+    event_status: EventStatus,
+}
+
+// ==========================================
+// 1. 查看定时任务 (Admin Only)
+// ==========================================
+// 包含已执行/已跳过的历史记录，按执行时间排列
+async fn get_schedule(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(event_id): Path<i64>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+    Json(fetch_schedule(&state.db, event_id).await).into_response()
+}
+
+async fn fetch_schedule(db: &SqlitePool, event_id: i64) -> Vec<EventSchedule> {
+    query_as("SELECT * FROM event_schedules WHERE event_id = ? ORDER BY run_at ASC, id ASC")
+        .bind(event_id)
+        .fetch_all(db)
+        .await
+        .unwrap_or_default()
+}
+
+// ==========================================
+// 2. 设置定时任务 (Admin Only - JSON)
+// ==========================================
+// 替换该场次所有待执行的任务；已执行的历史保留
+async fn set_schedule(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(event_id): Path<i64>,
+    Json(payload): Json<SetScheduleRequest>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    let bad_request =
+        |msg: String| (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response();
+
+    let entries: Vec<(Option<i64>, &str, String)> = if payload.use_sessions {
+        if payload.open_at.is_some() || payload.close_at.is_some() {
+            return bad_request("Use either use_sessions or open_at/close_at, not both".into());
+        }
+        let entries = session_entries(&state.db, event_id).await;
+        if entries.is_empty() {
+            return bad_request("No session has opening hours set".into());
+        }
+        entries
+    } else {
+        let open_at = match payload.open_at.as_deref().map(parse_run_at).transpose() {
+            Ok(v) => v,
+            Err(msg) => return bad_request(msg),
+        };
+        let close_at = match payload.close_at.as_deref().map(parse_run_at).transpose() {
+            Ok(v) => v,
+            Err(msg) => return bad_request(msg),
+        };
+        if let (Some(open), Some(close)) = (&open_at, &close_at) {
+            if open >= close {
+                return bad_request("close_at must be later than open_at".into());
+            }
+        }
+        let mut entries = Vec::new();
+        if let Some(open) = open_at {
+            entries.push((None, "open", open));
+        }
+        if let Some(close) = close_at {
+            entries.push((None, "close", close));
+        }
+        if entries.is_empty() {
+            return bad_request("open_at, close_at or use_sessions is required".into());
+        }
+        entries
+    };
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        replace_pending(&mut tx, event_id, &entries).await?;
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => Json(fetch_schedule(&state.db, event_id).await).into_response(),
+        Err(e) => {
+            eprintln!("Set schedule error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// 兼容 <input type="datetime-local"> 的 "YYYY-MM-DDTHH:MM"
+fn parse_run_at(value: &str) -> Result<String, String> {
+    let value = value.trim().replace('T', " ");
+    NaiveDateTime::parse_from_str(&value, RUN_AT_FORMAT)
+        .map(|t| t.format(RUN_AT_FORMAT).to_string())
+        .map_err(|_| format!("Invalid time: {} (expected YYYY-MM-DD HH:MM)", value))
+}
+
+// 由营业日的营业时间生成任务：每天开场 open，收摊 pause，最后一天收摊 close
+async fn session_entries(
+    db: &SqlitePool,
+    event_id: i64,
+) -> Vec<(Option<i64>, &'static str, String)> {
+    let sessions = fetch_sessions(db, event_id).await;
+    let last = sessions.len().saturating_sub(1);
+    let mut entries = Vec::new();
+    for (index, session) in sessions.iter().enumerate() {
+        if let Some(opens_at) = &session.opens_at {
+            entries.push((
+                Some(session.id),
+                "open",
+                format!("{} {}", session.session_date, opens_at),
+            ));
+        }
+        if let Some(closes_at) = &session.closes_at {
+            let action = if index == last { "close" } else { "pause" };
+            entries.push((
+                Some(session.id),
+                action,
+                format!("{} {}", session.session_date, closes_at),
+            ));
+        }
+    }
+    entries
+}
+
+async fn replace_pending(
+    tx: &mut Transaction<'_, Sqlite>,
+    event_id: i64,
+    entries: &[(Option<i64>, &str, String)],
+) -> Result<(), sqlx::Error> {
+    query("DELETE FROM event_schedules WHERE event_id = ? AND state = 'pending'")
+        .bind(event_id)
+        .execute(&mut **tx)
+        .await?;
+    for (session_id, action, run_at) in entries {
+        query("INSERT INTO event_schedules (event_id, session_id, action, run_at) VALUES (?, ?, ?, ?)")
+            .bind(event_id)
+            .bind(session_id)
+            .bind(action)
+            .bind(run_at)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+// 营业日调整后，按营业时间生成的待执行任务随之重建
+pub(crate) async fn refresh_session_schedule(
+    db: &SqlitePool,
+    event_id: i64,
+) -> Result<(), sqlx::Error> {
+    let (session_based,): (i64,) = query_as(
+        "SELECT COUNT(*) FROM event_schedules WHERE event_id = ? AND state = 'pending' AND session_id IS NOT NULL",
+    )
+    .bind(event_id)
+    .fetch_one(db)
+    .await?;
+    if session_based == 0 {
+        return Ok(());
+    }
+
+    let entries = session_entries(db, event_id).await;
+    let mut tx = db.begin().await?;
+    replace_pending(&mut tx, event_id, &entries).await?;
+    tx.commit().await
+}
+
+// ==========================================
+// 3. 清空待执行任务 (Admin Only)
+// ==========================================
+async fn clear_schedule(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(event_id): Path<i64>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+    let _ = query("DELETE FROM event_schedules WHERE event_id = ? AND state = 'pending'")
+        .bind(event_id)
+        .execute(&state.db)
+        .await;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+// ==========================================
+// 4. 即将执行的定时任务 (Admin Only)
+// ==========================================
+async fn list_upcoming(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Query(params): Query<UpcomingQuery>,
+) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(50).clamp(1, 500);
+    let upcoming: Vec<UpcomingSchedule> = query_as(
+        r#"
+        SELECT s.*, e.name AS event_name, e.status AS event_status
+        FROM event_schedules s
+        JOIN events e ON s.event_id = e.id
+        WHERE s.state = 'pending' AND e.deleted_at IS NULL
+        ORDER BY s.run_at ASC, s.id ASC
+        LIMIT ?
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await
+    .unwrap_or_default();
+
+    Json(upcoming).into_response()
+}
+
+// ==========================================
+// 5. 执行到期任务 (后台调度器调用)
+// ==========================================
+// 每个场次只执行最晚的一条到期任务，更早的视为已错过并跳过。
+// 这样服务在营业途中重启后，会直接恢复到此刻应处的状态。回收站中的场次暂不执行。
+pub async fn apply_due_schedules(db: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let now = Local::now().format(RUN_AT_FORMAT).to_string();
+    let due: Vec<EventSchedule> = query_as(
+        r#"
+        SELECT s.* FROM event_schedules s
+        JOIN events e ON s.event_id = e.id
+        WHERE s.state = 'pending' AND s.run_at <= ? AND e.deleted_at IS NULL
+        ORDER BY s.event_id ASC, s.run_at ASC, s.id ASC
+        "#,
+    )
+    .bind(&now)
+    .fetch_all(db)
+    .await?;
+
+    let mut applied = 0;
+    for (index, schedule) in due.iter().enumerate() {
+        let superseded = due
+            .get(index + 1)
+            .is_some_and(|next| next.event_id == schedule.event_id);
+        if superseded {
+            mark(
+                db,
+                schedule.id,
+                "skipped",
+                Some("Superseded by a later schedule"),
+            )
+            .await?;
+            continue;
+        }
+
+        let event: Option<Event> =
+            query_as("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
+                .bind(schedule.event_id)
+                .fetch_optional(db)
+                .await?;
+        let Some(event) = event else {
+            continue;
+        };
+
+        // 目标状态；None 表示无需变更
+        let target = match (schedule.action.as_str(), event.status) {
+            ("open", EventStatus::Ended) => {
+                mark(db, schedule.id, "skipped", Some("Event has already ended")).await?;
+                continue;
+            }
+            ("open", EventStatus::NotStarted) => Some(EventStatus::Active),
+            ("pause", EventStatus::Active) => Some(EventStatus::NotStarted),
+            ("close", EventStatus::NotStarted | EventStatus::Active) => Some(EventStatus::Ended),
+            ("open" | "pause" | "close", _) => None,
+            _ => {
+                mark(db, schedule.id, "skipped", Some("Unknown action")).await?;
+                continue;
+            }
+        };
+
+        if let Some(next) = target {
+            // 暂停 (进行中 -> 未进行) 不在常规状态机内，按强制变更记录审计日志
+            let forced = !event.status.can_transition_to(next);
+            // 期间被手动修改过：保持待执行，下一轮按最新状态重新判断
+            if change_status(db, &event, next, forced, "schedule")
+                .await?
+                .is_none()
+            {
+                continue;
+            }
+            println!(
+                "[Scheduler] Event {} -> {} ({})",
+                event.id,
+                next.as_str(),
+                schedule.action
+            );
+        }
+        mark(db, schedule.id, "applied", None).await?;
+        applied += 1;
+    }
+
+    Ok(applied)
+}
+
+async fn mark(
+    db: &SqlitePool,
+    schedule_id: i64,
+    state: &str,
+    note: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    query(
+        "UPDATE event_schedules SET state = ?, note = ?, processed_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(state)
+    .bind(note)
+    .bind(schedule_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,362 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    routing::{get, post},
+    Router,
+};
+use chrono::{Local, NaiveDate, NaiveTime};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{query, query_as, Sqlite, Transaction};
+use std::collections::HashSet;
+
+use crate::{
+    api::{event::event_exists, guard::AdminOnly, schedule},
+    db::models::EventSession,
+    state::AppState,
+    utils::security::Claims,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 公开：查看营业日；管理员：整体替换营业日列表
+        .route(
+            "/events/:event_id/sessions",
+            get(list_sessions).put(replace_sessions),
+        )
+        // 管理员/摊主：记录开场/收摊库存快照
+        .route(
+            "/events/:event_id/sessions/:session_id/snapshot",
+            post(record_snapshot),
+        )
+}
+
+// ==========================================
+// DTOs (数据传输对象)
+// ==========================================
+
+#[derive(Deserialize)]
+struct ReplaceSessionsRequest {
+    sessions: Vec<EventSession>,
+}
+
+#[derive(Deserialize)]
+struct SnapshotRequest {
+    kind: String, // "opening" | "closing"
+}
+
+// ==========================================
+// 1. 查看营业日 (Public)
+// ==========================================
+async fn list_sessions(
+    State(state): State<AppState>,
+    Path(event_id): Path<i64>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+    Json(fetch_sessions(&state.db, event_id).await).into_response()
+}
+
+// ==========================================
+// 2. 替换营业日列表 (Admin Only - JSON)
+// ==========================================
+// 按日期匹配：已有日期保留 id 并更新营业时间，新日期新增，未列出的日期删除。
+// 已有订单归属的营业日不允许删除。场次的 event_date 同步为第一天。
+async fn replace_sessions(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(event_id): Path<i64>,
+    Json(payload): Json<ReplaceSessionsRequest>,
+) -> impl IntoResponse {
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    let sessions = match normalize_sessions(payload.sessions) {
+        Ok(s) => s,
+        Err(msg) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response();
+        }
+    };
+
+    let existing = fetch_sessions(&state.db, event_id).await;
+    let keep: HashSet<&str> = sessions.iter().map(|s| s.session_date.as_str()).collect();
+    let removed: Vec<&EventSession> = existing
+        .iter()
+        .filter(|s| !keep.contains(s.session_date.as_str()))
+        .collect();
+
+    // 有订单的营业日不能删除，否则每日统计会丢失归属
+    for session in &removed {
+        let (orders,): (i64,) = query_as("SELECT COUNT(*) FROM orders WHERE session_id = ?")
+            .bind(session.id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap_or((0,));
+        if orders > 0 {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": format!("Session {} has {} orders and cannot be removed", session.session_date, orders),
+                    "date": session.session_date,
+                    "orders": orders
+                })),
+            )
+                .into_response();
+        }
+    }
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        for session in &removed {
+            query("DELETE FROM event_sessions WHERE id = ?")
+                .bind(session.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for session in &sessions {
+            query(
+                r#"
+                INSERT INTO event_sessions (event_id, session_date, opens_at, closes_at)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT (event_id, session_date)
+                DO UPDATE SET opens_at = excluded.opens_at, closes_at = excluded.closes_at
+                "#,
+            )
+            .bind(event_id)
+            .bind(&session.session_date)
+            .bind(&session.opens_at)
+            .bind(&session.closes_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        // 列表已按日期排序，第一天即展会日期
+        query(
+            "UPDATE events SET event_date = ?, version = version + 1 WHERE id = ? AND event_date != ?",
+        )
+        .bind(&sessions[0].session_date)
+        .bind(event_id)
+        .bind(&sessions[0].session_date)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+    .await;
+
+    // 按营业时间生成的定时开闭场随之更新
+    let result = match result {
+        Ok(()) => schedule::refresh_session_schedule(&state.db, event_id).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => Json(fetch_sessions(&state.db, event_id).await).into_response(),
+        Err(e) => {
+            eprintln!("Replace sessions error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// 校验日期/营业时间，按日期排序；营业日列表不能为空且日期不能重复
+fn normalize_sessions(mut sessions: Vec<EventSession>) -> Result<Vec<EventSession>, String> {
+    if sessions.is_empty() {
+        return Err("At least one session is required".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    for session in sessions.iter_mut() {
+        let date = NaiveDate::parse_from_str(session.session_date.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid session date: {}", session.session_date))?;
+        session.session_date = date.format("%Y-%m-%d").to_string();
+        if !seen.insert(session.session_date.clone()) {
+            return Err(format!("Duplicate session date: {}", session.session_date));
+        }
+
+        let opens = parse_time(&mut session.opens_at)?;
+        let closes = parse_time(&mut session.closes_at)?;
+        if let (Some(o), Some(c)) = (opens, closes) {
+            if o >= c {
+                return Err(format!(
+                    "Session {} closes before it opens",
+                    session.session_date
+                ));
+            }
+        }
+    }
+
+    sessions.sort_by(|a, b| a.session_date.cmp(&b.session_date));
+    Ok(sessions)
+}
+
+// 营业时间统一为 HH:MM；空字符串视为未设置
+fn parse_time(value: &mut Option<String>) -> Result<Option<NaiveTime>, String> {
+    let Some(raw) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) else {
+        *value = None;
+        return Ok(None);
+    };
+    let time = NaiveTime::parse_from_str(raw, "%H:%M")
+        .map_err(|_| format!("Invalid time: {} (expected HH:MM)", raw))?;
+    *value = Some(time.format("%H:%M").to_string());
+    Ok(Some(time))
+}
+
+// ==========================================
+// 3. 记录库存快照 (Admin/Vendor Scoped)
+// ==========================================
+// 以各商品当前库存作为该营业日的开场或收摊库存，重复记录会覆盖
+async fn record_snapshot(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path((event_id, session_id)): Path<(i64, i64)>,
+    Json(payload): Json<SnapshotRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = check_write_permission(&claims, event_id) {
+        return e.into_response();
+    }
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    let column = match payload.kind.as_str() {
+        "opening" => "opening_stock",
+        "closing" => "closing_stock",
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid kind. Must be one of: opening, closing"})),
+            )
+                .into_response()
+        }
+    };
+
+    let session: Option<EventSession> =
+        query_as("SELECT * FROM event_sessions WHERE id = ? AND event_id = ?")
+            .bind(session_id)
+            .bind(event_id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
+    if session.is_none() {
+        return (StatusCode::NOT_FOUND, "Session not found").into_response();
+    }
+
+    let sql = format!(
+        r#"
+        INSERT INTO event_session_stock (session_id, product_id, {column})
+        SELECT ?, id, current_stock FROM products WHERE event_id = ?
+        ON CONFLICT (session_id, product_id) DO UPDATE SET {column} = excluded.{column}
+        "#
+    );
+    match query(&sql)
+        .bind(session_id)
+        .bind(event_id)
+        .execute(&state.db)
+        .await
+    {
+        Ok(r) => Json(json!({
+            "session_id": session_id,
+            "kind": payload.kind,
+            "products": r.rows_affected()
+        }))
+        .into_response(),
+        Err(e) => {
+            eprintln!("Record snapshot error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 共享辅助函数 (订单/统计/场次复用)
+// ==========================================
+
+pub(crate) async fn fetch_sessions(db: &sqlx::SqlitePool, event_id: i64) -> Vec<EventSession> {
+    query_as("SELECT * FROM event_sessions WHERE event_id = ? ORDER BY session_date ASC")
+        .bind(event_id)
+        .fetch_all(db)
+        .await
+        .unwrap_or_default()
+}
+
+// 新订单归属的营业日：今天 (本地日期) 所在的营业日；不在营业日内时
+// 归到今天之前最近的一天，展会尚未开始则归到第一天。没有营业日时返回 None。
+// 同时在当日首笔订单扣减库存前记录开场库存。
+pub(crate) async fn attribute_order(
+    tx: &mut Transaction<'_, Sqlite>,
+    event_id: i64,
+) -> Result<Option<i64>, sqlx::Error> {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let session: Option<(i64,)> = query_as(
+        r#"
+        SELECT id FROM event_sessions
+        WHERE event_id = ?
+        ORDER BY (session_date <= ?) DESC,
+                 CASE WHEN session_date <= ? THEN session_date END DESC,
+                 session_date ASC
+        LIMIT 1
+        "#,
+    )
+    .bind(event_id)
+    .bind(&today)
+    .bind(&today)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some((session_id,)) = session else {
+        return Ok(None);
+    };
+
+    query(
+        r#"
+        INSERT INTO event_session_stock (session_id, product_id, opening_stock)
+        SELECT ?, id, current_stock FROM products WHERE event_id = ?
+        ON CONFLICT (session_id, product_id) DO UPDATE SET opening_stock = excluded.opening_stock
+        WHERE event_session_stock.opening_stock IS NULL
+        "#,
+    )
+    .bind(session_id)
+    .bind(event_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(Some(session_id))
+}
+
+// 新建场次时创建默认营业日
+pub(crate) async fn create_default_session(
+    tx: &mut Transaction<'_, Sqlite>,
+    event_id: i64,
+    date: &str,
+) -> Result<(), sqlx::Error> {
+    query("INSERT OR IGNORE INTO event_sessions (event_id, session_date) VALUES (?, ?)")
+        .bind(event_id)
+        .bind(date)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+// ==========================================
+// 权限检查辅助函数 (简单版)
+// ==========================================
+fn check_read_permission(claims: &Claims, event_id: i64) -> Result<(), (StatusCode, &'static str)> {
+    if claims.role == "admin"
+        || (claims.role == "vendor"
+            && (claims.access == "all" || claims.event_id == Some(event_id)))
+    {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "Access denied"))
+    }
+}
+
+fn check_write_permission(
+    claims: &Claims,
+    event_id: i64,
+) -> Result<(), (StatusCode, &'static str)> {
+    // 读写逻辑目前一致
+    check_read_permission(claims, event_id)
+}
@@ -0,0 +1,762 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
+    routing::{get, post},
+    Router,
+};
+use chrono::Local;
+use rust_xlsxwriter::{Color, Format, FormatBorder, Workbook};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{query, query_as};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+use crate::{
+    api::{
+        guard::AdminOnly,
+        master_product::{attach_tags, replace_cover_image},
+        sync::merge_tags,
+    },
+    db::models::{MasterProduct, Tag},
+    state::AppState,
+    utils::{
+        image::{save_image_bytes, MAX_UPLOAD_BYTES},
+        search,
+    },
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sync/export-catalog", get(export_catalog))
+        .route("/sync/import-catalog", post(import_catalog))
+        .layer(DefaultBodyLimit::max(1000 * 1024 * 1024))
+}
+
+// ==========================================
+// 列定义 (表头 ↔ 字段)
+// ==========================================
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Column {
+    ProductCode,
+    Name,
+    DefaultPrice,
+    Category,
+    IsActive,
+    Tags,
+    Image,
+}
+
+// (字段, 导出表头, 可识别的表头别名)
+const COLUMNS: [(Column, &str, &[&str]); 7] = [
+    (
+        Column::ProductCode,
+        "商品编号",
+        &["product_code", "code", "编号", "商品编号", "货号", "sku"],
+    ),
+    (
+        Column::Name,
+        "名称",
+        &["name", "名称", "商品名", "商品名称", "品名"],
+    ),
+    (
+        Column::DefaultPrice,
+        "价格",
+        &["default_price", "price", "价格", "售价", "单价", "定价"],
+    ),
+    (Column::Category, "分类", &["category", "分类", "类别"]),
+    (
+        Column::IsActive,
+        "上架",
+        &["is_active", "active", "上架", "状态", "是否上架"],
+    ),
+    (Column::Tags, "标签", &["tags", "tag", "标签"]),
+    (
+        Column::Image,
+        "图片",
+        &["image", "image_file", "图片", "图片文件", "图片文件名"],
+    ),
+];
+
+fn column_by_key(key: &str) -> Option<Column> {
+    let key = key.trim().to_lowercase();
+    COLUMNS
+        .iter()
+        .find(|(_, _, aliases)| aliases.iter().any(|a| a.to_lowercase() == key))
+        .map(|(col, _, _)| *col)
+}
+
+// ==========================================
+// 1. 导出 (CSV / XLSX)
+// ==========================================
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>, // "csv" | "xlsx" (默认)
+}
+
+// 标签单元格格式: "组名:标签; 组名:标签"
+fn format_tags(product: &MasterProduct) -> String {
+    product
+        .tags
+        .iter()
+        .map(|t| format!("{}:{}", t.group_name, t.name))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// 图片列导出文件名，便于和导入时的 ZIP 文件名对应
+fn image_file_name(product: &MasterProduct) -> String {
+    product
+        .image_url
+        .as_deref()
+        .and_then(|url| url.rsplit('/').next())
+        .unwrap_or("")
+        .to_string()
+}
+
+async fn export_catalog(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Query(params): Query<ExportQuery>,
+) -> impl IntoResponse {
+    let mut products: Vec<MasterProduct> =
+        query_as("SELECT * FROM master_products ORDER BY product_code ASC")
+            .fetch_all(&state.db)
+            .await
+            .unwrap_or_default();
+    attach_tags(&state.db, &mut products).await;
+
+    let rows: Vec<[String; 7]> = products
+        .iter()
+        .map(|p| {
+            [
+                p.product_code.clone(),
+                p.name.clone(),
+                p.default_price.to_string(),
+                p.category.clone().unwrap_or_default(),
+                if p.is_active { "1" } else { "0" }.to_string(),
+                format_tags(p),
+                image_file_name(p),
+            ]
+        })
+        .collect();
+    let headers: Vec<&str> = COLUMNS.iter().map(|(_, h, _)| *h).collect();
+    let stamp = Local::now().format("%Y%m%d_%H%M");
+
+    if params.format.as_deref() == Some("csv") {
+        // 带 BOM，Excel 直接打开不会乱码
+        let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+        let mut result = writer.write_record(&headers);
+        for row in &rows {
+            if result.is_ok() {
+                result = writer.write_record(row);
+            }
+        }
+        let buf = match result
+            .map_err(|e| e.to_string())
+            .and_then(|_| writer.into_inner().map_err(|e| e.to_string()))
+        {
+            Ok(buf) => buf,
+            Err(e) => {
+                eprintln!("CSV export error: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to generate csv")
+                    .into_response();
+            }
+        };
+
+        let disposition = format!("attachment; filename=\"catalog_{}.csv\"", stamp);
+        return (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (header::CONTENT_DISPOSITION, disposition.as_str()),
+            ],
+            buf,
+        )
+            .into_response();
+    }
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    let header_format = Format::new()
+        .set_bold()
+        .set_background_color(Color::RGB(0xDDEBF7))
+        .set_border(FormatBorder::Thin);
+    let price_format = Format::new().set_num_format("0.00");
+
+    let widths = [14.0, 36.0, 10.0, 14.0, 8.0, 36.0, 44.0];
+    for (col, (title, width)) in headers.iter().zip(widths).enumerate() {
+        let _ = worksheet.set_column_width(col as u16, width);
+        let _ = worksheet.write_string_with_format(0, col as u16, *title, &header_format);
+    }
+    for (i, (row, product)) in rows.iter().zip(&products).enumerate() {
+        let r = (i + 1) as u32;
+        for (col, value) in row.iter().enumerate() {
+            let _ = match col {
+                2 => worksheet.write_number_with_format(
+                    r,
+                    col as u16,
+                    product.default_price,
+                    &price_format,
+                ),
+                4 => worksheet.write_number(r, col as u16, if product.is_active { 1 } else { 0 }),
+                _ => worksheet.write_string(r, col as u16, value),
+            };
+        }
+    }
+    let _ = worksheet.set_freeze_panes(1, 0);
+
+    match workbook.save_to_buffer() {
+        Ok(buf) => {
+            let disposition = format!("attachment; filename=\"catalog_{}.xlsx\"", stamp);
+            (
+                [
+                    (
+                        header::CONTENT_TYPE,
+                        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+                    ),
+                    (header::CONTENT_DISPOSITION, disposition.as_str()),
+                ],
+                buf,
+            )
+                .into_response()
+        }
+        Err(e) => {
+            eprintln!("Excel export error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to generate excel",
+            )
+                .into_response()
+        }
+    }
+}
+
+// ==========================================
+// 2. 导入 (CSV / XLSX + 可选图片 ZIP)
+// ==========================================
+// Multipart 字段:
+// - file: 表格文件 (.csv / .xlsx)
+// - images: (可选) 图片 ZIP，按"图片"列的文件名匹配，未填写时按商品编号匹配 (如 A01.jpg)
+// - mapping: (可选) JSON，自定义表头 → 字段，如 {"品番": "product_code", "备注": ""}
+// - dry_run: (可选) "true" 时只校验不写入
+#[derive(Serialize)]
+struct RowIssue {
+    row: usize, // 表格中的行号 (表头为第 1 行)
+    column: Option<String>,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ColumnMapping {
+    header: String,
+    field: Option<Column>,
+}
+
+#[derive(Serialize, Default)]
+struct ImportReport {
+    dry_run: bool,
+    total_rows: usize,
+    created: usize,
+    updated: usize,
+    skipped: usize,
+    images_attached: usize,
+    columns: Vec<ColumnMapping>,
+    errors: Vec<RowIssue>,   // 导致该行被跳过的问题
+    warnings: Vec<RowIssue>, // 不影响导入的问题 (如找不到图片)
+}
+
+// 通过校验的一行；None 表示表格中没有该列，沿用已有值
+struct ParsedRow {
+    row: usize,
+    product_code: String,
+    name: String,
+    default_price: Option<f64>,
+    category: Option<Option<String>>,
+    is_active: Option<bool>,
+    tags: Option<Vec<Tag>>,
+    image: Option<String>,
+}
+
+fn bad_request(msg: &str) -> axum::response::Response {
+    (StatusCode::BAD_REQUEST, Json(json!({"error": msg}))).into_response()
+}
+
+async fn import_catalog(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut sheet: Option<(String, Vec<u8>)> = None;
+    let mut images_zip: Option<Vec<u8>> = None;
+    let mut custom_mapping: HashMap<String, String> = HashMap::new();
+    let mut dry_run = false;
+
+    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
+        let field_name = field.name().unwrap_or("").to_string();
+        match field_name.as_str() {
+            "file" => {
+                let file_name = field.file_name().unwrap_or("").to_lowercase();
+                match field.bytes().await {
+                    Ok(data) => sheet = Some((file_name, data.to_vec())),
+                    Err(e) => return bad_request(&format!("Upload error: {}", e)),
+                }
+            }
+            "images" => match field.bytes().await {
+                Ok(data) => images_zip = Some(data.to_vec()),
+                Err(e) => return bad_request(&format!("Upload error: {}", e)),
+            },
+            "mapping" => {
+                let value = field.text().await.unwrap_or_default();
+                if !value.trim().is_empty() {
+                    match serde_json::from_str(&value) {
+                        Ok(m) => custom_mapping = m,
+                        Err(_) => return bad_request("Invalid mapping JSON"),
+                    }
+                }
+            }
+            "dry_run" => dry_run = field.text().await.unwrap_or_default() == "true",
+            _ => {}
+        }
+    }
+
+    let (file_name, data) = match sheet {
+        Some(s) => s,
+        None => return bad_request("No file found in request"),
+    };
+
+    // 1. 读取表格 (第一张工作表)
+    let table = match read_table(&file_name, data) {
+        Ok(t) => t,
+        Err(e) => return bad_request(&e),
+    };
+    let (header_row, data_rows) = match table.split_first() {
+        Some(split) => split,
+        None => return bad_request("Spreadsheet is empty"),
+    };
+
+    // 2. 表头映射：自定义映射优先，其余按别名自动识别
+    let custom_mapping: HashMap<String, String> = custom_mapping
+        .into_iter()
+        .map(|(k, v)| (k.trim().to_lowercase(), v))
+        .collect();
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+    let mut column_index: HashMap<Column, usize> = HashMap::new();
+    for (idx, header_cell) in header_row.iter().enumerate() {
+        let key = header_cell.trim().to_lowercase();
+        let field = match custom_mapping.get(&key) {
+            Some(target) => column_by_key(target),
+            None => column_by_key(&key),
+        };
+        if let Some(col) = field {
+            column_index.entry(col).or_insert(idx);
+        }
+        report.columns.push(ColumnMapping {
+            header: header_cell.clone(),
+            field,
+        });
+    }
+
+    if !column_index.contains_key(&Column::ProductCode) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Missing product code column",
+                "columns": report.columns
+            })),
+        )
+            .into_response();
+    }
+
+    // 3. 逐行校验
+    let existing: HashMap<String, MasterProduct> =
+        query_as::<_, MasterProduct>("SELECT * FROM master_products")
+            .fetch_all(&state.db)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| (p.product_code.clone(), p))
+            .collect();
+
+    let mut parsed: Vec<ParsedRow> = Vec::new();
+    let mut seen_codes: HashSet<String> = HashSet::new();
+    for (i, cells) in data_rows.iter().enumerate() {
+        let row = i + 2;
+        // 整行为空直接忽略，不计入统计
+        if cells.iter().all(|c| c.trim().is_empty()) {
+            continue;
+        }
+        report.total_rows += 1;
+
+        match parse_row(row, cells, &column_index, &existing, &mut seen_codes) {
+            Ok(p) => parsed.push(p),
+            Err(issue) => report.errors.push(issue),
+        }
+    }
+    report.skipped = report.errors.len();
+
+    for p in &parsed {
+        if existing.contains_key(&p.product_code) {
+            report.updated += 1;
+        } else {
+            report.created += 1;
+        }
+    }
+
+    // 4. 预先从 ZIP 中找出每行对应的图片 (未上传 ZIP 时忽略图片列，导出的表格可直接回导)
+    let mut pending_images: Vec<(String, Vec<u8>)> = Vec::new();
+    if let Some(zip_data) = images_zip {
+        match collect_images(zip_data, &parsed, &mut report) {
+            Ok(images) => pending_images = images,
+            Err(e) => return bad_request(&e),
+        }
+    }
+
+    if dry_run {
+        report.images_attached = pending_images.len();
+        return Json(report).into_response();
+    }
+
+    // 5. 写入数据库 (单个事务)
+    if let Err(e) = write_rows(&state, &parsed, &existing).await {
+        eprintln!("Catalog import DB error: {:?}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("DB Write Failed: {}", e)})),
+        )
+            .into_response();
+    }
+
+    // 6. 处理图片 (替换封面)
+    for (code, bytes) in pending_images {
+        let row = parsed
+            .iter()
+            .find(|p| p.product_code == code)
+            .map(|p| p.row);
+        let product_id: Option<(i64,)> =
+            query_as("SELECT id FROM master_products WHERE product_code = ?")
+                .bind(&code)
+                .fetch_optional(&state.db)
+                .await
+                .unwrap_or(None);
+        let Some((product_id,)) = product_id else {
+            continue;
+        };
+
+        match save_image_bytes(&state.upload_dir, bytes, "products").await {
+            Ok(image) => {
+                replace_cover_image(&state, product_id, &image).await;
+                report.images_attached += 1;
+            }
+            Err(e) => report.warnings.push(RowIssue {
+                row: row.unwrap_or(0),
+                column: Some("image".to_string()),
+                message: format!("Invalid image: {}", e),
+            }),
+        }
+    }
+
+    if let Err(e) = search::rebuild_index(&state.db).await {
+        eprintln!("Search index rebuild failed: {:?}", e);
+    }
+
+    Json(report).into_response()
+}
+
+// ==========================================
+// 辅助：读取与解析
+// ==========================================
+
+// 读取第一张工作表为字符串矩阵；按扩展名/文件头判断格式
+fn read_table(file_name: &str, data: Vec<u8>) -> Result<Vec<Vec<String>>, String> {
+    let is_xlsx = file_name.ends_with(".xlsx")
+        || file_name.ends_with(".xls")
+        || file_name.ends_with(".ods")
+        || data.starts_with(b"PK");
+
+    if is_xlsx {
+        use calamine::{open_workbook_auto_from_rs, Reader};
+        let mut workbook = open_workbook_auto_from_rs(Cursor::new(data))
+            .map_err(|e| format!("Invalid spreadsheet: {}", e))?;
+        let range = workbook
+            .worksheet_range_at(0)
+            .ok_or("Spreadsheet has no worksheet")?
+            .map_err(|e| format!("Invalid spreadsheet: {}", e))?;
+        return Ok(range
+            .rows()
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect());
+    }
+
+    // CSV：去掉 Excel 导出的 BOM
+    let text = String::from_utf8(data).map_err(|_| "CSV must be UTF-8 encoded")?;
+    let text = text.trim_start_matches('\u{feff}');
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    reader
+        .records()
+        .map(|r| {
+            r.map(|rec| rec.iter().map(|s| s.to_string()).collect())
+                .map_err(|e| format!("Invalid CSV: {}", e))
+        })
+        .collect()
+}
+
+fn parse_row(
+    row: usize,
+    cells: &[String],
+    columns: &HashMap<Column, usize>,
+    existing: &HashMap<String, MasterProduct>,
+    seen_codes: &mut HashSet<String>,
+) -> Result<ParsedRow, RowIssue> {
+    let cell = |col: Column| -> Option<&str> {
+        columns
+            .get(&col)
+            .map(|idx| cells.get(*idx).map(|s| s.trim()).unwrap_or(""))
+    };
+    let issue = |col: &str, msg: &str| RowIssue {
+        row,
+        column: Some(col.to_string()),
+        message: msg.to_string(),
+    };
+
+    let product_code = cell(Column::ProductCode).unwrap_or("").to_string();
+    if product_code.is_empty() {
+        return Err(issue("product_code", "Product code is required"));
+    }
+    if !seen_codes.insert(product_code.clone()) {
+        return Err(issue("product_code", "Duplicate product code in file"));
+    }
+    let current = existing.get(&product_code);
+
+    // 名称：新商品必填；已有商品留空则保持不变
+    let name = match (cell(Column::Name), current) {
+        (Some(n), _) if !n.is_empty() => n.to_string(),
+        (_, Some(p)) => p.name.clone(),
+        _ => return Err(issue("name", "Name is required for new products")),
+    };
+
+    let default_price = match cell(Column::DefaultPrice) {
+        None | Some("") => None,
+        Some(v) => {
+            // 兼容 "¥45" / "45元" / "1,200" 等写法
+            let cleaned: String = v
+                .chars()
+                .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+                .collect();
+            match cleaned.parse::<f64>() {
+                Ok(p) if p >= 0.0 && p.is_finite() => Some(p),
+                _ => return Err(issue("default_price", "Invalid price")),
+            }
+        }
+    };
+
+    let category = cell(Column::Category).map(|v| {
+        if v.is_empty() {
+            None
+        } else {
+            Some(v.to_string())
+        }
+    });
+
+    let is_active = match cell(Column::IsActive) {
+        None | Some("") => None,
+        Some(v) => match v.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "y" | "是" | "上架" => Some(true),
+            "0" | "false" | "no" | "n" | "否" | "下架" => Some(false),
+            _ => return Err(issue("is_active", "Invalid active flag (use 1/0)")),
+        },
+    };
+
+    let tags = match cell(Column::Tags) {
+        None => None,
+        Some(v) => {
+            let mut tags = Vec::new();
+            for part in v
+                .split([';', '；', ',', '，', '、', '\n'])
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+            {
+                match part.split_once([':', '：']) {
+                    Some((group, tag)) if !group.trim().is_empty() && !tag.trim().is_empty() => {
+                        tags.push(Tag {
+                            id: 0,
+                            group_id: 0,
+                            name: tag.trim().to_string(),
+                            color: None,
+                            sort_order: 0,
+                            group_name: group.trim().to_string(),
+                        })
+                    }
+                    _ => {
+                        return Err(issue(
+                            "tags",
+                            &format!("Invalid tag \"{}\", expected \"group:tag\"", part),
+                        ))
+                    }
+                }
+            }
+            Some(tags)
+        }
+    };
+
+    let image = cell(Column::Image)
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string());
+
+    Ok(ParsedRow {
+        row,
+        product_code,
+        name,
+        default_price,
+        category,
+        is_active,
+        tags,
+        image,
+    })
+}
+
+// ZIP 内按文件名 (不区分大小写、忽略目录) 匹配；返回 (商品编号, 图片数据)
+fn collect_images(
+    zip_data: Vec<u8>,
+    rows: &[ParsedRow],
+    report: &mut ImportReport,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(zip_data)).map_err(|_| "Invalid image ZIP".to_string())?;
+
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    let mut by_stem: HashMap<String, usize> = HashMap::new();
+    for i in 0..archive.len() {
+        let Ok(file) = archive.by_index(i) else {
+            continue;
+        };
+        if file.is_dir() {
+            continue;
+        }
+        let base = file
+            .name()
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        // 跳过 macOS 打包产生的元数据文件
+        if base.is_empty() || base.starts_with("._") {
+            continue;
+        }
+        let stem = base.rsplit_once('.').map(|(s, _)| s).unwrap_or(&base);
+        by_stem.entry(stem.to_string()).or_insert(i);
+        by_name.insert(base, i);
+    }
+
+    let mut images = Vec::new();
+    for row in rows {
+        let index = match &row.image {
+            Some(name) => {
+                let key = name.rsplit(['/', '\\']).next().unwrap_or("").to_lowercase();
+                let found = by_name.get(&key).or_else(|| by_stem.get(&key)).copied();
+                if found.is_none() {
+                    report.warnings.push(RowIssue {
+                        row: row.row,
+                        column: Some("image".to_string()),
+                        message: format!("Image \"{}\" not found in ZIP", name),
+                    });
+                }
+                found
+            }
+            None => by_stem.get(&row.product_code.to_lowercase()).copied(),
+        };
+
+        if let Some(index) = index {
+            // 读取前先按声明大小拒绝超大文件，读取时再用 take 限制实际字节数 (声明大小可被伪造)
+            let mut buf = Vec::new();
+            let read = archive
+                .by_index(index)
+                .map_err(|e| e.to_string())
+                .and_then(|f| {
+                    if f.size() > MAX_UPLOAD_BYTES as u64 {
+                        return Err("Image too large (max 20MB)".to_string());
+                    }
+                    f.take(MAX_UPLOAD_BYTES as u64 + 1)
+                        .read_to_end(&mut buf)
+                        .map_err(|e| e.to_string())?;
+                    if buf.len() > MAX_UPLOAD_BYTES {
+                        return Err("Image too large (max 20MB)".to_string());
+                    }
+                    Ok(())
+                });
+            match read {
+                Ok(_) => images.push((row.product_code.clone(), buf)),
+                Err(e) => report.warnings.push(RowIssue {
+                    row: row.row,
+                    column: Some("image".to_string()),
+                    message: format!("Failed to read image: {}", e),
+                }),
+            }
+        }
+    }
+    Ok(images)
+}
+
+async fn write_rows(
+    state: &AppState,
+    rows: &[ParsedRow],
+    existing: &HashMap<String, MasterProduct>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = state.db.begin().await?;
+
+    for row in rows {
+        let current = existing.get(&row.product_code);
+        let default_price = row
+            .default_price
+            .or(current.map(|p| p.default_price))
+            .unwrap_or(0.0);
+        let category = match &row.category {
+            Some(c) => c.clone(),
+            None => current.and_then(|p| p.category.clone()),
+        };
+        let is_active = row
+            .is_active
+            .or(current.map(|p| p.is_active))
+            .unwrap_or(true);
+
+        let (product_id,): (i64,) = query_as(
+            r#"
+            INSERT INTO master_products (product_code, name, default_price, category, is_active)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(product_code) DO UPDATE SET
+                name = excluded.name,
+                default_price = excluded.default_price,
+                category = excluded.category,
+                is_active = excluded.is_active,
+                version = master_products.version + 1
+            RETURNING id
+            "#,
+        )
+        .bind(&row.product_code)
+        .bind(&row.name)
+        .bind(default_price)
+        .bind(&category)
+        .bind(is_active)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // 表格中有标签列时覆盖该商品的标签，不存在的组/标签自动创建
+        if let Some(tags) = &row.tags {
+            query("DELETE FROM master_product_tags WHERE master_product_id = ?")
+                .bind(product_id)
+                .execute(&mut *tx)
+                .await?;
+            merge_tags(&mut tx, product_id, tags).await?;
+        }
+    }
+
+    tx.commit().await
+}
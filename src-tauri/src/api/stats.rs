@@ -1,20 +1,27 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Json, Response},
     routing::get,
     Router,
 };
-use chrono::{NaiveDateTime, Timelike};
+use chrono::NaiveDateTime;
 
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use std::collections::HashMap;
 
-use crate::{db::models::Event, state::AppState, utils::security::Claims};
+use crate::{
+    api::{event::event_exists, event_settings::load_settings, session},
+    db::models::{Event, EventSession},
+    state::AppState,
+    utils::security::Claims,
+};
 
-use chrono::Local;
-use rust_xlsxwriter::{Color, Format, FormatAlign, FormatBorder, Workbook, Worksheet}; // 用于在Excel中显示生成时间（可选）
+use chrono::{Local, TimeZone};
+use rust_xlsxwriter::{
+    Chart, ChartType, Color, Format, FormatAlign, FormatBorder, Workbook, Worksheet,
+}; // 用于在Excel中显示生成时间（可选）
 
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -24,6 +31,9 @@ pub fn router() -> Router<AppState> {
             "/:event_id/sales_summary/download",
             get(download_sales_summary),
         )
+        .route("/:event_id/stats/tags", get(get_tag_stats))
+        .route("/:event_id/stats/sessions", get(get_session_stats))
+        .route("/:event_id/stats/basket", get(get_basket_stats))
 }
 
 #[derive(Deserialize)]
@@ -32,7 +42,14 @@ struct SummaryQuery {
     product_code: Option<String>,
     start_date: Option<String>,
     end_date: Option<String>,
-    interval_minutes: Option<i64>,
+    interval_minutes: Option<i64>, // 5 ~ 1440，默认取场次配置
+    session_id: Option<i64>,       // 只统计某个营业日
+    category: Option<String>,
+    group_by: Option<String>, // product / category：按商品或分类拆分序列
+    #[serde(default)]
+    cumulative: bool, // 附带累计值
+    #[serde(default)]
+    fill_empty: bool, // 没有销售的时间段补零
 }
 
 #[derive(Serialize, FromRow)]
@@ -58,11 +75,13 @@ async fn get_event_stats(
         return e.into_response();
     }
 
-    let event = match sqlx::query_as::<_, Event>("SELECT * FROM events WHERE id = ?")
-        .bind(event_id)
-        .fetch_optional(&state.db)
-        .await
-        .unwrap_or(None)
+    let event = match sqlx::query_as::<_, Event>(
+        "SELECT * FROM events WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(event_id)
+    .fetch_optional(&state.db)
+    .await
+    .unwrap_or(None)
     {
         Some(e) => e,
         None => return (StatusCode::NOT_FOUND, "Event not found").into_response(),
@@ -118,17 +137,31 @@ async fn get_event_stats(
     .await
     .unwrap_or_default();
 
+    // 按营业日拆分的汇总 (不含商品明细，明细见 /stats/sessions)
+    let daily: Vec<SessionSummary> = session_breakdown(&state.db, event_id)
+        .await
+        .into_iter()
+        .map(|b| SessionSummary {
+            session: b.session,
+            totals: b.totals,
+        })
+        .collect();
+
     #[derive(Serialize)]
     struct StatsResponse {
         event_info: Event,
+        currency_symbol: String,
         summary: SummaryStats,
         product_details: Vec<ProductSalesItem>,
+        daily: Vec<SessionSummary>,
     }
 
     Json(StatsResponse {
         event_info: event,
+        currency_symbol: load_settings(&state.db, event_id).await.currency_symbol,
         summary,
         product_details,
+        daily,
     })
     .into_response()
 }
@@ -147,11 +180,13 @@ async fn get_sales_summary(
     }
 
     // 检查展会存在性
-    let event = match sqlx::query_as::<_, Event>("SELECT * FROM events WHERE id = ?")
-        .bind(event_id)
-        .fetch_optional(&state.db)
-        .await
-        .unwrap_or(None)
+    let event = match sqlx::query_as::<_, Event>(
+        "SELECT * FROM events WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(event_id)
+    .fetch_optional(&state.db)
+    .await
+    .unwrap_or(None)
     {
         Some(e) => e,
         None => return (StatusCode::NOT_FOUND, "Event not found").into_response(),
@@ -182,6 +217,12 @@ async fn get_sales_summary(
         summary_query.push_str(" AND p.product_code = ?");
         sql_params.push(code.clone());
     }
+    if let Some(ref category) = params.category {
+        summary_query.push_str(
+            " AND COALESCE((SELECT NULLIF(mp.category, '') FROM master_products mp WHERE mp.id = p.master_product_id), '未分类') = ?",
+        );
+        sql_params.push(category.clone());
+    }
     if let Some(ref start) = params.start_date {
         summary_query.push_str(" AND DATE(o.created_at) >= ?");
         sql_params.push(start.clone());
@@ -190,6 +231,10 @@ async fn get_sales_summary(
         summary_query.push_str(" AND DATE(o.created_at) <= ?");
         sql_params.push(end.clone());
     }
+    if let Some(session_id) = params.session_id {
+        summary_query.push_str(" AND o.session_id = ?");
+        sql_params.push(session_id.to_string());
+    }
 
     summary_query.push_str(
         r#"
@@ -209,117 +254,351 @@ async fn get_sales_summary(
         q.fetch_all(&state.db).await.unwrap_or_default()
     };
 
-    // 2. 获取总销售额
-    let total_revenue: (f64,) = sqlx::query_as(
-        "SELECT COALESCE(SUM(o.total_amount), 0.0) FROM orders o WHERE o.event_id = ? AND o.status != 'cancelled'"
-    )
-    .bind(event_id)
-    .fetch_one(&state.db)
-    .await
-    .unwrap_or((0.0,));
-
-    // 3. 获取时间序列数据（用于图表）
-    #[derive(FromRow)]
-    struct TimePoint {
-        created_at: NaiveDateTime,
-        total_amount: f64,
+    // 2. 时间序列 (SQL 分桶)：整体一条，按商品/分类拆分时每组一条
+    let settings = load_settings(&state.db, event_id).await;
+    let interval_val = params
+        .interval_minutes
+        .unwrap_or(settings.stats_interval_minutes);
+    if !(MIN_INTERVAL_MINUTES..=MAX_INTERVAL_MINUTES).contains(&interval_val) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!(
+                    "interval_minutes must be between {} and {}",
+                    MIN_INTERVAL_MINUTES, MAX_INTERVAL_MINUTES
+                )
+            })),
+        )
+            .into_response();
     }
+    let group_by = match params.group_by.as_deref() {
+        None | Some("") | Some("none") => SeriesGroup::None,
+        Some("product") => SeriesGroup::Product,
+        Some("category") => SeriesGroup::Category,
+        Some(_) => return (
+            StatusCode::BAD_REQUEST,
+            Json(
+                serde_json::json!({"error": "Invalid group_by. Must be one of: product, category"}),
+            ),
+        )
+            .into_response(),
+    };
 
-    let mut ts_query = String::from(
-        r#"
-        SELECT o.created_at, o.total_amount
-        FROM orders o
-        WHERE o.event_id = ? AND o.status != 'cancelled'
-        "#,
-    );
-
-    let mut ts_params: Vec<String> = vec![event_id.to_string()];
-
-    // 如果指定了 product_code，需要在时间序列查询中也进行过滤
-    if let Some(ref code) = params.product_code {
-        ts_query.push_str(
-            r#"
-            AND EXISTS (
-                SELECT 1 FROM order_items oi2
-                LEFT JOIN products p2 ON oi2.product_id = p2.id
-                WHERE oi2.order_id = o.id AND p2.product_code = ?
-            )
-            "#,
-        );
-        ts_params.push(code.clone());
-    }
-    if let Some(ref start) = params.start_date {
-        ts_query.push_str(" AND DATE(o.created_at) >= ?");
-        ts_params.push(start.clone());
-    }
-    if let Some(ref end) = params.end_date {
-        ts_query.push_str(" AND DATE(o.created_at) <= ?");
-        ts_params.push(end.clone());
-    }
+    let filter = SeriesFilter {
+        product_code: params.product_code.clone(),
+        category: params.category.clone(),
+        start_date: params.start_date.clone(),
+        end_date: params.end_date.clone(),
+        session_id: params.session_id,
+    };
+    let interval_secs = interval_val * 60;
+    let overall = bucket_sales(
+        &state.db,
+        event_id,
+        interval_secs,
+        SeriesGroup::None,
+        &filter,
+    )
+    .await;
+    let grouped = match group_by {
+        SeriesGroup::None => Vec::new(),
+        group => bucket_sales(&state.db, event_id, interval_secs, group, &filter).await,
+    };
 
-    ts_query.push_str(" ORDER BY o.created_at ASC");
+    // 3. 总销售额：有筛选条件时只统计符合条件的明细 (与趋势图一致)，否则按订单实收合计
+    let filtered = filter.product_code.is_some()
+        || filter.category.is_some()
+        || filter.start_date.is_some()
+        || filter.end_date.is_some()
+        || filter.session_id.is_some();
+    let total_revenue = if filtered {
+        overall.iter().map(|r| r.revenue).sum()
+    } else {
+        sqlx::query_as::<_, (f64,)>(
+            "SELECT COALESCE(SUM(o.total_amount), 0.0) FROM orders o WHERE o.event_id = ? AND o.status != 'cancelled'",
+        )
+        .bind(event_id)
+        .fetch_one(&state.db)
+        .await
+        .map(|(total,)| total)
+        .unwrap_or(0.0)
+    };
 
-    let time_points = {
-        let mut q = sqlx::query_as::<_, TimePoint>(&ts_query);
-        q = q.bind(&ts_params[0]);
-        for param in &ts_params[1..] {
-            q = q.bind(param);
+    // 补零：坐标轴覆盖第一个到最后一个有销售的时间段
+    let axis = if params.fill_empty {
+        match (overall.first(), overall.last()) {
+            (Some(first), Some(last)) => {
+                let count = (last.bucket - first.bucket) / interval_secs + 1;
+                if count > MAX_BUCKETS {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({
+                            "error": format!("Too many buckets ({}), use a larger interval_minutes", count)
+                        })),
+                    )
+                        .into_response();
+                }
+                Some(
+                    (0..count)
+                        .map(|i| first.bucket + i * interval_secs)
+                        .collect::<Vec<_>>(),
+                )
+            }
+            _ => Some(Vec::new()),
         }
-        q.fetch_all(&state.db).await.unwrap_or_default()
+    } else {
+        None
     };
 
-    // 4. 按时间粒度分组（默认 60 分钟）
-    let interval_minutes = params.interval_minutes.unwrap_or(60);
-    let interval_val = if interval_minutes == 30 { 30 } else { 60 };
-
-    let mut bucketed: HashMap<String, f64> = HashMap::new();
-    for point in time_points {
-        let floored_minute =
-            (point.created_at.minute() / interval_val as u32) * interval_val as u32;
-        let bucket_time = point
-            .created_at
-            .with_minute(floored_minute)
-            .unwrap()
-            .with_second(0)
-            .unwrap();
-        let key = bucket_time.format("%Y-%m-%d %H:%M").to_string();
-        *bucketed.entry(key).or_insert(0.0) += point.total_amount;
-    }
-
-    // 5. 转换为时间序列格式
-    #[derive(Serialize)]
-    struct TimeseriesItem {
-        date: String,
-        revenue: f64,
-    }
+    let timeseries = build_points(&overall, axis.as_deref(), params.cumulative);
 
-    let mut timeseries: Vec<TimeseriesItem> = bucketed
+    let mut groups: Vec<(String, String, Vec<BucketRow>)> = Vec::new();
+    for row in grouped {
+        match groups.iter_mut().find(|(key, _, _)| *key == row.series_key) {
+            Some((_, _, rows)) => rows.push(row),
+            None => groups.push((row.series_key.clone(), row.series_label.clone(), vec![row])),
+        }
+    }
+    let mut series: Vec<SeriesItem> = groups
         .into_iter()
-        .map(|(date, revenue)| TimeseriesItem {
-            date,
-            revenue: (revenue * 100.0).round() / 100.0,
+        .map(|(key, label, rows)| SeriesItem {
+            key,
+            label,
+            total_revenue: round2(rows.iter().map(|r| r.revenue).sum()),
+            total_units: rows.iter().map(|r| r.units).sum(),
+            points: build_points(&rows, axis.as_deref(), params.cumulative),
         })
         .collect();
-
-    // 按时间排序
-    timeseries.sort_by(|a, b| a.date.cmp(&b.date));
+    series.sort_by(|a, b| b.total_revenue.total_cmp(&a.total_revenue));
 
     #[derive(Serialize)]
     struct SalesResponse {
         event_name: String,
+        currency_symbol: String,
+        interval_minutes: i64,
+        cumulative: bool,
         total_revenue: f64,
         summary: Vec<ProductSalesItem>,
         timeseries: Vec<TimeseriesItem>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        group_by: Option<&'static str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        series: Option<Vec<SeriesItem>>,
     }
 
     Json(SalesResponse {
         event_name: event.name,
-        total_revenue: (total_revenue.0 * 100.0).round() / 100.0,
+        currency_symbol: settings.currency_symbol,
+        interval_minutes: interval_val,
+        cumulative: params.cumulative,
+        total_revenue: round2(total_revenue),
         summary,
         timeseries,
+        group_by: group_by.name(),
+        series: group_by.name().map(|_| series),
     })
     .into_response()
 }
+
+// ==========================================
+// 时间序列分桶 (销售趋势复用)
+// ==========================================
+// 分桶在 SQL 中完成：按 created_at (UTC) 的秒数整除区间长度。
+// 营收/件数只统计符合筛选条件的订单明细，订单数为包含这些明细的订单数。
+
+const MIN_INTERVAL_MINUTES: i64 = 5;
+const MAX_INTERVAL_MINUTES: i64 = 1440;
+// 补零后单条序列的最大点数，避免 5 分钟粒度覆盖过长时间
+const MAX_BUCKETS: i64 = 5000;
+
+#[derive(Clone, Copy)]
+enum SeriesGroup {
+    None,
+    Product,
+    Category,
+}
+
+impl SeriesGroup {
+    fn name(self) -> Option<&'static str> {
+        match self {
+            SeriesGroup::None => None,
+            SeriesGroup::Product => Some("product"),
+            SeriesGroup::Category => Some("category"),
+        }
+    }
+
+    // (分组键, 显示名称) 的 SQL 表达式
+    fn columns(self) -> (&'static str, &'static str) {
+        match self {
+            SeriesGroup::None => ("''", "''"),
+            // 已删除的场次商品没有编号，用明细里的商品 id 区分
+            SeriesGroup::Product => (
+                "COALESCE(p.product_code, '#' || oi.product_id)",
+                "MAX(oi.product_name)",
+            ),
+            SeriesGroup::Category => (
+                "COALESCE(NULLIF(mp.category, ''), '未分类')",
+                "COALESCE(NULLIF(mp.category, ''), '未分类')",
+            ),
+        }
+    }
+}
+
+struct SeriesFilter {
+    product_code: Option<String>,
+    category: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    session_id: Option<i64>,
+}
+
+#[derive(FromRow)]
+struct BucketRow {
+    bucket: i64, // 区间起点 (Unix 秒，UTC)
+    series_key: String,
+    series_label: String,
+    revenue: f64,
+    orders: i64,
+    units: i64,
+}
+
+#[derive(Serialize)]
+struct TimeseriesItem {
+    date: String,
+    revenue: f64,
+    orders: i64,
+    units: i64,
+    // cumulative=true 时附带截至该时间段的累计值
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cumulative_revenue: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cumulative_orders: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cumulative_units: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct SeriesItem {
+    key: String,
+    label: String,
+    total_revenue: f64,
+    total_units: i64,
+    points: Vec<TimeseriesItem>,
+}
+
+async fn bucket_sales(
+    db: &sqlx::SqlitePool,
+    event_id: i64,
+    interval_secs: i64,
+    group: SeriesGroup,
+    filter: &SeriesFilter,
+) -> Vec<BucketRow> {
+    let (key_expr, label_expr) = group.columns();
+    let mut sql = format!(
+        r#"
+        SELECT
+            (CAST(strftime('%s', o.created_at) AS INTEGER) / ?) * ? AS bucket,
+            {key_expr} AS series_key,
+            {label_expr} AS series_label,
+            COALESCE(SUM(oi.product_price * oi.quantity), 0.0) AS revenue,
+            COUNT(DISTINCT o.id) AS orders,
+            COALESCE(SUM(oi.quantity), 0) AS units
+        FROM order_items oi
+        JOIN orders o ON oi.order_id = o.id
+        LEFT JOIN products p ON oi.product_id = p.id
+        LEFT JOIN master_products mp ON p.master_product_id = mp.id
+        WHERE o.event_id = ? AND o.status != 'cancelled'
+        "#
+    );
+
+    let mut sql_params: Vec<String> = Vec::new();
+    if let Some(ref code) = filter.product_code {
+        sql.push_str(" AND p.product_code = ?");
+        sql_params.push(code.clone());
+    }
+    if let Some(ref category) = filter.category {
+        sql.push_str(" AND COALESCE(NULLIF(mp.category, ''), '未分类') = ?");
+        sql_params.push(category.clone());
+    }
+    if let Some(ref start) = filter.start_date {
+        sql.push_str(" AND DATE(o.created_at) >= ?");
+        sql_params.push(start.clone());
+    }
+    if let Some(ref end) = filter.end_date {
+        sql.push_str(" AND DATE(o.created_at) <= ?");
+        sql_params.push(end.clone());
+    }
+    if let Some(session_id) = filter.session_id {
+        sql.push_str(" AND o.session_id = ?");
+        sql_params.push(session_id.to_string());
+    }
+    sql.push_str(" GROUP BY bucket, series_key ORDER BY bucket ASC, series_key ASC");
+
+    let mut q = sqlx::query_as::<_, BucketRow>(&sql)
+        .bind(interval_secs)
+        .bind(interval_secs)
+        .bind(event_id);
+    for param in &sql_params {
+        q = q.bind(param);
+    }
+    q.fetch_all(db).await.unwrap_or_else(|e| {
+        eprintln!("Sales timeseries query error: {:?}", e);
+        Vec::new()
+    })
+}
+
+// rows 已按 bucket 升序；axis 为补零后的完整坐标轴
+fn build_points(rows: &[BucketRow], axis: Option<&[i64]>, cumulative: bool) -> Vec<TimeseriesItem> {
+    let by_bucket: HashMap<i64, &BucketRow> = rows.iter().map(|r| (r.bucket, r)).collect();
+    let buckets: Vec<i64> = match axis {
+        Some(axis) => axis.to_vec(),
+        None => rows.iter().map(|r| r.bucket).collect(),
+    };
+
+    let (mut sum_revenue, mut sum_orders, mut sum_units) = (0.0, 0, 0);
+    buckets
+        .into_iter()
+        .map(|bucket| {
+            let (revenue, orders, units) = by_bucket
+                .get(&bucket)
+                .map(|r| (r.revenue, r.orders, r.units))
+                .unwrap_or((0.0, 0, 0));
+            sum_revenue += revenue;
+            sum_orders += orders;
+            sum_units += units;
+            TimeseriesItem {
+                date: chrono::DateTime::from_timestamp(bucket, 0)
+                    .map(|t| t.naive_utc().format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default(),
+                revenue: round2(revenue),
+                orders,
+                units,
+                cumulative_revenue: cumulative.then(|| round2(sum_revenue)),
+                cumulative_orders: cumulative.then_some(sum_orders),
+                cumulative_units: cumulative.then_some(sum_units),
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+pub(crate) fn round4(value: f64) -> f64 {
+    (value * 10000.0).round() / 10000.0
+}
+
+// 解析逗号分隔的场次 ID 列表 (跨场次统计的 event_ids 参数)
+pub(crate) fn parse_event_ids(raw: &str) -> Result<Vec<i64>, String> {
+    raw.split(',')
+        .map(|part| {
+            part.trim()
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid event id: {}", part.trim()))
+        })
+        .collect()
+}
 // ==========================================
 // 3. 导出 Excel (Excel Download) - 美化版
 // ==========================================
@@ -332,14 +611,34 @@ async fn download_sales_summary(
         return e.into_response();
     }
 
-    // 获取展会名称
-    let event_name = sqlx::query_as::<_, Event>("SELECT * FROM events WHERE id = ?")
-        .bind(event_id)
-        .fetch_optional(&state.db)
-        .await
-        .unwrap_or(None)
-        .map(|e| e.name)
-        .unwrap_or_else(|| format!("Event {}", event_id));
+    // 获取展会名称及实际营业时间
+    let event =
+        sqlx::query_as::<_, Event>("SELECT * FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(event_id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
+    let Some(event) = event else {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    };
+    let event_name = event.name.clone();
+    // 数据库时间为 UTC，报表按本地时间显示
+    let local_time = |t: Option<NaiveDateTime>| {
+        t.map(|t| {
+            Local
+                .from_utc_datetime(&t)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+    };
+    let business_hours = match (event.started_at, event.ended_at) {
+        (started, ended) if started.is_some() => format!(
+            "营业时间: {} ~ {}    ",
+            local_time(started).unwrap_or_default(),
+            local_time(ended).unwrap_or_else(|| "进行中".to_string())
+        ),
+        _ => String::new(),
+    };
 
     // 获取数据
     let details = sqlx::query_as::<_, ProductSalesItem>(
@@ -365,56 +664,14 @@ async fn download_sales_summary(
     .await
     .unwrap_or_default();
 
-    use axum::http::header::{HeaderMap, HeaderValue};
-    use std::fs;
-
-    let temp_dir = std::env::temp_dir();
-    let filename = format!("sales_report_{}.xlsx", event_id);
-    let temp_file = temp_dir.join(&filename);
-
-    // --- Excel 生成逻辑 ---
+    // --- Excel 生成逻辑 (在内存中生成，并发下载互不影响) ---
     let mut workbook = Workbook::new();
     let worksheet = workbook.add_worksheet();
+    let _ = worksheet.set_name("汇总");
 
     // 1. 样式定义
-    let title_format = Format::new()
-        .set_bold()
-        .set_font_size(16)
-        .set_align(FormatAlign::Center)
-        .set_align(FormatAlign::VerticalCenter);
-
-    let header_format = Format::new()
-        .set_bold()
-        .set_background_color(Color::RGB(0xDDEBF7))
-        .set_border(FormatBorder::Thin)
-        .set_align(FormatAlign::Center)
-        .set_align(FormatAlign::VerticalCenter);
-
-    let text_format = Format::new()
-        .set_border(FormatBorder::Thin)
-        .set_align(FormatAlign::Left);
-
-    let center_format = Format::new()
-        .set_border(FormatBorder::Thin)
-        .set_align(FormatAlign::Center);
-
-    let currency_format = Format::new()
-        .set_border(FormatBorder::Thin)
-        .set_align(FormatAlign::Right)
-        .set_num_format("#,##0.00");
-
-    let total_row_format = Format::new()
-        .set_bold()
-        .set_background_color(Color::RGB(0xF2F2F2))
-        .set_border(FormatBorder::Thin)
-        .set_align(FormatAlign::Center);
-
-    let total_currency_format = Format::new()
-        .set_bold()
-        .set_background_color(Color::RGB(0xF2F2F2))
-        .set_border(FormatBorder::Thin)
-        .set_align(FormatAlign::Right)
-        .set_num_format("#,##0.00");
+    let settings = load_settings(&state.db, event_id).await;
+    let formats = ReportFormats::new(&settings.currency_symbol);
 
     // 2. 设置列宽
     let _ = worksheet.set_column_width(0, 15);
@@ -427,9 +684,13 @@ async fn download_sales_summary(
 
     // 3. 写入标题 (merge_range 保持不变，它支持带格式)
     let title_text = format!("{} 展会销售记录表", event_name);
-    let _ = worksheet.merge_range(0, 0, 0, 6, &title_text, &title_format);
+    let _ = worksheet.merge_range(0, 0, 0, 6, &title_text, &formats.title);
 
-    let time_str = format!("生成时间: {}", Local::now().format("%Y-%m-%d %H:%M"));
+    let time_str = format!(
+        "{}生成时间: {}",
+        business_hours,
+        Local::now().format("%Y-%m-%d %H:%M")
+    );
     let _ = worksheet.merge_range(
         1,
         0,
@@ -453,7 +714,7 @@ async fn download_sales_summary(
     for (col, text) in headers.iter().enumerate() {
         // 修改点：使用 write_string_with_format
         let _ =
-            worksheet.write_string_with_format(header_row_idx, col as u16, *text, &header_format);
+            worksheet.write_string_with_format(header_row_idx, col as u16, *text, &formats.header);
     }
 
     // 冻结窗格
@@ -467,30 +728,31 @@ async fn download_sales_summary(
     for item in details.iter() {
         // 修改点：所有带 format 的都加上 _with_format
         let _ =
-            worksheet.write_string_with_format(start_row, 0, &item.product_code, &center_format);
-        let _ = worksheet.write_string_with_format(start_row, 1, &item.product_name, &text_format);
+            worksheet.write_string_with_format(start_row, 0, &item.product_code, &formats.center);
+        let _ = worksheet.write_string_with_format(start_row, 1, &item.product_name, &formats.text);
         let _ = worksheet.write_number_with_format(
             start_row,
             2,
             item.initial_stock as f64,
-            &center_format,
+            &formats.center,
         );
-        let _ = worksheet.write_number_with_format(start_row, 4, item.unit_price, &currency_format);
+        let _ =
+            worksheet.write_number_with_format(start_row, 4, item.unit_price, &formats.currency);
         let _ = worksheet.write_number_with_format(
             start_row,
             5,
             item.total_quantity as f64,
-            &center_format,
+            &formats.center,
         );
         let _ = worksheet.write_number_with_format(
             start_row,
             6,
             item.total_revenue_per_item,
-            &currency_format,
+            &formats.currency,
         );
 
         // 第三列空着，用于现场填写结束数量进行盘点
-        let _ = worksheet.write_blank(start_row, 3, &text_format);
+        let _ = worksheet.write_blank(start_row, 3, &formats.text);
 
         sum_quantity += item.total_quantity;
         sum_revenue += item.total_revenue_per_item;
@@ -498,17 +760,17 @@ async fn download_sales_summary(
     }
 
     // 6. 写入总计
-    let _ = worksheet.write_string_with_format(start_row, 0, "总计", &total_row_format);
+    let _ = worksheet.write_string_with_format(start_row, 0, "总计", &formats.total_row);
 
     // 注意：write_blank 不需要 _with_format 就能应用背景色
-    let _ = worksheet.write_blank(start_row, 1, &total_row_format);
-    let _ = worksheet.write_blank(start_row, 2, &total_row_format);
-    let _ = worksheet.write_blank(start_row, 3, &total_row_format);
-    let _ = worksheet.write_blank(start_row, 4, &total_row_format);
+    let _ = worksheet.write_blank(start_row, 1, &formats.total_row);
+    let _ = worksheet.write_blank(start_row, 2, &formats.total_row);
+    let _ = worksheet.write_blank(start_row, 3, &formats.total_row);
+    let _ = worksheet.write_blank(start_row, 4, &formats.total_row);
 
     let _ =
-        worksheet.write_number_with_format(start_row, 5, sum_quantity as f64, &total_row_format);
-    let _ = worksheet.write_number_with_format(start_row, 6, sum_revenue, &total_currency_format);
+        worksheet.write_number_with_format(start_row, 5, sum_quantity as f64, &formats.total_row);
+    let _ = worksheet.write_number_with_format(start_row, 6, sum_revenue, &formats.total_currency);
 
     start_row += 1;
     // 7. 写入备注和签名窗格
@@ -548,31 +810,166 @@ async fn download_sales_summary(
         &instruction_format,
     );
 
-    // 保存
-    match workbook.save(&temp_file) {
-        Ok(_) => match fs::read(&temp_file) {
-            Ok(buf) => {
-                let _ = fs::remove_file(&temp_file);
-                let mut headers = HeaderMap::new();
-                headers.insert(
-                    axum::http::header::CONTENT_TYPE,
-                    HeaderValue::from_static(
+    // 各商品销售额柱状图，放在汇总表右侧
+    let data_rows = details.len() as u32;
+    if data_rows > 0 {
+        let first = header_row_idx + 1;
+        let last = header_row_idx + data_rows;
+        let mut chart = Chart::new(ChartType::Column);
+        chart
+            .add_series()
+            .set_name("销售额")
+            .set_categories(("汇总", first, 1, last, 1))
+            .set_values(("汇总", first, 6, last, 6));
+        chart.title().set_name("各商品销售额");
+        chart.legend().set_hidden();
+        let _ = worksheet.insert_chart(header_row_idx, 8, &chart);
+    }
+
+    // 8. 订单明细：每行一个订单明细，含已取消订单
+    let ledger = sqlx::query_as::<_, LedgerLine>(
+        r#"
+        SELECT o.id AS order_id,
+               datetime(o.created_at, 'localtime') AS local_time,
+               o.status,
+               o.payment_method,
+               o.total_amount,
+               COALESCE(p.product_code, '') AS product_code,
+               oi.product_name,
+               oi.product_price,
+               oi.quantity
+        FROM orders o
+        JOIN order_items oi ON oi.order_id = o.id
+        LEFT JOIN products p ON oi.product_id = p.id
+        WHERE o.event_id = ?
+        ORDER BY o.created_at ASC, o.id ASC, oi.id ASC
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(&state.db)
+    .await
+    .unwrap_or_default();
+    write_ledger_sheet(&mut workbook, &event_name, &ledger, &formats);
+
+    // 9. 销售趋势：按场次配置的统计间隔分桶，没有销售的时间段补零
+    let interval_secs = settings.stats_interval_minutes * 60;
+    let filter = SeriesFilter {
+        product_code: None,
+        category: None,
+        start_date: None,
+        end_date: None,
+        session_id: None,
+    };
+    let rows = bucket_sales(
+        &state.db,
+        event_id,
+        interval_secs,
+        SeriesGroup::None,
+        &filter,
+    )
+    .await;
+    let axis = match (rows.first(), rows.last()) {
+        (Some(first), Some(last)) if (last.bucket - first.bucket) / interval_secs < MAX_BUCKETS => {
+            Some(
+                (0..=(last.bucket - first.bucket) / interval_secs)
+                    .map(|i| first.bucket + i * interval_secs)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        _ => None,
+    };
+    let points = build_points(&rows, axis.as_deref(), true);
+    write_timeseries_sheet(
+        &mut workbook,
+        &event_name,
+        settings.stats_interval_minutes,
+        &points,
+        &formats,
+    );
+
+    // 10. 库存：场次全部商品的初始/已售/剩余
+    let stock = sqlx::query_as::<_, StockLine>(
+        r#"
+        SELECT p.product_code, p.name, p.price, p.initial_stock, p.current_stock,
+               COALESCE((
+                   SELECT SUM(oi.quantity)
+                   FROM order_items oi
+                   JOIN orders o ON oi.order_id = o.id
+                   WHERE oi.product_id = p.id AND o.status != 'cancelled'
+               ), 0) AS sold
+        FROM products p
+        WHERE p.event_id = ?
+        ORDER BY p.sort_order ASC, p.product_code ASC
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(&state.db)
+    .await
+    .unwrap_or_default();
+    write_stock_sheet(&mut workbook, &event_name, &stock, &formats);
+
+    // 11. 收款方式与订单状态
+    // 优惠金额 = 商品原价合计 - 实收金额 (订单金额被手动调整时不为 0)
+    let payments = sqlx::query_as::<_, PaymentLine>(
+        r#"
+        SELECT COALESCE(NULLIF(o.payment_method, ''), '未记录') AS label,
+               COUNT(*) AS orders,
+               COALESCE(SUM((
+                   SELECT SUM(oi.product_price * oi.quantity)
+                   FROM order_items oi WHERE oi.order_id = o.id
+               )), 0.0) AS list_amount,
+               COALESCE(SUM(o.total_amount), 0.0) AS collected
+        FROM orders o
+        WHERE o.event_id = ? AND o.status != 'cancelled'
+        GROUP BY label
+        ORDER BY collected DESC
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(&state.db)
+    .await
+    .unwrap_or_default();
+    let statuses: Vec<(String, i64, f64)> = sqlx::query_as(
+        r#"
+        SELECT status, COUNT(*), COALESCE(SUM(total_amount), 0.0)
+        FROM orders
+        WHERE event_id = ?
+        GROUP BY status
+        ORDER BY CASE status WHEN 'completed' THEN 0 WHEN 'pending' THEN 1 ELSE 2 END
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(&state.db)
+    .await
+    .unwrap_or_default();
+    write_payment_sheet(&mut workbook, &event_name, &payments, &statuses, &formats);
+
+    // 12. 多日展会：每个营业日单独一张工作表，列出开场/收摊库存
+    let sessions = session_breakdown(&state.db, event_id).await;
+    if sessions.len() > 1 {
+        for (index, breakdown) in sessions.iter().enumerate() {
+            write_session_sheet(&mut workbook, &event_name, index, breakdown, &formats);
+        }
+    }
+
+    match workbook.save_to_buffer() {
+        Ok(buf) => {
+            let disposition = format!(
+                "attachment; filename=\"sales_report_event_{}.xlsx\"",
+                event_id
+            );
+            (
+                [
+                    (
+                        header::CONTENT_TYPE,
                         "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
                     ),
-                );
-                let download_filename = format!("sales_report_event_{}.xlsx", event_id);
-                headers.insert(
-                    axum::http::header::CONTENT_DISPOSITION,
-                    HeaderValue::from_str(&format!(
-                        "attachment; filename=\"{}\"",
-                        download_filename
-                    ))
-                    .unwrap_or_else(|_| HeaderValue::from_static("attachment")),
-                );
-                (headers, buf).into_response()
-            }
-            Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read excel").into_response(),
-        },
+                    (header::CONTENT_DISPOSITION, disposition.as_str()),
+                ],
+                buf,
+            )
+                .into_response()
+        }
         Err(e) => {
             eprintln!("Excel generation error: {}", e);
             (
@@ -584,6 +981,1164 @@ async fn download_sales_summary(
     }
 }
 
+// ==========================================
+// 4. 按标签统计 (Tag Breakdown)
+// ==========================================
+// 一个商品带有同组多个标签时，会分别计入每个标签
+#[derive(Deserialize)]
+struct TagStatsQuery {
+    group_id: Option<i64>, // 指定标签组时，额外返回该组"未标记"的商品汇总
+    start_date: Option<String>,
+    end_date: Option<String>,
+    session_id: Option<i64>,
+}
+
+#[derive(Serialize, FromRow)]
+struct TagSalesItem {
+    group_id: Option<i64>,
+    group_name: Option<String>,
+    tag_id: Option<i64>, // None 表示该组下未打标签的商品
+    tag_name: Option<String>,
+    product_count: i64,
+    total_quantity: i64,
+    total_revenue: f64,
+}
+
+async fn get_tag_stats(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+    Query(params): Query<TagStatsQuery>,
+) -> impl IntoResponse {
+    if let Err(e) = check_read_permission(&claims, event_id) {
+        return e.into_response();
+    }
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    // 公共筛选条件：有效订单 + 日期范围
+    let mut filter = String::from("o.event_id = ? AND o.status != 'cancelled'");
+    let mut filter_params: Vec<String> = vec![event_id.to_string()];
+    if let Some(ref start) = params.start_date {
+        filter.push_str(" AND DATE(o.created_at) >= ?");
+        filter_params.push(start.clone());
+    }
+    if let Some(ref end) = params.end_date {
+        filter.push_str(" AND DATE(o.created_at) <= ?");
+        filter_params.push(end.clone());
+    }
+    if let Some(session_id) = params.session_id {
+        filter.push_str(" AND o.session_id = ?");
+        filter_params.push(session_id.to_string());
+    }
+
+    let group_filter = if params.group_id.is_some() {
+        " AND g.id = ?"
+    } else {
+        ""
+    };
+    let tag_sql = format!(
+        r#"
+        SELECT
+            g.id as group_id,
+            g.name as group_name,
+            t.id as tag_id,
+            t.name as tag_name,
+            COUNT(DISTINCT oi.product_id) as product_count,
+            SUM(oi.quantity) as total_quantity,
+            SUM(oi.product_price * oi.quantity) as total_revenue
+        FROM order_items oi
+        JOIN orders o ON oi.order_id = o.id
+        JOIN products p ON oi.product_id = p.id
+        JOIN master_product_tags mpt ON mpt.master_product_id = p.master_product_id
+        JOIN tags t ON mpt.tag_id = t.id
+        JOIN tag_groups g ON t.group_id = g.id
+        WHERE {}{}
+        GROUP BY t.id
+        ORDER BY g.sort_order ASC, g.id ASC, total_revenue DESC
+        "#,
+        filter, group_filter
+    );
+
+    let mut q = sqlx::query_as::<_, TagSalesItem>(&tag_sql);
+    for param in &filter_params {
+        q = q.bind(param);
+    }
+    if let Some(group_id) = params.group_id {
+        q = q.bind(group_id);
+    }
+    let mut items = q.fetch_all(&state.db).await.unwrap_or_default();
+
+    // 指定标签组时补充"未标记"一行，方便看出该维度的覆盖率
+    if let Some(group_id) = params.group_id {
+        let untagged_sql = format!(
+            r#"
+            SELECT
+                ? as group_id,
+                (SELECT name FROM tag_groups WHERE id = ?) as group_name,
+                NULL as tag_id,
+                NULL as tag_name,
+                COUNT(DISTINCT oi.product_id) as product_count,
+                COALESCE(SUM(oi.quantity), 0) as total_quantity,
+                COALESCE(SUM(oi.product_price * oi.quantity), 0.0) as total_revenue
+            FROM order_items oi
+            JOIN orders o ON oi.order_id = o.id
+            LEFT JOIN products p ON oi.product_id = p.id
+            WHERE {}
+              AND NOT EXISTS (
+                SELECT 1 FROM master_product_tags mpt
+                JOIN tags t ON mpt.tag_id = t.id
+                WHERE mpt.master_product_id = p.master_product_id AND t.group_id = ?
+              )
+            "#,
+            filter
+        );
+        let mut q = sqlx::query_as::<_, TagSalesItem>(&untagged_sql)
+            .bind(group_id)
+            .bind(group_id);
+        for param in &filter_params {
+            q = q.bind(param);
+        }
+        if let Ok(untagged) = q.bind(group_id).fetch_one(&state.db).await {
+            if untagged.total_quantity > 0 {
+                items.push(untagged);
+            }
+        }
+    }
+
+    for item in items.iter_mut() {
+        item.total_revenue = (item.total_revenue * 100.0).round() / 100.0;
+    }
+
+    Json(items).into_response()
+}
+
+// ==========================================
+// 5. 按营业日统计 (Per-day Breakdown)
+// ==========================================
+// 开场库存：已记录的快照 > 前一营业日的收摊库存 > 初始库存
+// 收摊库存：已记录的快照 > 开场库存 - 当日销量
+#[derive(Serialize)]
+struct SessionProductLine {
+    product_id: i64,
+    product_code: String,
+    product_name: String,
+    unit_price: f64,
+    opening_stock: Option<i64>, // 商品已从场次移除时无法推算，为 null
+    closing_stock: Option<i64>,
+    opening_recorded: bool,
+    closing_recorded: bool,
+    total_quantity: i64,
+    total_revenue: f64,
+}
+
+#[derive(Serialize, Default, Clone, Copy)]
+struct SessionTotals {
+    total_revenue: f64,
+    orders_count: i64,
+    items_sold: i64,
+}
+
+struct SessionBreakdown {
+    session: Option<EventSession>, // None：未归属营业日的订单
+    totals: SessionTotals,
+    products: Vec<SessionProductLine>,
+}
+
+#[derive(Serialize)]
+struct SessionSummary {
+    session: Option<EventSession>,
+    #[serde(flatten)]
+    totals: SessionTotals,
+}
+
+async fn get_session_stats(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(e) = check_read_permission(&claims, event_id) {
+        return e.into_response();
+    }
+
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    #[derive(Serialize)]
+    struct SessionStatsItem {
+        session: Option<EventSession>,
+        #[serde(flatten)]
+        totals: SessionTotals,
+        products: Vec<SessionProductLine>,
+    }
+
+    let breakdown = session_breakdown(&state.db, event_id).await;
+    let mut total = SessionTotals::default();
+    for b in &breakdown {
+        total.total_revenue += b.totals.total_revenue;
+        total.orders_count += b.totals.orders_count;
+        total.items_sold += b.totals.items_sold;
+    }
+    total.total_revenue = (total.total_revenue * 100.0).round() / 100.0;
+
+    let sessions: Vec<SessionStatsItem> = breakdown
+        .into_iter()
+        .map(|b| SessionStatsItem {
+            session: b.session,
+            totals: b.totals,
+            products: b.products,
+        })
+        .collect();
+
+    Json(serde_json::json!({ "sessions": sessions, "total": total })).into_response()
+}
+
+async fn session_breakdown(db: &sqlx::SqlitePool, event_id: i64) -> Vec<SessionBreakdown> {
+    let sessions = session::fetch_sessions(db, event_id).await;
+
+    #[derive(FromRow)]
+    struct ProductRow {
+        id: i64,
+        product_code: String,
+        name: String,
+        price: f64,
+        initial_stock: i64,
+    }
+    let products: Vec<ProductRow> = sqlx::query_as(
+        r#"
+        SELECT id, product_code, name, price, initial_stock FROM products
+        WHERE event_id = ?
+        ORDER BY sort_order ASC, product_code ASC
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await
+    .unwrap_or_default();
+
+    #[derive(FromRow)]
+    struct SalesRow {
+        session_id: Option<i64>,
+        product_id: i64,
+        product_code: String,
+        product_name: String,
+        unit_price: f64,
+        quantity: i64,
+        revenue: f64,
+    }
+    let sales: Vec<SalesRow> = sqlx::query_as(
+        r#"
+        SELECT o.session_id, oi.product_id,
+               COALESCE(MAX(p.product_code), '') as product_code,
+               MAX(oi.product_name) as product_name,
+               MAX(oi.product_price) as unit_price,
+               SUM(oi.quantity) as quantity,
+               SUM(oi.product_price * oi.quantity) as revenue
+        FROM order_items oi
+        JOIN orders o ON oi.order_id = o.id
+        LEFT JOIN products p ON oi.product_id = p.id
+        WHERE o.event_id = ? AND o.status != 'cancelled'
+        GROUP BY o.session_id, oi.product_id
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await
+    .unwrap_or_default();
+
+    #[derive(FromRow)]
+    struct OrderTotalsRow {
+        session_id: Option<i64>,
+        total_revenue: f64,
+        orders_count: i64,
+    }
+    let order_totals: HashMap<Option<i64>, OrderTotalsRow> = sqlx::query_as::<_, OrderTotalsRow>(
+        r#"
+        SELECT session_id, COALESCE(SUM(total_amount), 0.0) as total_revenue, COUNT(*) as orders_count
+        FROM orders
+        WHERE event_id = ? AND status != 'cancelled'
+        GROUP BY session_id
+        "#,
+    )
+    .bind(event_id)
+    .fetch_all(db)
+    .await
+    .unwrap_or_default()
+    .into_iter()
+    .map(|r| (r.session_id, r))
+    .collect();
+
+    let snapshots: HashMap<(i64, i64), (Option<i64>, Option<i64>)> =
+        sqlx::query_as::<_, (i64, i64, Option<i64>, Option<i64>)>(
+            r#"
+            SELECT ss.session_id, ss.product_id, ss.opening_stock, ss.closing_stock
+            FROM event_session_stock ss
+            JOIN event_sessions s ON ss.session_id = s.id
+            WHERE s.event_id = ?
+            "#,
+        )
+        .bind(event_id)
+        .fetch_all(db)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(sid, pid, opening, closing)| ((sid, pid), (opening, closing)))
+        .collect();
+
+    let totals_for = |session_id: Option<i64>| {
+        let items_sold = sales
+            .iter()
+            .filter(|s| s.session_id == session_id)
+            .map(|s| s.quantity)
+            .sum();
+        let (total_revenue, orders_count) = order_totals
+            .get(&session_id)
+            .map(|r| (r.total_revenue, r.orders_count))
+            .unwrap_or((0.0, 0));
+        SessionTotals {
+            total_revenue: (total_revenue * 100.0).round() / 100.0,
+            orders_count,
+            items_sold,
+        }
+    };
+    // 已从场次移除、但仍有销售记录的商品
+    let listed = &products;
+    let sold_only = |session_id: Option<i64>| {
+        sales
+            .iter()
+            .filter(move |s| {
+                s.session_id == session_id && !listed.iter().any(|p| p.id == s.product_id)
+            })
+            .map(|s| SessionProductLine {
+                product_id: s.product_id,
+                product_code: s.product_code.clone(),
+                product_name: s.product_name.clone(),
+                unit_price: s.unit_price,
+                opening_stock: None,
+                closing_stock: None,
+                opening_recorded: false,
+                closing_recorded: false,
+                total_quantity: s.quantity,
+                total_revenue: (s.revenue * 100.0).round() / 100.0,
+            })
+    };
+
+    let mut result = Vec::new();
+    let mut previous_closing: HashMap<i64, i64> = HashMap::new();
+    for session in sessions {
+        let mut lines = Vec::new();
+        for p in &products {
+            let sold = sales
+                .iter()
+                .find(|s| s.session_id == Some(session.id) && s.product_id == p.id);
+            let quantity = sold.map(|s| s.quantity).unwrap_or(0);
+            let (recorded_opening, recorded_closing) = snapshots
+                .get(&(session.id, p.id))
+                .copied()
+                .unwrap_or((None, None));
+
+            let opening = recorded_opening
+                .or_else(|| previous_closing.get(&p.id).copied())
+                .unwrap_or(p.initial_stock);
+            let closing = recorded_closing.unwrap_or(opening - quantity);
+            previous_closing.insert(p.id, closing);
+
+            lines.push(SessionProductLine {
+                product_id: p.id,
+                product_code: p.product_code.clone(),
+                product_name: p.name.clone(),
+                unit_price: sold.map(|s| s.unit_price).unwrap_or(p.price),
+                opening_stock: Some(opening),
+                closing_stock: Some(closing),
+                opening_recorded: recorded_opening.is_some(),
+                closing_recorded: recorded_closing.is_some(),
+                total_quantity: quantity,
+                total_revenue: sold
+                    .map(|s| (s.revenue * 100.0).round() / 100.0)
+                    .unwrap_or(0.0),
+            });
+        }
+        lines.extend(sold_only(Some(session.id)));
+
+        result.push(SessionBreakdown {
+            totals: totals_for(Some(session.id)),
+            session: Some(session),
+            products: lines,
+        });
+    }
+
+    if order_totals.contains_key(&None) {
+        result.push(SessionBreakdown {
+            session: None,
+            totals: totals_for(None),
+            products: sales
+                .iter()
+                .filter(|s| s.session_id.is_none())
+                .map(|s| SessionProductLine {
+                    product_id: s.product_id,
+                    product_code: s.product_code.clone(),
+                    product_name: s.product_name.clone(),
+                    unit_price: s.unit_price,
+                    opening_stock: None,
+                    closing_stock: None,
+                    opening_recorded: false,
+                    closing_recorded: false,
+                    total_quantity: s.quantity,
+                    total_revenue: (s.revenue * 100.0).round() / 100.0,
+                })
+                .collect(),
+        });
+    }
+
+    result
+}
+
+// ==========================================
+// 6. 顾客行为分析 (Basket & Peak Hours)
+// ==========================================
+// 客单价、每单件数、购物篮大小分布、日期 × 小时热力图与最繁忙的 15 分钟时段。
+// 与其他统计一致，不计已取消订单；时间按本地时间显示。
+#[derive(Deserialize)]
+struct BasketQuery {
+    session_id: Option<i64>, // 只统计某个营业日
+    top: Option<i64>,        // 繁忙时段条数，默认 5，最多 50
+}
+
+// 购物篮大小分布的最后一档：10 件及以上
+const BASKET_SIZE_CAP: i64 = 10;
+
+#[derive(Serialize, FromRow)]
+struct BasketTotals {
+    orders: i64,
+    revenue: f64,
+    units: i64,
+    lines: i64, // 每单不同商品数之和
+}
+
+#[derive(Serialize)]
+struct BasketSizeBucket {
+    size: i64, // 件数；最后一档表示 >= BASKET_SIZE_CAP
+    label: String,
+    orders: i64,
+    share: f64, // 占订单比例
+}
+
+#[derive(Serialize)]
+struct HeatmapDay {
+    date: String,
+    orders: Vec<i64>, // 下标为小时 0-23
+    revenue: Vec<f64>,
+}
+
+#[derive(Serialize, FromRow)]
+struct PeakWindow {
+    start: String, // 本地时间 "YYYY-MM-DD HH:MM"
+    orders: i64,
+    units: i64,
+    revenue: f64,
+}
+
+async fn get_basket_stats(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+    Query(params): Query<BasketQuery>,
+) -> impl IntoResponse {
+    if let Err(e) = check_read_permission(&claims, event_id) {
+        return e.into_response();
+    }
+
+    if !event_exists(&state.db, event_id).await {
+        return (StatusCode::NOT_FOUND, "Event not found").into_response();
+    }
+
+    let top = params.top.unwrap_or(5).clamp(1, 50);
+    let session_clause = if params.session_id.is_some() {
+        " AND o.session_id = ?"
+    } else {
+        ""
+    };
+
+    // 每单汇总：件数、不同商品数；金额取订单总额
+    let per_order = format!(
+        r#"
+        SELECT o.id, o.total_amount, datetime(o.created_at, 'localtime') AS local_time,
+               SUM(oi.quantity) AS units, COUNT(DISTINCT oi.product_id) AS lines
+        FROM orders o
+        JOIN order_items oi ON oi.order_id = o.id
+        WHERE o.event_id = ? AND o.status != 'cancelled'{session_clause}
+        GROUP BY o.id
+        "#
+    );
+
+    let totals_sql = format!(
+        r#"
+        WITH per_order AS ({per_order})
+        SELECT COUNT(*) AS orders,
+               COALESCE(SUM(total_amount), 0.0) AS revenue,
+               COALESCE(SUM(units), 0) AS units,
+               COALESCE(SUM(lines), 0) AS lines
+        FROM per_order
+        "#
+    );
+    let sizes_sql = format!(
+        r#"
+        WITH per_order AS ({per_order})
+        SELECT MIN(units, ?) AS size, COUNT(*) AS orders
+        FROM per_order
+        GROUP BY size
+        ORDER BY size ASC
+        "#
+    );
+    let heatmap_sql = format!(
+        r#"
+        WITH per_order AS ({per_order})
+        SELECT date(local_time) AS day,
+               CAST(strftime('%H', local_time) AS INTEGER) AS hour,
+               COUNT(*) AS orders,
+               SUM(total_amount) AS revenue
+        FROM per_order
+        GROUP BY day, hour
+        ORDER BY day ASC, hour ASC
+        "#
+    );
+    let peaks_sql = format!(
+        r#"
+        WITH per_order AS ({per_order})
+        SELECT strftime('%Y-%m-%d %H:', local_time)
+                   || printf('%02d', (CAST(strftime('%M', local_time) AS INTEGER) / 15) * 15) AS start,
+               COUNT(*) AS orders,
+               SUM(units) AS units,
+               SUM(total_amount) AS revenue
+        FROM per_order
+        GROUP BY start
+        ORDER BY orders DESC, revenue DESC, start ASC
+        LIMIT ?
+        "#
+    );
+
+    // 绑定顺序：event_id, [session_id], 各查询自身的参数
+    let mut totals_q = sqlx::query_as::<_, BasketTotals>(&totals_sql).bind(event_id);
+    let mut sizes_q = sqlx::query_as::<_, (i64, i64)>(&sizes_sql).bind(event_id);
+    let mut heatmap_q = sqlx::query_as::<_, (String, i64, i64, f64)>(&heatmap_sql).bind(event_id);
+    let mut peaks_q = sqlx::query_as::<_, PeakWindow>(&peaks_sql).bind(event_id);
+    if let Some(session_id) = params.session_id {
+        totals_q = totals_q.bind(session_id);
+        sizes_q = sizes_q.bind(session_id);
+        heatmap_q = heatmap_q.bind(session_id);
+        peaks_q = peaks_q.bind(session_id);
+    }
+
+    let totals = totals_q.fetch_one(&state.db).await.unwrap_or(BasketTotals {
+        orders: 0,
+        revenue: 0.0,
+        units: 0,
+        lines: 0,
+    });
+    let sizes = sizes_q
+        .bind(BASKET_SIZE_CAP)
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default();
+    let heatmap_rows = heatmap_q.fetch_all(&state.db).await.unwrap_or_default();
+    let mut peak_windows = peaks_q
+        .bind(top)
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default();
+
+    // 购物篮大小分布：1 ~ 9 件逐档列出 (没有订单的档位为 0)，10 件及以上合为一档
+    let basket_sizes: Vec<BasketSizeBucket> = (1..=BASKET_SIZE_CAP)
+        .map(|size| {
+            let orders = sizes
+                .iter()
+                .find(|(s, _)| *s == size)
+                .map(|(_, n)| *n)
+                .unwrap_or(0);
+            BasketSizeBucket {
+                size,
+                label: if size == BASKET_SIZE_CAP {
+                    format!("{}+", size)
+                } else {
+                    size.to_string()
+                },
+                orders,
+                share: (ratio(orders as f64, totals.orders) * 10000.0).round() / 10000.0,
+            }
+        })
+        .collect();
+
+    let mut heatmap: Vec<HeatmapDay> = Vec::new();
+    for (day, hour, orders, revenue) in heatmap_rows {
+        if heatmap.last().map(|d| &d.date) != Some(&day) {
+            heatmap.push(HeatmapDay {
+                date: day,
+                orders: vec![0; 24],
+                revenue: vec![0.0; 24],
+            });
+        }
+        if let (Some(last), Ok(hour)) = (heatmap.last_mut(), usize::try_from(hour)) {
+            if hour < 24 {
+                last.orders[hour] = orders;
+                last.revenue[hour] = (revenue * 100.0).round() / 100.0;
+            }
+        }
+    }
+
+    for window in peak_windows.iter_mut() {
+        window.revenue = (window.revenue * 100.0).round() / 100.0;
+    }
+
+    #[derive(Serialize)]
+    struct BasketResponse {
+        currency_symbol: String,
+        orders: i64,
+        revenue: f64,
+        units: i64,
+        average_order_value: f64,
+        items_per_order: f64,
+        products_per_order: f64,
+        basket_sizes: Vec<BasketSizeBucket>,
+        heatmap: Vec<HeatmapDay>,
+        peak_windows: Vec<PeakWindow>,
+    }
+
+    Json(BasketResponse {
+        currency_symbol: load_settings(&state.db, event_id).await.currency_symbol,
+        average_order_value: round2(ratio(totals.revenue, totals.orders)),
+        items_per_order: round2(ratio(totals.units as f64, totals.orders)),
+        products_per_order: round2(ratio(totals.lines as f64, totals.orders)),
+        orders: totals.orders,
+        revenue: round2(totals.revenue),
+        units: totals.units,
+        basket_sizes,
+        heatmap,
+        peak_windows,
+    })
+    .into_response()
+}
+
+fn ratio(value: f64, orders: i64) -> f64 {
+    if orders > 0 {
+        value / orders as f64
+    } else {
+        0.0
+    }
+}
+
+// Excel 报表通用样式
+pub(crate) struct ReportFormats {
+    pub(crate) title: Format,
+    pub(crate) header: Format,
+    pub(crate) text: Format,
+    pub(crate) center: Format,
+    pub(crate) currency: Format,
+    pub(crate) percent: Format,
+    pub(crate) total_row: Format,
+    pub(crate) total_currency: Format,
+}
+
+impl ReportFormats {
+    // 金额列带上场次配置的货币符号
+    pub(crate) fn new(currency_symbol: &str) -> Self {
+        let money_format = format!("\"{}\"#,##0.00", currency_symbol.replace('"', ""));
+        Self {
+            title: Format::new()
+                .set_bold()
+                .set_font_size(16)
+                .set_align(FormatAlign::Center)
+                .set_align(FormatAlign::VerticalCenter),
+            header: Format::new()
+                .set_bold()
+                .set_background_color(Color::RGB(0xDDEBF7))
+                .set_border(FormatBorder::Thin)
+                .set_align(FormatAlign::Center)
+                .set_align(FormatAlign::VerticalCenter),
+            text: Format::new()
+                .set_border(FormatBorder::Thin)
+                .set_align(FormatAlign::Left),
+            center: Format::new()
+                .set_border(FormatBorder::Thin)
+                .set_align(FormatAlign::Center),
+            currency: Format::new()
+                .set_border(FormatBorder::Thin)
+                .set_align(FormatAlign::Right)
+                .set_num_format(&money_format),
+            percent: Format::new()
+                .set_border(FormatBorder::Thin)
+                .set_align(FormatAlign::Right)
+                .set_num_format("0.0%"),
+            total_row: Format::new()
+                .set_bold()
+                .set_background_color(Color::RGB(0xF2F2F2))
+                .set_border(FormatBorder::Thin)
+                .set_align(FormatAlign::Center),
+            total_currency: Format::new()
+                .set_bold()
+                .set_background_color(Color::RGB(0xF2F2F2))
+                .set_border(FormatBorder::Thin)
+                .set_align(FormatAlign::Right)
+                .set_num_format(&money_format),
+        }
+    }
+}
+
+// 单个营业日的工作表：与汇总表同样的版式，库存列换成开场/收摊库存
+fn write_session_sheet(
+    workbook: &mut Workbook,
+    event_name: &str,
+    index: usize,
+    breakdown: &SessionBreakdown,
+    formats: &ReportFormats,
+) {
+    let worksheet: &mut Worksheet = workbook.add_worksheet();
+
+    let (sheet_name, title_text) = match &breakdown.session {
+        Some(s) => {
+            let hours = match (&s.opens_at, &s.closes_at) {
+                (Some(o), Some(c)) => format!(" ({}-{})", o, c),
+                _ => String::new(),
+            };
+            (
+                format!("第{}天 {}", index + 1, s.session_date),
+                format!("{} {} 销售记录表{}", event_name, s.session_date, hours),
+            )
+        }
+        None => (
+            "未归属营业日".to_string(),
+            format!("{} 未归属营业日的销售记录", event_name),
+        ),
+    };
+    let _ = worksheet.set_name(&sheet_name);
+
+    for (col, width) in [15, 25, 12, 12, 15, 12, 15].iter().enumerate() {
+        let _ = worksheet.set_column_width(col as u16, *width);
+    }
+    let _ = worksheet.merge_range(0, 0, 0, 6, &title_text, &formats.title);
+
+    let headers = [
+        "制品编号",
+        "制品名",
+        "开场库存",
+        "收摊库存",
+        "单价",
+        "销售量",
+        "销售额",
+    ];
+    for (col, text) in headers.iter().enumerate() {
+        let _ = worksheet.write_string_with_format(1, col as u16, *text, &formats.header);
+    }
+    let _ = worksheet.set_freeze_panes(2, 0);
+
+    let mut row = 2;
+    for item in &breakdown.products {
+        let _ = worksheet.write_string_with_format(row, 0, &item.product_code, &formats.center);
+        let _ = worksheet.write_string_with_format(row, 1, &item.product_name, &formats.text);
+        for (col, value) in [(2, item.opening_stock), (3, item.closing_stock)] {
+            match value {
+                Some(v) => {
+                    let _ = worksheet.write_number_with_format(row, col, v as f64, &formats.center);
+                }
+                None => {
+                    let _ = worksheet.write_blank(row, col, &formats.center);
+                }
+            }
+        }
+        let _ = worksheet.write_number_with_format(row, 4, item.unit_price, &formats.currency);
+        let _ =
+            worksheet.write_number_with_format(row, 5, item.total_quantity as f64, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 6, item.total_revenue, &formats.currency);
+        row += 1;
+    }
+
+    let _ = worksheet.write_string_with_format(row, 0, "当日合计", &formats.total_row);
+    for col in 1..=4 {
+        let _ = worksheet.write_blank(row, col, &formats.total_row);
+    }
+    let _ = worksheet.write_number_with_format(
+        row,
+        5,
+        breakdown.totals.items_sold as f64,
+        &formats.total_row,
+    );
+    let _ = worksheet.write_number_with_format(
+        row,
+        6,
+        breakdown.totals.total_revenue,
+        &formats.total_currency,
+    );
+}
+
+// ==========================================
+// Excel 附加工作表 (订单明细 / 销售趋势 / 库存 / 收款方式)
+// ==========================================
+// 每张表第 0 行为标题、第 1 行为表头，数据从第 2 行开始；图表引用对应的数据区域
+
+const LEDGER_SHEET: &str = "订单明细";
+const TIMESERIES_SHEET: &str = "销售趋势";
+const STOCK_SHEET: &str = "库存";
+const PAYMENT_SHEET: &str = "收款方式";
+
+#[derive(FromRow)]
+struct LedgerLine {
+    order_id: i64,
+    local_time: String,
+    status: String,
+    payment_method: Option<String>,
+    total_amount: f64,
+    product_code: String,
+    product_name: String,
+    product_price: f64,
+    quantity: i64,
+}
+
+#[derive(FromRow)]
+struct StockLine {
+    product_code: String,
+    name: String,
+    price: f64,
+    initial_stock: i64,
+    current_stock: i64,
+    sold: i64,
+}
+
+#[derive(FromRow)]
+struct PaymentLine {
+    label: String,
+    orders: i64,
+    list_amount: f64, // 商品原价合计
+    collected: f64,   // 实收 (订单金额)
+}
+
+// 标题 + 表头，冻结到表头下方
+fn write_sheet_header(
+    worksheet: &mut Worksheet,
+    title: &str,
+    columns: &[(&str, u16)],
+    formats: &ReportFormats,
+) {
+    for (col, (_, width)) in columns.iter().enumerate() {
+        let _ = worksheet.set_column_width(col as u16, *width);
+    }
+    let last_col = columns.len().saturating_sub(1) as u16;
+    let _ = worksheet.merge_range(0, 0, 0, last_col, title, &formats.title);
+    for (col, (text, _)) in columns.iter().enumerate() {
+        let _ = worksheet.write_string_with_format(1, col as u16, *text, &formats.header);
+    }
+    let _ = worksheet.set_freeze_panes(2, 0);
+}
+
+fn status_label(status: &str) -> &str {
+    match status {
+        "pending" => "待处理",
+        "completed" => "已完成",
+        "cancelled" => "已取消",
+        other => other,
+    }
+}
+
+// 订单明细：订单金额只写在每单的第一行，整列求和即为总额
+fn write_ledger_sheet(
+    workbook: &mut Workbook,
+    event_name: &str,
+    lines: &[LedgerLine],
+    formats: &ReportFormats,
+) {
+    let worksheet = workbook.add_worksheet();
+    let _ = worksheet.set_name(LEDGER_SHEET);
+    write_sheet_header(
+        worksheet,
+        &format!("{} 订单明细", event_name),
+        &[
+            ("订单号", 10),
+            ("下单时间", 20),
+            ("状态", 10),
+            ("收款方式", 14),
+            ("制品编号", 12),
+            ("制品名", 25),
+            ("单价", 12),
+            ("数量", 8),
+            ("小计", 12),
+            ("订单金额", 12),
+        ],
+        formats,
+    );
+
+    let mut row = 2;
+    let mut last_order = None;
+    for line in lines {
+        let _ = worksheet.write_number_with_format(row, 0, line.order_id as f64, &formats.center);
+        let _ = worksheet.write_string_with_format(row, 1, &line.local_time, &formats.center);
+        let _ =
+            worksheet.write_string_with_format(row, 2, status_label(&line.status), &formats.center);
+        let _ = worksheet.write_string_with_format(
+            row,
+            3,
+            line.payment_method.as_deref().unwrap_or(""),
+            &formats.center,
+        );
+        let _ = worksheet.write_string_with_format(row, 4, &line.product_code, &formats.center);
+        let _ = worksheet.write_string_with_format(row, 5, &line.product_name, &formats.text);
+        let _ = worksheet.write_number_with_format(row, 6, line.product_price, &formats.currency);
+        let _ = worksheet.write_number_with_format(row, 7, line.quantity as f64, &formats.center);
+        let _ = worksheet.write_number_with_format(
+            row,
+            8,
+            line.product_price * line.quantity as f64,
+            &formats.currency,
+        );
+        if last_order != Some(line.order_id) {
+            let _ =
+                worksheet.write_number_with_format(row, 9, line.total_amount, &formats.currency);
+        } else {
+            let _ = worksheet.write_blank(row, 9, &formats.currency);
+        }
+        last_order = Some(line.order_id);
+        row += 1;
+    }
+    if row > 2 {
+        let _ = worksheet.autofilter(1, 0, row - 1, 9);
+    }
+}
+
+// 销售趋势：时间段按本地时间显示，折线图展示每段销售额与累计销售额
+fn write_timeseries_sheet(
+    workbook: &mut Workbook,
+    event_name: &str,
+    interval_minutes: i64,
+    points: &[TimeseriesItem],
+    formats: &ReportFormats,
+) {
+    let worksheet = workbook.add_worksheet();
+    let _ = worksheet.set_name(TIMESERIES_SHEET);
+    write_sheet_header(
+        worksheet,
+        &format!("{} 销售趋势 (每 {} 分钟)", event_name, interval_minutes),
+        &[
+            ("时间段", 20),
+            ("订单数", 10),
+            ("销售量", 10),
+            ("销售额", 14),
+            ("累计销售额", 14),
+        ],
+        formats,
+    );
+
+    let mut row = 2;
+    for point in points {
+        // build_points 的时间为 UTC
+        let label = NaiveDateTime::parse_from_str(&point.date, "%Y-%m-%d %H:%M")
+            .map(|t| {
+                Local
+                    .from_utc_datetime(&t)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| point.date.clone());
+        let _ = worksheet.write_string_with_format(row, 0, &label, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 1, point.orders as f64, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 2, point.units as f64, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 3, point.revenue, &formats.currency);
+        let _ = worksheet.write_number_with_format(
+            row,
+            4,
+            point.cumulative_revenue.unwrap_or_default(),
+            &formats.currency,
+        );
+        row += 1;
+    }
+
+    if row > 2 {
+        let last = row - 1;
+        let mut chart = Chart::new(ChartType::Line);
+        chart
+            .add_series()
+            .set_name("销售额")
+            .set_categories((TIMESERIES_SHEET, 2, 0, last, 0))
+            .set_values((TIMESERIES_SHEET, 2, 3, last, 3));
+        chart
+            .add_series()
+            .set_name("累计销售额")
+            .set_categories((TIMESERIES_SHEET, 2, 0, last, 0))
+            .set_values((TIMESERIES_SHEET, 2, 4, last, 4))
+            .set_secondary_axis(true);
+        chart.title().set_name("销售趋势");
+        chart.set_width(720);
+        let _ = worksheet.insert_chart(1, 6, &chart);
+    }
+}
+
+// 库存：已售与剩余的堆积柱状图
+fn write_stock_sheet(
+    workbook: &mut Workbook,
+    event_name: &str,
+    lines: &[StockLine],
+    formats: &ReportFormats,
+) {
+    let worksheet = workbook.add_worksheet();
+    let _ = worksheet.set_name(STOCK_SHEET);
+    write_sheet_header(
+        worksheet,
+        &format!("{} 库存情况", event_name),
+        &[
+            ("制品编号", 12),
+            ("制品名", 25),
+            ("单价", 12),
+            ("初始库存", 10),
+            ("已售", 10),
+            ("剩余库存", 10),
+            ("售罄率", 10),
+            ("剩余货值", 14),
+        ],
+        formats,
+    );
+
+    let mut row = 2;
+    let (mut sum_initial, mut sum_sold, mut sum_current, mut sum_value) = (0, 0, 0, 0.0);
+    for line in lines {
+        let _ = worksheet.write_string_with_format(row, 0, &line.product_code, &formats.center);
+        let _ = worksheet.write_string_with_format(row, 1, &line.name, &formats.text);
+        let _ = worksheet.write_number_with_format(row, 2, line.price, &formats.currency);
+        let _ =
+            worksheet.write_number_with_format(row, 3, line.initial_stock as f64, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 4, line.sold as f64, &formats.center);
+        let _ =
+            worksheet.write_number_with_format(row, 5, line.current_stock as f64, &formats.center);
+        if line.initial_stock > 0 {
+            let _ = worksheet.write_number_with_format(
+                row,
+                6,
+                line.sold as f64 / line.initial_stock as f64,
+                &formats.percent,
+            );
+        } else {
+            let _ = worksheet.write_blank(row, 6, &formats.percent);
+        }
+        let value = line.current_stock.max(0) as f64 * line.price;
+        let _ = worksheet.write_number_with_format(row, 7, value, &formats.currency);
+
+        sum_initial += line.initial_stock;
+        sum_sold += line.sold;
+        sum_current += line.current_stock;
+        sum_value += value;
+        row += 1;
+    }
+
+    let _ = worksheet.write_string_with_format(row, 0, "总计", &formats.total_row);
+    let _ = worksheet.write_blank(row, 1, &formats.total_row);
+    let _ = worksheet.write_blank(row, 2, &formats.total_row);
+    let _ = worksheet.write_number_with_format(row, 3, sum_initial as f64, &formats.total_row);
+    let _ = worksheet.write_number_with_format(row, 4, sum_sold as f64, &formats.total_row);
+    let _ = worksheet.write_number_with_format(row, 5, sum_current as f64, &formats.total_row);
+    let _ = worksheet.write_blank(row, 6, &formats.total_row);
+    let _ = worksheet.write_number_with_format(row, 7, sum_value, &formats.total_currency);
+
+    if !lines.is_empty() {
+        let last = row - 1;
+        let mut chart = Chart::new(ChartType::ColumnStacked);
+        chart
+            .add_series()
+            .set_name("已售")
+            .set_categories((STOCK_SHEET, 2, 1, last, 1))
+            .set_values((STOCK_SHEET, 2, 4, last, 4));
+        chart
+            .add_series()
+            .set_name("剩余库存")
+            .set_categories((STOCK_SHEET, 2, 1, last, 1))
+            .set_values((STOCK_SHEET, 2, 5, last, 5));
+        chart.title().set_name("已售 / 剩余库存");
+        let _ = worksheet.insert_chart(1, 9, &chart);
+    }
+}
+
+// 收款方式 (不含已取消订单) 与按订单状态的汇总，饼图展示各收款方式实收占比
+fn write_payment_sheet(
+    workbook: &mut Workbook,
+    event_name: &str,
+    payments: &[PaymentLine],
+    statuses: &[(String, i64, f64)],
+    formats: &ReportFormats,
+) {
+    let worksheet = workbook.add_worksheet();
+    let _ = worksheet.set_name(PAYMENT_SHEET);
+    write_sheet_header(
+        worksheet,
+        &format!("{} 收款统计", event_name),
+        &[
+            ("收款方式", 16),
+            ("订单数", 10),
+            ("商品原价", 14),
+            ("实收金额", 14),
+            ("优惠金额", 14),
+            ("实收占比", 10),
+        ],
+        formats,
+    );
+
+    let total_collected: f64 = payments.iter().map(|p| p.collected).sum();
+    let mut row = 2;
+    let (mut sum_orders, mut sum_list) = (0, 0.0);
+    for payment in payments {
+        let _ = worksheet.write_string_with_format(row, 0, &payment.label, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 1, payment.orders as f64, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 2, payment.list_amount, &formats.currency);
+        let _ = worksheet.write_number_with_format(row, 3, payment.collected, &formats.currency);
+        let _ = worksheet.write_number_with_format(
+            row,
+            4,
+            payment.list_amount - payment.collected,
+            &formats.currency,
+        );
+        let share = if total_collected > 0.0 {
+            payment.collected / total_collected
+        } else {
+            0.0
+        };
+        let _ = worksheet.write_number_with_format(row, 5, share, &formats.percent);
+        sum_orders += payment.orders;
+        sum_list += payment.list_amount;
+        row += 1;
+    }
+
+    let _ = worksheet.write_string_with_format(row, 0, "总计", &formats.total_row);
+    let _ = worksheet.write_number_with_format(row, 1, sum_orders as f64, &formats.total_row);
+    let _ = worksheet.write_number_with_format(row, 2, sum_list, &formats.total_currency);
+    let _ = worksheet.write_number_with_format(row, 3, total_collected, &formats.total_currency);
+    let _ = worksheet.write_number_with_format(
+        row,
+        4,
+        sum_list - total_collected,
+        &formats.total_currency,
+    );
+    let _ = worksheet.write_blank(row, 5, &formats.total_row);
+
+    if !payments.is_empty() {
+        let last = row - 1;
+        let mut chart = Chart::new(ChartType::Pie);
+        chart
+            .add_series()
+            .set_name("实收金额")
+            .set_categories((PAYMENT_SHEET, 2, 0, last, 0))
+            .set_values((PAYMENT_SHEET, 2, 3, last, 3));
+        chart.title().set_name("收款方式占比");
+        let _ = worksheet.insert_chart(1, 7, &chart);
+    }
+
+    // 按订单状态：待处理的订单尚未确认收款
+    row += 2;
+    for (col, text) in ["订单状态", "订单数", "订单金额"].iter().enumerate() {
+        let _ = worksheet.write_string_with_format(row, col as u16, *text, &formats.header);
+    }
+    for (status, orders, amount) in statuses {
+        row += 1;
+        let _ = worksheet.write_string_with_format(row, 0, status_label(status), &formats.center);
+        let _ = worksheet.write_number_with_format(row, 1, *orders as f64, &formats.center);
+        let _ = worksheet.write_number_with_format(row, 2, *amount, &formats.currency);
+    }
+}
+
 fn check_read_permission(claims: &Claims, event_id: i64) -> Result<(), (StatusCode, &'static str)> {
     // 管理员拥有所有权限
     if claims.role == "admin" {
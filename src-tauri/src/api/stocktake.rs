@@ -0,0 +1,643 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post, put},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::query_as;
+
+use crate::{
+    api::event::{ensure_status_allows, event_exists, GuardedAction, OverrideQuery},
+    db::models::{Stocktake, StocktakeItem},
+    state::AppState,
+    utils::security::Claims,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 管理员/摊主：开始盘点 & 查看历史盘点
+        .route(
+            "/events/:event_id/stocktakes",
+            get(list_stocktakes).post(start_stocktake),
+        )
+        // 管理员/摊主：查看差异报告
+        .route("/stocktakes/:stocktake_id", get(get_stocktake_report))
+        // 管理员/摊主：提交清点数量
+        .route("/stocktakes/:stocktake_id/counts", put(submit_counts))
+        // 管理员/摊主：完成盘点 (可选写回库存)
+        .route("/stocktakes/:stocktake_id/commit", post(commit_stocktake))
+        .route("/stocktakes/:stocktake_id/cancel", post(cancel_stocktake))
+}
+
+// ==========================================
+// DTOs (数据传输对象)
+// ==========================================
+
+#[derive(Deserialize)]
+struct StartStocktakeRequest {
+    note: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CountEntry {
+    product_id: i64,
+    counted_stock: i64,
+}
+
+#[derive(Deserialize)]
+struct SubmitCountsRequest {
+    counts: Vec<CountEntry>,
+}
+
+#[derive(Deserialize)]
+struct CommitStocktakeRequest {
+    // true: 将差异作为修正写回 products.current_stock
+    #[serde(default)]
+    apply_adjustments: bool,
+}
+
+// 差异报告中的单行
+#[derive(Serialize)]
+struct DiscrepancyLine {
+    #[serde(flatten)]
+    item: StocktakeItem,
+    difference: Option<i64>,   // counted - expected，未清点为 null
+    value_impact: Option<f64>, // difference * unit_price，负数表示损失
+}
+
+#[derive(Serialize)]
+struct DiscrepancyTotals {
+    total_expected: i64,
+    total_counted: i64,
+    counted_items: i64,
+    uncounted_items: i64,
+    mismatched_items: i64,
+    total_difference: i64,
+    total_value_impact: f64,
+}
+
+#[derive(Serialize)]
+struct StocktakeReport {
+    #[serde(flatten)]
+    stocktake: Stocktake,
+    totals: DiscrepancyTotals,
+    items: Vec<DiscrepancyLine>,
+}
+
+// ==========================================
+// 1. 开始盘点 (Admin/Vendor Scoped)
+// ==========================================
+async fn start_stocktake(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+    Json(payload): Json<StartStocktakeRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = check_write_permission(&claims, event_id) {
+        return e.into_response();
+    }
+
+    let event_exists: Option<(i64,)> =
+        sqlx::query_as("SELECT id FROM events WHERE id = ? AND deleted_at IS NULL")
+            .bind(event_id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or(None);
+
+    if event_exists.is_none() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Event not found"})),
+        )
+            .into_response();
+    }
+
+    // 同一场次同时只允许一个进行中的盘点，避免两台手机各数各的
+    // (并发请求由 idx_stocktakes_one_open 唯一索引兜底，见下方插入)
+    if let Some(id) = find_open_stocktake(&state, event_id).await {
+        return stocktake_in_progress(Some(id));
+    }
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    };
+
+    let note = payload.note.filter(|n| !n.trim().is_empty());
+    let stocktake_id: i64 = match sqlx::query_scalar(
+        "INSERT INTO stocktakes (event_id, status, note) VALUES (?, 'open', ?) RETURNING id",
+    )
+    .bind(event_id)
+    .bind(&note)
+    .fetch_one(&mut *tx)
+    .await
+    {
+        Ok(id) => id,
+        Err(e) if e.to_string().contains("UNIQUE constraint failed") => {
+            drop(tx);
+            return stocktake_in_progress(find_open_stocktake(&state, event_id).await);
+        }
+        Err(e) => {
+            eprintln!("Start stocktake error: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+        }
+    };
+
+    // 以当前系统库存作为期望值快照
+    let snapshot = sqlx::query(
+        r#"
+        INSERT INTO stocktake_items
+        (stocktake_id, product_id, product_code, product_name, unit_price, expected_stock)
+        SELECT ?, p.id, p.product_code, p.name, p.price, p.current_stock
+        FROM products p
+        WHERE p.event_id = ?
+        "#,
+    )
+    .bind(stocktake_id)
+    .bind(event_id)
+    .execute(&mut *tx)
+    .await;
+
+    if let Err(e) = snapshot {
+        eprintln!("Stocktake snapshot error: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+    }
+
+    if let Err(e) = tx.commit().await {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Commit Failed: {}", e),
+        )
+            .into_response();
+    }
+
+    match build_report(&state, stocktake_id).await {
+        Some(report) => (StatusCode::CREATED, Json(report)).into_response(),
+        None => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    }
+}
+
+async fn find_open_stocktake(state: &AppState, event_id: i64) -> Option<i64> {
+    sqlx::query_scalar("SELECT id FROM stocktakes WHERE event_id = ? AND status = 'open'")
+        .bind(event_id)
+        .fetch_optional(&state.db)
+        .await
+        .unwrap_or(None)
+}
+
+fn stocktake_in_progress(stocktake_id: Option<i64>) -> Response {
+    (
+        StatusCode::CONFLICT,
+        Json(json!({
+            "error": "A stocktake is already in progress for this event",
+            "stocktake_id": stocktake_id
+        })),
+    )
+        .into_response()
+}
+
+// ==========================================
+// 2. 历史盘点列表 (Admin/Vendor Scoped)
+// ==========================================
+async fn list_stocktakes(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(event_id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(e) = check_read_permission(&claims, event_id) {
+        return e.into_response();
+    }
+    if !event_exists(&state.db, event_id).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Event not found"})),
+        )
+            .into_response();
+    }
+
+    let stocktakes: Vec<Stocktake> =
+        query_as("SELECT * FROM stocktakes WHERE event_id = ? ORDER BY id DESC")
+            .bind(event_id)
+            .fetch_all(&state.db)
+            .await
+            .unwrap_or_default();
+
+    Json(stocktakes).into_response()
+}
+
+// ==========================================
+// 3. 差异报告 (Admin/Vendor Scoped)
+// ==========================================
+async fn get_stocktake_report(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(stocktake_id): Path<i64>,
+) -> impl IntoResponse {
+    let stocktake = match load_authorized(&state, &claims, stocktake_id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
+
+    match build_report(&state, stocktake.id).await {
+        Some(report) => Json(report).into_response(),
+        None => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    }
+}
+
+// ==========================================
+// 4. 提交清点数量 (Admin/Vendor Scoped)
+// ==========================================
+// 可多次提交（分区清点），后提交的覆盖同一商品之前的数量
+async fn submit_counts(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(stocktake_id): Path<i64>,
+    Json(payload): Json<SubmitCountsRequest>,
+) -> impl IntoResponse {
+    let stocktake = match load_authorized(&state, &claims, stocktake_id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
+
+    if stocktake.status != "open" {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "Stocktake is already closed"})),
+        )
+            .into_response();
+    }
+
+    if let Some(bad) = payload.counts.iter().find(|c| c.counted_stock < 0) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!("Counted stock cannot be negative (product {})", bad.product_id)
+            })),
+        )
+            .into_response();
+    }
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    };
+
+    for entry in &payload.counts {
+        let result = sqlx::query(
+            "UPDATE stocktake_items SET counted_stock = ? WHERE stocktake_id = ? AND product_id = ?",
+        )
+        .bind(entry.counted_stock)
+        .bind(stocktake_id)
+        .bind(entry.product_id)
+        .execute(&mut *tx)
+        .await;
+
+        match result {
+            Ok(r) if r.rows_affected() == 0 => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({
+                        "error": format!("Product {} is not part of this stocktake", entry.product_id)
+                    })),
+                )
+                    .into_response();
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Submit count error: {:?}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+            }
+        }
+    }
+
+    if let Err(e) = tx.commit().await {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Commit Failed: {}", e),
+        )
+            .into_response();
+    }
+
+    match build_report(&state, stocktake_id).await {
+        Some(report) => Json(report).into_response(),
+        None => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    }
+}
+
+// ==========================================
+// 5. 完成盘点 (Admin/Vendor Scoped)
+// ==========================================
+// apply_adjustments = true 时，把 (counted - expected) 作为修正量加到 current_stock 上。
+// 使用增量而不是直接覆盖，这样盘点期间仍在发生的销售不会被抹掉。
+async fn commit_stocktake(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(stocktake_id): Path<i64>,
+    Query(overrides): Query<OverrideQuery>,
+    Json(payload): Json<CommitStocktakeRequest>,
+) -> impl IntoResponse {
+    let stocktake = match load_authorized(&state, &claims, stocktake_id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
+    // 修正库存与修改商品同样受场次状态限制 (已结束需管理员 ?force=true)
+    if let Err(resp) = ensure_status_allows(
+        &state.db,
+        stocktake.event_id,
+        GuardedAction::EditProducts,
+        Some(&claims),
+        &overrides,
+    )
+    .await
+    {
+        return resp;
+    }
+
+    if stocktake.status != "open" {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "Stocktake is already closed"})),
+        )
+            .into_response();
+    }
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    };
+
+    // 先在事务内把状态从 open 改为 committed，并发提交时只有一个请求能改到这一行，
+    // 其余请求直接回滚，避免库存修正被重复应用
+    let result = sqlx::query(
+        r#"
+        UPDATE stocktakes
+        SET status = 'committed', applied = ?, finished_at = CURRENT_TIMESTAMP
+        WHERE id = ? AND status = 'open'
+        "#,
+    )
+    .bind(payload.apply_adjustments)
+    .bind(stocktake_id)
+    .execute(&mut *tx)
+    .await;
+
+    match result {
+        Ok(r) if r.rows_affected() == 1 => {}
+        Ok(_) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({"error": "Stocktake is already closed"})),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            eprintln!("Commit stocktake error: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+        }
+    }
+
+    if payload.apply_adjustments {
+        let items: Vec<StocktakeItem> = match query_as(
+            "SELECT * FROM stocktake_items WHERE stocktake_id = ? AND counted_stock IS NOT NULL",
+        )
+        .bind(stocktake_id)
+        .fetch_all(&mut *tx)
+        .await
+        {
+            Ok(items) => items,
+            Err(e) => {
+                eprintln!("Load stocktake items error: {:?}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+            }
+        };
+
+        for item in items {
+            let delta = item.counted_stock.unwrap_or(item.expected_stock) - item.expected_stock;
+
+            if delta != 0 {
+                // 商品可能已从场次移除，此时 rows_affected = 0，仅记录不报错
+                let result = sqlx::query(
+                    "UPDATE products SET current_stock = MAX(0, current_stock + ?), version = version + 1 WHERE id = ?",
+                )
+                .bind(delta)
+                .bind(item.product_id)
+                .execute(&mut *tx)
+                .await;
+
+                if let Err(e) = result {
+                    eprintln!("Apply stock adjustment error: {:?}", e);
+                    return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update stock")
+                        .into_response();
+                }
+            }
+
+            let result = sqlx::query("UPDATE stocktake_items SET adjustment = ? WHERE id = ?")
+                .bind(delta)
+                .bind(item.id)
+                .execute(&mut *tx)
+                .await;
+
+            if let Err(e) = result {
+                eprintln!("Record adjustment error: {:?}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response();
+            }
+        }
+    }
+
+    if let Err(e) = tx.commit().await {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Commit Failed: {}", e),
+        )
+            .into_response();
+    }
+
+    match build_report(&state, stocktake_id).await {
+        Some(report) => Json(report).into_response(),
+        None => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    }
+}
+
+// ==========================================
+// 6. 放弃盘点 (Admin/Vendor Scoped)
+// ==========================================
+// 记录仍然保留，方便复盘
+async fn cancel_stocktake(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(stocktake_id): Path<i64>,
+) -> impl IntoResponse {
+    let stocktake = match load_authorized(&state, &claims, stocktake_id).await {
+        Ok(s) => s,
+        Err(resp) => return resp,
+    };
+
+    if stocktake.status != "open" {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "Stocktake is already closed"})),
+        )
+            .into_response();
+    }
+
+    // 带上 status 条件，避免覆盖刚被其他请求提交的盘点
+    let result = query_as::<_, Stocktake>(
+        r#"
+        UPDATE stocktakes
+        SET status = 'cancelled', finished_at = CURRENT_TIMESTAMP
+        WHERE id = ? AND status = 'open'
+        RETURNING *
+        "#,
+    )
+    .bind(stocktake_id)
+    .fetch_optional(&state.db)
+    .await;
+
+    match result {
+        Ok(Some(s)) => Json(s).into_response(),
+        Ok(None) => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "Stocktake is already closed"})),
+        )
+            .into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    }
+}
+
+// ==========================================
+// 辅助函数
+// ==========================================
+
+// 查询盘点并按其所属场次校验权限
+async fn load_authorized(
+    state: &AppState,
+    claims: &Claims,
+    stocktake_id: i64,
+) -> Result<Stocktake, Response> {
+    // 回收站中场次的盘点视为不存在
+    let stocktake: Option<Stocktake> = query_as(
+        "SELECT s.* FROM stocktakes s JOIN events e ON e.id = s.event_id WHERE s.id = ? AND e.deleted_at IS NULL",
+    )
+        .bind(stocktake_id)
+        .fetch_optional(&state.db)
+        .await
+        .unwrap_or(None);
+
+    let stocktake = match stocktake {
+        Some(s) => s,
+        None => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "Stocktake not found"})),
+            )
+                .into_response())
+        }
+    };
+
+    check_write_permission(claims, stocktake.event_id).map_err(|e| e.into_response())?;
+
+    Ok(stocktake)
+}
+
+async fn build_report(state: &AppState, stocktake_id: i64) -> Option<StocktakeReport> {
+    let stocktake: Stocktake = query_as("SELECT * FROM stocktakes WHERE id = ?")
+        .bind(stocktake_id)
+        .fetch_optional(&state.db)
+        .await
+        .ok()??;
+
+    let items: Vec<StocktakeItem> = query_as(
+        "SELECT * FROM stocktake_items WHERE stocktake_id = ? ORDER BY product_code ASC",
+    )
+    .bind(stocktake_id)
+    .fetch_all(&state.db)
+    .await
+    .ok()?;
+
+    let mut totals = DiscrepancyTotals {
+        total_expected: 0,
+        total_counted: 0,
+        counted_items: 0,
+        uncounted_items: 0,
+        mismatched_items: 0,
+        total_difference: 0,
+        total_value_impact: 0.0,
+    };
+
+    let lines: Vec<DiscrepancyLine> = items
+        .into_iter()
+        .map(|item| {
+            totals.total_expected += item.expected_stock;
+
+            let difference = item.counted_stock.map(|c| c - item.expected_stock);
+            let value_impact =
+                difference.map(|d| ((d as f64) * item.unit_price * 100.0).round() / 100.0);
+
+            match (item.counted_stock, difference) {
+                (Some(counted), Some(diff)) => {
+                    totals.counted_items += 1;
+                    totals.total_counted += counted;
+                    totals.total_difference += diff;
+                    totals.total_value_impact += value_impact.unwrap_or(0.0);
+                    if diff != 0 {
+                        totals.mismatched_items += 1;
+                    }
+                }
+                _ => totals.uncounted_items += 1,
+            }
+
+            DiscrepancyLine {
+                item,
+                difference,
+                value_impact,
+            }
+        })
+        .collect();
+
+    totals.total_value_impact = (totals.total_value_impact * 100.0).round() / 100.0;
+
+    Some(StocktakeReport {
+        stocktake,
+        totals,
+        items: lines,
+    })
+}
+
+fn check_read_permission(claims: &Claims, event_id: i64) -> Result<(), (StatusCode, &'static str)> {
+    if claims.role == "admin"
+        || (claims.role == "vendor"
+            && (claims.access == "all" || claims.event_id == Some(event_id)))
+    {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "Access denied"))
+    }
+}
+
+fn check_write_permission(
+    claims: &Claims,
+    event_id: i64,
+) -> Result<(), (StatusCode, &'static str)> {
+    // 读写逻辑目前一致
+    check_read_permission(claims, event_id)
+}
@@ -7,11 +7,21 @@ use axum::{
 };
 use chrono::Local;
 use sqlx::{query, query_as};
+use std::collections::BTreeSet;
 use std::io::{Cursor, Read, Write};
+use std::path::Path;
 use zip::{write::FileOptions, ZipArchive, ZipWriter};
 
 // 引入你的项目模块
-use crate::{api::guard::AdminOnly, db::models::MasterProduct, state::AppState};
+use crate::{
+    api::{
+        guard::AdminOnly,
+        master_product::{attach_images, attach_tags, fetch_translations, replace_translations},
+    },
+    db::models::{MasterProduct, MasterProductImage, Tag},
+    state::AppState,
+    utils::search,
+};
 
 use axum::extract::DefaultBodyLimit;
 
@@ -34,10 +44,17 @@ async fn export_products(
     _: AdminOnly, // 仅管理员可操作
 ) -> impl IntoResponse {
     // 1. 从数据库获取所有制品信息
-    let products = query_as::<_, MasterProduct>("SELECT * FROM master_products")
+    let mut products = query_as::<_, MasterProduct>("SELECT * FROM master_products")
         .fetch_all(&state.db)
         .await
         .unwrap_or_default();
+    // 相册、标签与多语言随 catalog.json 一起导出
+    attach_images(&state.db, &mut products).await;
+    attach_tags(&state.db, &mut products).await;
+    let mut translations = fetch_translations(&state.db, None).await;
+    for product in products.iter_mut() {
+        product.translations = translations.remove(&product.id).unwrap_or_default();
+    }
 
     // 2. 在内存中创建 ZIP
     let buf = Vec::new();
@@ -78,45 +95,21 @@ async fn export_products(
         }
     }
 
-    // 4. 遍历制品，写入关联的图片文件
+    // 4. 遍历制品，写入关联的图片文件 (封面各尺寸 + 相册各尺寸，去重)
     // 数据库存的是: "/uploads/products/uuid.jpg"
     // state.upload_dir 是 uploads 文件夹的物理路径
+    let mut image_urls: BTreeSet<&String> = BTreeSet::new();
     for prod in &products {
-        if let Some(image_url) = &prod.image_url {
-            // 去掉前导的 "/uploads/" 来获取相对路径
-            let relative_path = image_url
-                .trim_start_matches("/uploads/")
-                .trim_start_matches("uploads/");
-
-            // 构建物理路径
-            let physical_path = state.upload_dir.join(relative_path);
-
-            if physical_path.exists() && physical_path.is_file() {
-                // 读取物理文件（zip 库是同步的）
-                match std::fs::read(&physical_path) {
-                    Ok(file_bytes) => {
-                        // ZIP 内使用简洁路径: products/xxx.jpg
-                        // 确保使用正斜杠（ZIP 标准）
-                        let zip_path = relative_path.replace('\\', "/");
-
-                        if let Err(e) = zip.start_file(&zip_path, options) {
-                            eprintln!("Failed to add {} to zip: {}", zip_path, e);
-                            continue;
-                        }
-                        if let Err(e) = zip.write_all(&file_bytes) {
-                            eprintln!("Failed to write {} to zip: {}", zip_path, e);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to read image file {:?}: {}", physical_path, e);
-                    }
-                }
-            } else {
-                eprintln!("Image file not found: {:?}", physical_path);
-            }
+        image_urls.extend(prod.image_url.iter());
+        image_urls.extend(prod.image_md_url.iter());
+        image_urls.extend(prod.image_sm_url.iter());
+        for img in &prod.images {
+            image_urls.extend([&img.url, &img.url_md, &img.url_sm]);
         }
     }
 
+    write_upload_files(&mut zip, &state.upload_dir, image_urls, options);
+
     // 5. 完成 ZIP 构建
     let cursor = match zip.finish() {
         Ok(c) => c,
@@ -210,66 +203,11 @@ async fn import_products(
                 }
             };
 
-            // 5. 解压图片文件到物理硬盘
-            // 遍历 ZIP 中的所有文件
-            for i in 0..archive.len() {
-                let mut file = match archive.by_index(i) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        eprintln!("Failed to read zip entry {}: {}", i, e);
-                        continue;
-                    }
-                };
-                let file_path_str = file.name().to_string();
-
-                // 跳过 json 和文件夹
-                if file_path_str == "catalog.json" || file_path_str.ends_with('/') {
-                    continue;
-                }
-
-                // 安全检查：防止 Zip Slip 漏洞
-                if file_path_str.contains("..") {
-                    eprintln!("Security: rejected path with ..: {}", file_path_str);
-                    continue;
-                }
-
-                // ZIP 内的路径是: products/xxx.jpg
-                // 目标物理路径: state.upload_dir/products/xxx.jpg
-                let target_path = state.upload_dir.join(&file_path_str);
-
-                // 确保父目录存在
-                if let Some(parent) = target_path.parent() {
-                    if let Err(e) = std::fs::create_dir_all(parent) {
-                        eprintln!("Failed to create dir {:?}: {}", parent, e);
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            format!("Failed to create directory: {}", e),
-                        )
-                            .into_response();
-                    }
-                }
-
-                // 写入文件（使用 std::fs 因为 zip 库是同步的）
-                match std::fs::File::create(&target_path) {
-                    Ok(mut outfile) => {
-                        if let Err(e) = std::io::copy(&mut file, &mut outfile) {
-                            eprintln!("Failed to extract file {}: {}", file_path_str, e);
-                            return (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                format!("Failed to extract file: {}", e),
-                            )
-                                .into_response();
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to create file {:?}: {}", target_path, e);
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            format!("Failed to create file: {}", e),
-                        )
-                            .into_response();
-                    }
-                }
+            // 5. 解压图片文件到物理硬盘 (同名文件覆盖)
+            if let Err(e) =
+                extract_upload_files(&mut archive, &state.upload_dir, "catalog.json", true)
+            {
+                return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
             }
 
             // 6. 数据库 Upsert (插入或更新)
@@ -278,28 +216,55 @@ async fn import_products(
                 // SQLite Upsert 语法
                 // 如果 product_code 冲突，则覆盖更新
                 let sql = r#"
-                    INSERT INTO master_products (product_code, name, default_price, category, image_url, is_active)
-                    VALUES (?, ?, ?, ?, ?, ?)
+                    INSERT INTO master_products
+                        (product_code, name, default_price, category, image_url, image_md_url, image_sm_url, is_active, description)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
                     ON CONFLICT(product_code) DO UPDATE SET
                         name = excluded.name,
                         default_price = excluded.default_price,
                         category = excluded.category,
+                        description = COALESCE(excluded.description, master_products.description),
                         image_url = excluded.image_url,
-                        is_active = excluded.is_active
+                        image_md_url = excluded.image_md_url,
+                        image_sm_url = excluded.image_sm_url,
+                        is_active = excluded.is_active,
+                        version = master_products.version + 1
+                    RETURNING id
                 "#;
 
+                // 旧版制品包没有中图/缩略图，各尺寸都回退到原图
+                let image_md_url = prod.image_md_url.clone().or_else(|| prod.image_url.clone());
+                let image_sm_url = prod.image_sm_url.clone().or_else(|| prod.image_url.clone());
+
                 // 确保 image_url 使用正确的格式（/uploads/...）
                 // 导入的数据应该保持原有的 /uploads/ 前缀
-                let res = query(sql)
+                let res = query_as::<_, (i64,)>(sql)
                     .bind(&prod.product_code)
                     .bind(&prod.name)
                     .bind(&prod.default_price)
                     .bind(&prod.category)
                     .bind(&prod.image_url)
+                    .bind(&image_md_url)
+                    .bind(&image_sm_url)
                     .bind(&prod.is_active)
-                    .execute(&mut *tx)
+                    .bind(&prod.description)
+                    .fetch_one(&mut *tx)
                     .await;
 
+                let res = match res {
+                    Ok((product_id,)) => match replace_gallery(&mut tx, product_id, &prod).await {
+                        Ok(()) => match merge_tags(&mut tx, product_id, &prod.tags).await {
+                            // 旧版制品包没有多语言数据，此时保留本地已有翻译
+                            Ok(()) if !prod.translations.is_empty() => {
+                                replace_translations(&mut tx, product_id, &prod.translations).await
+                            }
+                            other => other,
+                        },
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                };
+
                 if let Err(e) = res {
                     // 数据库写入失败，事务会自动回滚
                     eprintln!("Import DB Error for {}: {}", prod.product_code, e);
@@ -320,6 +285,11 @@ async fn import_products(
                     .into_response();
             }
 
+            // 8. 批量导入后重建检索索引
+            if let Err(e) = search::rebuild_index(&state.db).await {
+                eprintln!("Search index rebuild failed: {:?}", e);
+            }
+
             return (
                 StatusCode::OK,
                 Json(serde_json::json!({
@@ -333,3 +303,204 @@ async fn import_products(
 
     (StatusCode::BAD_REQUEST, "No file found in request").into_response()
 }
+
+// 用制品包中的相册覆盖本地相册；旧版制品包只有单图时，以封面生成一张相册图片
+pub(crate) async fn replace_gallery(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    product_id: i64,
+    prod: &MasterProduct,
+) -> Result<(), sqlx::Error> {
+    query("DELETE FROM master_product_images WHERE master_product_id = ?")
+        .bind(product_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let mut images = prod.images.clone();
+    if images.is_empty() {
+        if let Some(url) = &prod.image_url {
+            images.push(MasterProductImage {
+                id: 0,
+                master_product_id: product_id,
+                position: 0,
+                url: url.clone(),
+                url_md: prod.image_md_url.clone().unwrap_or_else(|| url.clone()),
+                url_sm: prod.image_sm_url.clone().unwrap_or_else(|| url.clone()),
+                width: None,
+                height: None,
+            });
+        }
+    }
+
+    for img in &images {
+        query(
+            r#"
+            INSERT INTO master_product_images
+                (master_product_id, position, url, url_md, url_sm, width, height)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(product_id)
+        .bind(img.position)
+        .bind(&img.url)
+        .bind(&img.url_md)
+        .bind(&img.url_sm)
+        .bind(img.width)
+        .bind(img.height)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+// 按"组名 + 标签名"匹配本地标签，不存在则创建；只追加关联，不移除本地已有标签
+pub(crate) async fn merge_tags(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    product_id: i64,
+    tags: &[Tag],
+) -> Result<(), sqlx::Error> {
+    for tag in tags {
+        if tag.group_name.is_empty() || tag.name.is_empty() {
+            continue;
+        }
+
+        query("INSERT OR IGNORE INTO tag_groups (name) VALUES (?)")
+            .bind(&tag.group_name)
+            .execute(&mut **tx)
+            .await?;
+        query(
+            r#"
+            INSERT OR IGNORE INTO tags (group_id, name, color, sort_order)
+            SELECT id, ?, ?, ? FROM tag_groups WHERE name = ?
+            "#,
+        )
+        .bind(&tag.name)
+        .bind(&tag.color)
+        .bind(tag.sort_order)
+        .bind(&tag.group_name)
+        .execute(&mut **tx)
+        .await?;
+        query(
+            r#"
+            INSERT OR IGNORE INTO master_product_tags (master_product_id, tag_id)
+            SELECT ?, t.id FROM tags t
+            JOIN tag_groups g ON t.group_id = g.id
+            WHERE g.name = ? AND t.name = ?
+            "#,
+        )
+        .bind(product_id)
+        .bind(&tag.group_name)
+        .bind(&tag.name)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+// ==========================================
+// 共享辅助函数 (制品包/展会归档复用)
+// ==========================================
+
+// 将 uploads 下的文件写入 ZIP，ZIP 内路径与 uploads 下的相对路径一致 (如 products/xxx.jpg)
+pub(crate) fn write_upload_files<'a, W: Write + std::io::Seek>(
+    zip: &mut ZipWriter<W>,
+    upload_dir: &Path,
+    image_urls: impl IntoIterator<Item = &'a String>,
+    options: FileOptions,
+) {
+    for image_url in image_urls {
+        // 去掉前导的 "/uploads/" 来获取相对路径
+        let relative_path = image_url
+            .trim_start_matches("/uploads/")
+            .trim_start_matches("uploads/");
+
+        // 构建物理路径
+        let physical_path = upload_dir.join(relative_path);
+
+        if physical_path.exists() && physical_path.is_file() {
+            // 读取物理文件（zip 库是同步的）
+            match std::fs::read(&physical_path) {
+                Ok(file_bytes) => {
+                    // 确保使用正斜杠（ZIP 标准）
+                    let zip_path = relative_path.replace('\\', "/");
+
+                    if let Err(e) = zip.start_file(&zip_path, options) {
+                        eprintln!("Failed to add {} to zip: {}", zip_path, e);
+                        continue;
+                    }
+                    if let Err(e) = zip.write_all(&file_bytes) {
+                        eprintln!("Failed to write {} to zip: {}", zip_path, e);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to read image file {:?}: {}", physical_path, e);
+                }
+            }
+        } else {
+            eprintln!("Image file not found: {:?}", physical_path);
+        }
+    }
+}
+
+// 将 ZIP 中除数据文件外的所有文件解压到 uploads；overwrite 为 false 时保留本地已有文件。
+// 返回写入的文件数
+pub(crate) fn extract_upload_files<R: Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+    upload_dir: &Path,
+    data_file: &str,
+    overwrite: bool,
+) -> Result<usize, String> {
+    let mut written = 0;
+    // 遍历 ZIP 中的所有文件
+    for i in 0..archive.len() {
+        let mut file = match archive.by_index(i) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("Failed to read zip entry {}: {}", i, e);
+                continue;
+            }
+        };
+        let file_path_str = file.name().to_string();
+
+        // 跳过 json 和文件夹
+        if file_path_str == data_file || file_path_str.ends_with('/') {
+            continue;
+        }
+
+        // 安全检查：防止 Zip Slip 漏洞 (绝对路径、.. 等逃出解压目录的条目一律跳过)
+        let Some(relative) = file.enclosed_name().map(|p| p.to_path_buf()) else {
+            eprintln!("Security: rejected unsafe path: {}", file_path_str);
+            continue;
+        };
+
+        // ZIP 内的路径是: products/xxx.jpg
+        // 目标物理路径: upload_dir/products/xxx.jpg
+        let target_path = upload_dir.join(&relative);
+        if !overwrite && target_path.exists() {
+            continue;
+        }
+
+        // 确保父目录存在
+        if let Some(parent) = target_path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                eprintln!("Failed to create dir {:?}: {}", parent, e);
+                return Err(format!("Failed to create directory: {}", e));
+            }
+        }
+
+        // 写入文件（使用 std::fs 因为 zip 库是同步的）
+        match std::fs::File::create(&target_path) {
+            Ok(mut outfile) => {
+                if let Err(e) = std::io::copy(&mut file, &mut outfile) {
+                    eprintln!("Failed to extract file {}: {}", file_path_str, e);
+                    return Err(format!("Failed to extract file: {}", e));
+                }
+                written += 1;
+            }
+            Err(e) => {
+                eprintln!("Failed to create file {:?}: {}", target_path, e);
+                return Err(format!("Failed to create file: {}", e));
+            }
+        }
+    }
+    Ok(written)
+}
@@ -0,0 +1,506 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    routing::{get, post, put},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{query, query_as, SqlitePool};
+use std::collections::HashMap;
+
+use crate::{
+    api::guard::AdminOnly,
+    db::models::{Tag, TagGroup},
+    state::AppState,
+    utils::search,
+};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // 公开接口
+        .route("/tag-groups", get(list_tag_groups).post(create_tag_group))
+        // 管理员接口
+        .route(
+            "/tag-groups/:id",
+            put(update_tag_group).delete(delete_tag_group),
+        )
+        .route("/tags", post(create_tag))
+        .route("/tags/:id", put(update_tag).delete(delete_tag))
+}
+
+// 标签查询的公共列 (JOIN tag_groups 获取组名)
+const TAG_COLUMNS: &str = r#"
+    t.id, t.group_id, t.name, t.color, t.sort_order, g.name AS group_name
+"#;
+
+fn unique_violation(e: &sqlx::Error) -> bool {
+    e.to_string().contains("UNIQUE constraint failed")
+}
+
+// ==========================================
+// 1. 获取标签组及其标签 (Public)
+// ==========================================
+#[derive(Serialize)]
+struct TagGroupWithTags {
+    #[serde(flatten)]
+    group: TagGroup,
+    tags: Vec<Tag>,
+}
+
+async fn list_tag_groups(State(state): State<AppState>) -> impl IntoResponse {
+    let groups: Vec<TagGroup> =
+        query_as("SELECT id, name, sort_order FROM tag_groups ORDER BY sort_order ASC, id ASC")
+            .fetch_all(&state.db)
+            .await
+            .unwrap_or_default();
+
+    let sql = format!(
+        r#"
+        SELECT {}
+        FROM tags t
+        JOIN tag_groups g ON t.group_id = g.id
+        ORDER BY t.sort_order ASC, t.id ASC
+        "#,
+        TAG_COLUMNS
+    );
+    let tags: Vec<Tag> = query_as(&sql)
+        .fetch_all(&state.db)
+        .await
+        .unwrap_or_default();
+
+    let result: Vec<TagGroupWithTags> = groups
+        .into_iter()
+        .map(|group| TagGroupWithTags {
+            tags: tags
+                .iter()
+                .filter(|t| t.group_id == group.id)
+                .cloned()
+                .collect(),
+            group,
+        })
+        .collect();
+
+    Json(result)
+}
+
+// ==========================================
+// 2. 标签组 CRUD (Admin Only)
+// ==========================================
+#[derive(Deserialize)]
+struct TagGroupRequest {
+    name: String,
+    sort_order: Option<i64>,
+}
+
+async fn create_tag_group(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Json(payload): Json<TagGroupRequest>,
+) -> impl IntoResponse {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Name is required"})),
+        )
+            .into_response();
+    }
+
+    let result = query_as::<_, TagGroup>(
+        r#"
+        INSERT INTO tag_groups (name, sort_order)
+        VALUES (?, COALESCE(?, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM tag_groups)))
+        RETURNING id, name, sort_order
+        "#,
+    )
+    .bind(name)
+    .bind(payload.sort_order)
+    .fetch_one(&state.db)
+    .await;
+
+    match result {
+        Ok(group) => (StatusCode::CREATED, Json(group)).into_response(),
+        Err(e) if unique_violation(&e) => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "Tag group already exists"})),
+        )
+            .into_response(),
+        Err(e) => {
+            eprintln!("DB Error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+async fn update_tag_group(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+    Json(payload): Json<TagGroupRequest>,
+) -> impl IntoResponse {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Name is required"})),
+        )
+            .into_response();
+    }
+
+    let result = query_as::<_, TagGroup>(
+        r#"
+        UPDATE tag_groups
+        SET name = ?, sort_order = COALESCE(?, sort_order)
+        WHERE id = ?
+        RETURNING id, name, sort_order
+        "#,
+    )
+    .bind(name)
+    .bind(payload.sort_order)
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await;
+
+    match result {
+        Ok(Some(group)) => (StatusCode::OK, Json(group)).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Tag group not found").into_response(),
+        Err(e) if unique_violation(&e) => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "Tag group already exists"})),
+        )
+            .into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+    }
+}
+
+// 删除标签组会连同组内标签及商品关联一起删除
+async fn delete_tag_group(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let result: Result<u64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        query(
+            "DELETE FROM master_product_tags WHERE tag_id IN (SELECT id FROM tags WHERE group_id = ?)",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        query("DELETE FROM tags WHERE group_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let res = query("DELETE FROM tag_groups WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(res.rows_affected())
+    }
+    .await;
+
+    match result {
+        Ok(0) => (StatusCode::NOT_FOUND, "Tag group not found").into_response(),
+        Ok(_) => {
+            reindex(&state.db).await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            eprintln!("Delete tag group error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 3. 标签 CRUD (Admin Only)
+// ==========================================
+#[derive(Deserialize)]
+struct CreateTagRequest {
+    group_id: i64,
+    name: String,
+    color: Option<String>,
+    sort_order: Option<i64>,
+}
+
+async fn create_tag(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Json(payload): Json<CreateTagRequest>,
+) -> impl IntoResponse {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Name is required"})),
+        )
+            .into_response();
+    }
+
+    // 组不存在时 INSERT ... SELECT 不插入任何行
+    let result = query(
+        r#"
+        INSERT INTO tags (group_id, name, color, sort_order)
+        SELECT id, ?, ?, COALESCE(?, (SELECT COALESCE(MAX(sort_order) + 1, 0) FROM tags WHERE group_id = ?))
+        FROM tag_groups WHERE id = ?
+        "#,
+    )
+    .bind(name)
+    .bind(&payload.color)
+    .bind(payload.sort_order)
+    .bind(payload.group_id)
+    .bind(payload.group_id)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(res) if res.rows_affected() == 0 => {
+            (StatusCode::NOT_FOUND, "Tag group not found").into_response()
+        }
+        Ok(res) => match fetch_tag(&state.db, res.last_insert_rowid()).await {
+            Some(tag) => (StatusCode::CREATED, Json(tag)).into_response(),
+            None => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response(),
+        },
+        Err(e) if unique_violation(&e) => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "Tag already exists in this group"})),
+        )
+            .into_response(),
+        Err(e) => {
+            eprintln!("DB Error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UpdateTagRequest {
+    group_id: Option<i64>, // 可移动到其他标签组
+    name: Option<String>,
+    color: Option<String>,
+    sort_order: Option<i64>,
+}
+
+async fn update_tag(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+    Json(payload): Json<UpdateTagRequest>,
+) -> impl IntoResponse {
+    let name = payload.name.as_deref().map(str::trim);
+    if name == Some("") {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Name cannot be empty"})),
+        )
+            .into_response();
+    }
+
+    let result = query(
+        r#"
+        UPDATE tags
+        SET group_id = COALESCE(?, group_id),
+            name = COALESCE(?, name),
+            color = COALESCE(?, color),
+            sort_order = COALESCE(?, sort_order)
+        WHERE id = ?
+        "#,
+    )
+    .bind(payload.group_id)
+    .bind(name)
+    .bind(&payload.color)
+    .bind(payload.sort_order)
+    .bind(id)
+    .execute(&state.db)
+    .await;
+
+    match result {
+        Ok(res) if res.rows_affected() == 0 => {
+            (StatusCode::NOT_FOUND, "Tag not found").into_response()
+        }
+        Ok(_) => {
+            // 标签名参与商品检索，改名后需要重建索引
+            if name.is_some() {
+                reindex(&state.db).await;
+            }
+            match fetch_tag(&state.db, id).await {
+                Some(tag) => (StatusCode::OK, Json(tag)).into_response(),
+                None => (StatusCode::NOT_FOUND, "Tag not found").into_response(),
+            }
+        }
+        Err(e) if unique_violation(&e) => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "Tag already exists in this group"})),
+        )
+            .into_response(),
+        Err(e) => {
+            // 外键约束失败：目标标签组不存在
+            eprintln!("Update tag error: {:?}", e);
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid tag group"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn delete_tag(
+    State(state): State<AppState>,
+    _: AdminOnly,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let result: Result<u64, sqlx::Error> = async {
+        let mut tx = state.db.begin().await?;
+        query("DELETE FROM master_product_tags WHERE tag_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let res = query("DELETE FROM tags WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(res.rows_affected())
+    }
+    .await;
+
+    match result {
+        Ok(0) => (StatusCode::NOT_FOUND, "Tag not found").into_response(),
+        Ok(_) => {
+            reindex(&state.db).await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => {
+            eprintln!("Delete tag error: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database Error").into_response()
+        }
+    }
+}
+
+// ==========================================
+// 辅助：供商品列表/统计使用
+// ==========================================
+async fn fetch_tag(db: &SqlitePool, id: i64) -> Option<Tag> {
+    let sql = format!(
+        "SELECT {} FROM tags t JOIN tag_groups g ON t.group_id = g.id WHERE t.id = ?",
+        TAG_COLUMNS
+    );
+    query_as::<_, Tag>(&sql)
+        .bind(id)
+        .fetch_optional(db)
+        .await
+        .unwrap_or(None)
+}
+
+async fn reindex(db: &SqlitePool) {
+    if let Err(e) = search::rebuild_index(db).await {
+        eprintln!("Search index rebuild failed: {:?}", e);
+    }
+}
+
+/// 解析查询参数中的标签 ID 列表，如 `?tags=1,2,5`
+pub(crate) fn parse_tag_ids(raw: Option<&str>) -> Vec<i64> {
+    raw.unwrap_or("")
+        .split(',')
+        .filter_map(|s| s.trim().parse().ok())
+        .collect()
+}
+
+/// 生成标签筛选的 SQL 条件：同一标签组内为 OR，不同标签组之间为 AND
+///
+/// - `product_column`: 外层查询中全局商品 ID 的列名，如 `mp.id` / `p.master_product_id`
+///
+/// 返回 (以 AND 开头的条件片段, 需要依次绑定的标签 ID)；不存在的标签会被忽略
+pub(crate) async fn tag_filter(
+    db: &SqlitePool,
+    tag_ids: &[i64],
+    product_column: &str,
+) -> (String, Vec<i64>) {
+    if tag_ids.is_empty() {
+        return (String::new(), Vec::new());
+    }
+
+    let placeholders = tag_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    let sql = format!(
+        "SELECT id, group_id FROM tags WHERE id IN ({}) ORDER BY group_id",
+        placeholders
+    );
+    let mut q = query_as::<_, (i64, i64)>(&sql);
+    for id in tag_ids {
+        q = q.bind(id);
+    }
+    let rows = q.fetch_all(db).await.unwrap_or_default();
+
+    let mut by_group: Vec<(i64, Vec<i64>)> = Vec::new();
+    for (tag_id, group_id) in rows {
+        match by_group.iter_mut().find(|(g, _)| *g == group_id) {
+            Some((_, ids)) => ids.push(tag_id),
+            None => by_group.push((group_id, vec![tag_id])),
+        }
+    }
+
+    let mut clause = String::new();
+    let mut binds = Vec::new();
+    for (_, ids) in by_group {
+        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        clause.push_str(&format!(
+            " AND EXISTS (SELECT 1 FROM master_product_tags mpt WHERE mpt.master_product_id = {} AND mpt.tag_id IN ({}))",
+            product_column, placeholders
+        ));
+        binds.extend(ids);
+    }
+
+    // 传入的标签全都不存在时，筛选结果应为空而不是全部
+    if binds.is_empty() {
+        clause.push_str(" AND 0");
+    }
+    (clause, binds)
+}
+
+/// 查询商品的标签，按全局商品 ID 分组；product_ids 为 None 时读取全部商品
+pub(crate) async fn tags_by_product(
+    db: &SqlitePool,
+    product_ids: Option<&[i64]>,
+) -> HashMap<i64, Vec<Tag>> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        master_product_id: i64,
+        #[sqlx(flatten)]
+        tag: Tag,
+    }
+
+    let id_filter = match product_ids {
+        Some([]) => return HashMap::new(),
+        Some(ids) => format!(
+            "WHERE mpt.master_product_id IN ({})",
+            ids.iter().map(|_| "?").collect::<Vec<_>>().join(",")
+        ),
+        None => String::new(),
+    };
+    let sql = format!(
+        r#"
+        SELECT mpt.master_product_id, {}
+        FROM master_product_tags mpt
+        JOIN tags t ON mpt.tag_id = t.id
+        JOIN tag_groups g ON t.group_id = g.id
+        {}
+        ORDER BY g.sort_order ASC, g.id ASC, t.sort_order ASC, t.id ASC
+        "#,
+        TAG_COLUMNS, id_filter
+    );
+    let mut q = query_as::<_, Row>(&sql);
+    for id in product_ids.unwrap_or_default() {
+        q = q.bind(id);
+    }
+    let rows: Vec<Row> = q.fetch_all(db).await.unwrap_or_default();
+
+    let mut map: HashMap<i64, Vec<Tag>> = HashMap::new();
+    for row in rows {
+        map.entry(row.master_product_id).or_default().push(row.tag);
+    }
+    map
+}
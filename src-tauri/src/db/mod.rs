@@ -31,6 +31,9 @@ pub async fn init_db(app_data_dir: &PathBuf) -> Result<SqlitePool, sqlx::Error>
     // 5. 运行迁移 (使用运行时方式避免编译时需要 DATABASE_URL)
     sqlx::migrate!().run(&pool).await?;
 
+    // 5.1 重建商品检索索引 (拼音/罗马音规则可能随版本更新)
+    crate::utils::search::rebuild_index(&pool).await?;
+
     // ================== 新增逻辑 ==================
     // 6. 检查并初始化默认管理员密码
     // 假设默认密码是 "admin123" (生产环境应强制用户首次登录修改，这里为了演示简化)
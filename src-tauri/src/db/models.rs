@@ -11,12 +11,66 @@ pub struct MasterProduct {
     pub product_code: String,
     pub name: String,
     pub default_price: f64,        // SQLite REAL 对应 f64
-    pub image_url: Option<String>, // 可能为空
+    pub image_url: Option<String>, // 可能为空；封面大图
+    #[serde(default)] // 旧版 .boothpack 中没有尺寸字段
+    pub image_md_url: Option<String>, // 封面中图
+    #[serde(default)]
+    pub image_sm_url: Option<String>, // 封面缩略图
     pub category: Option<String>,
     pub is_active: bool,
+    #[serde(default)] // 旧版 .boothpack 中没有该字段
+    pub version: i64,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub archived_at: Option<NaiveDateTime>, // 归档时间，NULL 表示未归档
+    // 相册不在 master_products 表中，需要时由接口单独填充
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub images: Vec<MasterProductImage>,
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub description: Option<String>, // 默认语言的描述
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub translations: Vec<ProductTranslation>,
+    // 按请求语言解析出的展示名称/描述 (缺失时回退到默认值)，仅在响应中出现
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_description: Option<String>,
     // created_at 通常 API 不需要返回，或者需要自定义序列化格式，这里暂时忽略
 }
 
+// 商品多语言翻译 (name/description 为空表示沿用默认语言)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProductTranslation {
+    #[serde(default)]
+    pub master_product_id: i64,
+    pub locale: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+// 商品相册图片 (position 最小的一张即封面)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MasterProductImage {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub master_product_id: i64,
+    pub position: i64,
+    pub url: String,
+    pub url_md: String,
+    pub url_sm: String,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+}
+
 // 用于接收前端创建商品的请求 Body
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
@@ -37,11 +91,71 @@ pub struct Event {
     #[serde(rename = "date")] //以此匹配前端 JSON 字段名 "date"
     pub event_date: String,
     pub location: Option<String>,
-    pub status: String,
+    pub status: EventStatus,
     // vendor_password 不应该通过 API 直接返回给前端，加上 skip_serializing
     #[serde(skip_serializing)]
     pub vendor_password: Option<String>,
     pub payment_qr_code_path: Option<String>,
+    pub soldout_policy: String, // "show", "bottom", "hide"
+    pub version: i64,
+    pub started_at: Option<NaiveDateTime>, // 最近一次开始营业的时间
+    pub ended_at: Option<NaiveDateTime>,   // 最近一次结束营业的时间
+    // 移入回收站的时间，NULL 表示未删除
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+// 场次状态：未进行 → 进行中 → 已结束 (未进行也可直接结束，表示取消出摊)
+// 其余变更 (重新开放、回退) 需要管理员强制执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+pub enum EventStatus {
+    #[serde(rename = "未进行")]
+    #[sqlx(rename = "未进行")]
+    NotStarted,
+    #[serde(rename = "进行中")]
+    #[sqlx(rename = "进行中")]
+    Active,
+    #[serde(rename = "已结束")]
+    #[sqlx(rename = "已结束")]
+    Ended,
+}
+
+impl EventStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventStatus::NotStarted => "未进行",
+            EventStatus::Active => "进行中",
+            EventStatus::Ended => "已结束",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "未进行" => Some(EventStatus::NotStarted),
+            "进行中" => Some(EventStatus::Active),
+            "已结束" => Some(EventStatus::Ended),
+            _ => None,
+        }
+    }
+
+    pub fn can_transition_to(&self, next: EventStatus) -> bool {
+        matches!(
+            (self, next),
+            (EventStatus::NotStarted, EventStatus::Active)
+                | (EventStatus::NotStarted, EventStatus::Ended)
+                | (EventStatus::Active, EventStatus::Ended)
+        )
+    }
+
+    // 只有进行中的场次接受顾客下单
+    pub fn accepts_orders(&self) -> bool {
+        *self == EventStatus::Active
+    }
+
+    // 已结束的场次锁定商品阵容与库存
+    pub fn allows_product_edits(&self) -> bool {
+        *self != EventStatus::Ended
+    }
 }
 
 // ==========================================
@@ -57,12 +171,33 @@ pub struct Product {
     pub price: f64,
     pub initial_stock: i64,
     pub current_stock: i64,
+    pub sort_order: i64,
+    pub is_featured: bool,
+    pub is_new: bool,
+    pub version: i64,
     // 以下字段数据库中没有，需要通过 JOIN master_products 获取
     // 使用 sqlx 里的 default 属性处理 JOIN 出来的 nullable 字段
     #[sqlx(default)]
     pub image_url: Option<String>,
     #[sqlx(default)]
+    pub image_md_url: Option<String>,
+    #[sqlx(default)]
+    pub image_sm_url: Option<String>,
+    #[sqlx(default)]
     pub category: Option<String>,
+    #[sqlx(default)]
+    pub description: Option<String>,
+    // 标签挂在全局商品上，需要时由接口单独填充
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    // 按请求语言解析出的展示名称/描述；name 仍是上架时的默认语言快照 (订单使用)
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_description: Option<String>,
 }
 
 // ==========================================
@@ -76,6 +211,9 @@ pub struct Order {
     pub status: String, // "pending", "completed", "cancelled"
     #[serde(rename = "timestamp")]
     pub created_at: NaiveDateTime, // sqlx 会自动处理 SQLite 的 DATETIME
+    pub session_id: Option<i64>, // 归属的营业日 (多日展会)
+    pub payment_method_id: Option<i64>, // 顾客使用的收款方式
+    pub payment_method: Option<String>, // 收款方式名称快照
 }
 
 // 这是一个"复合结构体"，用于 API 返回包含 items 的完整订单信息
@@ -162,3 +300,173 @@ pub struct SalesReport {
     pub summary: Vec<ProductSalesDetail>,
     pub timeseries: Vec<SalesTimeSeries>,
 }
+
+// ==========================================
+// 8. Stocktake (盘点)
+// ==========================================
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Stocktake {
+    pub id: i64,
+    pub event_id: i64,
+    pub status: String, // "open", "committed", "cancelled"
+    pub note: Option<String>,
+    pub applied: bool,
+    pub created_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StocktakeItem {
+    pub id: i64,
+    pub stocktake_id: i64,
+    pub product_id: i64,
+    pub product_code: String,
+    pub product_name: String,
+    pub unit_price: f64,
+    pub expected_stock: i64,
+    pub counted_stock: Option<i64>,
+    pub adjustment: Option<i64>,
+}
+
+// ==========================================
+// 9. Tag (商品标签)
+// ==========================================
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TagGroup {
+    pub id: i64,
+    pub name: String,
+    pub sort_order: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Tag {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub group_id: i64,
+    pub name: String,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub sort_order: i64,
+    // JOIN tag_groups 获取，制品包导入时按组名匹配
+    #[sqlx(default)]
+    pub group_name: String,
+}
+
+// ==========================================
+// 10. Event Session (营业日)
+// ==========================================
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct EventSession {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub event_id: i64,
+    #[serde(rename = "date")]
+    pub session_date: String,
+    pub opens_at: Option<String>, // HH:MM
+    pub closes_at: Option<String>,
+}
+
+// ==========================================
+// 11. Event Schedule (定时开闭场)
+// ==========================================
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct EventSchedule {
+    pub id: i64,
+    pub event_id: i64,
+    pub session_id: Option<i64>,
+    pub action: String, // "open", "pause", "close"
+    pub run_at: String, // 本地时间 YYYY-MM-DD HH:MM
+    pub state: String,  // "pending", "applied", "skipped"
+    pub note: Option<String>,
+    pub processed_at: Option<NaiveDateTime>,
+}
+
+// ==========================================
+// 12. Event Settings (场次配置)
+// ==========================================
+// 存于 event_settings.settings (JSON)；缺省字段取默认值，旧数据无需迁移
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventSettings {
+    pub currency_symbol: String,
+    // 关闭后顾客不能自行下单，只能由摊主/管理员代下单
+    pub self_ordering: bool,
+    // 待付款订单超时自动取消并归还库存 (分钟)；None 表示不超时
+    pub pending_order_timeout_minutes: Option<i64>,
+    // 单笔订单商品总件数上限；None 表示不限
+    pub max_items_per_order: Option<i64>,
+    // 与 events.soldout_policy 同步："show", "bottom", "hide"
+    pub soldout_policy: String,
+    pub receipt_header: Option<String>,
+    pub receipt_footer: Option<String>,
+    // 统计默认时间粒度 (分钟)：5 ~ 1440
+    pub stats_interval_minutes: i64,
+    // 单场次摊主登录有效期 (小时)
+    pub vendor_token_hours: i64,
+}
+
+impl Default for EventSettings {
+    fn default() -> Self {
+        Self {
+            currency_symbol: "¥".to_string(),
+            self_ordering: true,
+            pending_order_timeout_minutes: None,
+            max_items_per_order: None,
+            soldout_policy: "show".to_string(),
+            receipt_header: None,
+            receipt_footer: None,
+            stats_interval_minutes: 60,
+            vendor_token_hours: 24,
+        }
+    }
+}
+
+impl EventSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let symbol_len = self.currency_symbol.trim().chars().count();
+        if symbol_len == 0 || symbol_len > 8 {
+            return Err("currency_symbol must be 1-8 characters".to_string());
+        }
+        if let Some(minutes) = self.pending_order_timeout_minutes {
+            if !(1..=1440).contains(&minutes) {
+                return Err("pending_order_timeout_minutes must be between 1 and 1440".to_string());
+            }
+        }
+        if self.max_items_per_order.is_some_and(|n| n < 1) {
+            return Err("max_items_per_order must be at least 1".to_string());
+        }
+        if !matches!(self.soldout_policy.as_str(), "show" | "bottom" | "hide") {
+            return Err("Invalid soldout_policy. Must be one of: show, bottom, hide".to_string());
+        }
+        for text in [&self.receipt_header, &self.receipt_footer]
+            .into_iter()
+            .flatten()
+        {
+            if text.chars().count() > 500 {
+                return Err("Receipt text must be at most 500 characters".to_string());
+            }
+        }
+        if !(5..=1440).contains(&self.stats_interval_minutes) {
+            return Err("stats_interval_minutes must be between 5 and 1440".to_string());
+        }
+        if !(1..=168).contains(&self.vendor_token_hours) {
+            return Err("vendor_token_hours must be between 1 and 168".to_string());
+        }
+        Ok(())
+    }
+}
+
+// ==========================================
+// 13. Payment Method (收款方式)
+// ==========================================
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PaymentMethod {
+    pub id: i64,
+    pub event_id: i64,
+    pub label: String,
+    #[serde(rename = "qrcode_url")]
+    pub qr_code_path: Option<String>, // 完整路径，如 /uploads/events/xxx.jpg
+    pub sort_order: i64,
+}
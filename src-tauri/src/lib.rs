@@ -5,6 +5,7 @@ use tauri::{DragDropEvent, Emitter, Manager, WindowEvent};
 
 mod api;
 mod db;
+mod scheduler;
 mod server;
 mod state;
 mod utils;
@@ -104,6 +105,9 @@ pub fn run() {
             // -------------------------------------------------------------
             // [优化点 2] 使用 Tauri 内置异步运行时
             // -------------------------------------------------------------
+            // 定时开闭场：启动时立即补执行错过的任务，之后定期检查
+            tauri::async_runtime::spawn(scheduler::run(state.db.clone()));
+
             tauri::async_runtime::spawn(async move {
                 println!("[Booth Tool] Starting HTTP server on port 5140...");
                 server::start_server(state, 5140).await;
@@ -0,0 +1,30 @@
+// src/scheduler.rs
+//
+// 后台调度器：定期执行到期的定时开闭场任务 (见 api/schedule.rs)，
+// 并取消超时的待付款订单 (见 api/order.rs)
+
+use sqlx::SqlitePool;
+use std::time::Duration;
+
+use crate::api::{order::expire_pending_orders, schedule::apply_due_schedules};
+
+// 检查间隔；任务精确到分钟，30 秒足够
+const TICK: Duration = Duration::from_secs(30);
+
+pub async fn run(db: SqlitePool) {
+    let mut ticker = tokio::time::interval(TICK);
+    // 首次 tick 立即触发，服务重启后马上补执行错过的任务
+    loop {
+        ticker.tick().await;
+        match apply_due_schedules(&db).await {
+            Ok(0) => {}
+            Ok(n) => println!("[Scheduler] Applied {} scheduled transition(s)", n),
+            Err(e) => eprintln!("[Scheduler] Failed to apply schedules: {:?}", e),
+        }
+        match expire_pending_orders(&db).await {
+            Ok(0) => {}
+            Ok(n) => println!("[Scheduler] Cancelled {} expired pending order(s)", n),
+            Err(e) => eprintln!("[Scheduler] Failed to expire pending orders: {:?}", e),
+        }
+    }
+}
@@ -50,7 +50,7 @@ pub async fn save_upload_file(
     relative_path_str.push_str(&new_filename);
 
     // 5. 读取并写入文件
-    // TODO: 如果未来需要图片压缩 (image crate)，可以在这里拦截 bytes 进行处理
+    // 商品图片请使用 utils::image::save_upload_image (校验 + 压缩 + 缩略图)
     let data = field.bytes().await.map_err(|e| e.to_string())?;
 
     fs::write(&file_path, data)
@@ -78,8 +78,10 @@ pub async fn delete_file(base_dir: &PathBuf, relative_path: &str) -> std::io::Re
         ));
     }
 
-    // 去掉 "uploads/" 前缀（如果存在）
-    let path_without_uploads = relative_path.trim_start_matches("uploads/");
+    // 去掉 "/uploads/" 前缀（如果存在）；保留前导斜杠会让 join 变成绝对路径
+    let path_without_uploads = relative_path
+        .trim_start_matches('/')
+        .trim_start_matches("uploads/");
 
     let file_path = base_dir.join(path_without_uploads);
     if file_path.exists() {
@@ -87,3 +89,44 @@ pub async fn delete_file(base_dir: &PathBuf, relative_path: &str) -> std::io::Re
     }
     Ok(())
 }
+
+/// 复制已上传的文件 (新文件名)，用于克隆场次等需要独立文件的场景
+///
+/// - `relative_path`: 源文件路径，如 "/uploads/events/xxx.jpg" 或 "events/xxx.jpg"
+///
+/// 返回: 新文件的完整路径 (包含 /uploads/ 前缀)
+pub async fn copy_file(base_dir: &std::path::Path, relative_path: &str) -> std::io::Result<String> {
+    if relative_path.contains("..") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Invalid path",
+        ));
+    }
+
+    let source = relative_path
+        .trim_start_matches('/')
+        .trim_start_matches("uploads/");
+    let source_path = std::path::Path::new(source);
+    let ext = source_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("jpg");
+    let new_relative = match source_path.parent().and_then(|p| p.to_str()) {
+        Some(folder) if !folder.is_empty() => format!("{}/{}.{}", folder, Uuid::new_v4(), ext),
+        _ => format!("{}.{}", Uuid::new_v4(), ext),
+    };
+
+    fs::copy(base_dir.join(source), base_dir.join(&new_relative)).await?;
+    Ok(format!("/uploads/{}", new_relative))
+}
+
+/// 统一为可直接访问的完整路径
+///
+/// 旧数据存的是 "events/xxx.jpg"，新数据是 "/uploads/events/xxx.jpg"
+pub fn upload_url(path: &str) -> String {
+    if path.starts_with("/uploads/") {
+        path.to_string()
+    } else {
+        format!("/uploads/{}", path.trim_start_matches('/'))
+    }
+}
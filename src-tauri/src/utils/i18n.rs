@@ -0,0 +1,113 @@
+// src/utils/i18n.rs
+//
+// 请求语言解析：?lang= 优先，其次 Accept-Language。
+// 语言代码统一为小写主语言 (zh-CN → zh, ja-JP → ja)，与 master_product_translations.locale 一致。
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+use std::convert::Infallible;
+
+use crate::db::models::ProductTranslation;
+
+/// 按优先级排列的请求语言列表 (可能为空)
+pub struct RequestLocale(pub Vec<String>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestLocale
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let mut locales: Vec<String> = Vec::new();
+
+        // 1. ?lang=ja
+        if let Some(query) = parts.uri.query() {
+            for pair in query.split('&') {
+                if let Some(value) = pair.strip_prefix("lang=") {
+                    if let Some(locale) = normalize_locale(value) {
+                        locales.push(locale);
+                    }
+                }
+            }
+        }
+
+        // 2. Accept-Language: ja-JP,ja;q=0.9,en;q=0.8
+        if let Some(value) = parts
+            .headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+        {
+            for locale in parse_accept_language(value) {
+                if !locales.contains(&locale) {
+                    locales.push(locale);
+                }
+            }
+        }
+
+        Ok(RequestLocale(locales))
+    }
+}
+
+/// 规范化语言代码；非法输入返回 None
+pub fn normalize_locale(value: &str) -> Option<String> {
+    let primary = value.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+    if (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_lowercase()) {
+        Some(primary)
+    } else {
+        None
+    }
+}
+
+fn parse_accept_language(value: &str) -> Vec<String> {
+    let mut weighted: Vec<(String, f32)> = value
+        .split(',')
+        .filter_map(|part| {
+            let mut pieces = part.split(';');
+            let locale = normalize_locale(pieces.next()?)?;
+            let q = pieces
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (q > 0.0).then_some((locale, q))
+        })
+        .collect();
+    // 稳定排序：同权重保持原顺序
+    weighted.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut locales: Vec<String> = Vec::new();
+    for (locale, _) in weighted {
+        if !locales.contains(&locale) {
+            locales.push(locale);
+        }
+    }
+    locales
+}
+
+/// 按请求语言挑选展示名称/描述，逐字段回退：请求语言 → 默认值
+pub fn localize(
+    locales: &[String],
+    translations: &[ProductTranslation],
+    default_name: &str,
+    default_description: Option<&str>,
+) -> (String, Option<String>) {
+    let pick = |field: fn(&ProductTranslation) -> Option<&String>| {
+        locales.iter().find_map(|locale| {
+            translations
+                .iter()
+                .find(|t| &t.locale == locale)
+                .and_then(field)
+                .filter(|v| !v.trim().is_empty())
+                .cloned()
+        })
+    };
+
+    let name = pick(|t| t.name.as_ref()).unwrap_or_else(|| default_name.to_string());
+    let description =
+        pick(|t| t.description.as_ref()).or_else(|| default_description.map(|d| d.to_string()));
+    (name, description)
+}
@@ -0,0 +1,183 @@
+// src/utils/image.rs
+
+use axum::extract::multipart::Field;
+use image::{
+    codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+    DynamicImage, ImageDecoder, ImageReader, Limits,
+};
+use std::io::Cursor;
+use std::path::Path;
+use tokio::fs;
+use uuid::Uuid;
+
+/// 上传大小上限 (20MB)，手机原图通常在 3~8MB
+pub const MAX_UPLOAD_BYTES: usize = 20 * 1024 * 1024;
+/// 像素尺寸上限，防止解压炸弹
+const MAX_DIMENSION: u32 = 12_000;
+
+/// 各尺寸长边像素：原图 (压缩后保存)、中图 (详情/大卡片)、缩略图 (点单网格)
+const FULL_SIZE: u32 = 1600;
+const MEDIUM_SIZE: u32 = 800;
+const THUMB_SIZE: u32 = 320;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// 处理后的图片各尺寸访问路径 (均带 /uploads/ 前缀)
+#[derive(Debug, Clone)]
+pub struct StoredImage {
+    pub url: String,
+    pub url_md: String,
+    pub url_sm: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl StoredImage {
+    /// 返回所有尺寸的路径，删除文件时使用
+    pub fn all_urls(&self) -> [&str; 3] {
+        [&self.url, &self.url_md, &self.url_sm]
+    }
+}
+
+struct EncodedImage {
+    full: Vec<u8>,
+    medium: Vec<u8>,
+    thumb: Vec<u8>,
+    ext: &'static str,
+    width: u32,
+    height: u32,
+}
+
+/// 保存上传的图片：校验格式 → 按 EXIF 方向摆正 → 重新编码 (顺带去除 EXIF) → 生成中图/缩略图
+///
+/// - 不透明图片输出 JPEG，带透明通道的输出 WebP (无损)
+/// - 文件名: `uuid.jpg` / `uuid_md.jpg` / `uuid_sm.jpg`
+pub async fn save_upload_image(
+    base_dir: &Path,
+    field: Field<'_>,
+    sub_folder: &str,
+) -> Result<StoredImage, String> {
+    let data = field.bytes().await.map_err(|e| e.to_string())?;
+    save_image_bytes(base_dir, data.to_vec(), sub_folder).await
+}
+
+/// 与 `save_upload_image` 相同，但直接处理内存中的图片数据 (如批量导入时从 ZIP 中读出的文件)
+pub async fn save_image_bytes(
+    base_dir: &Path,
+    data: Vec<u8>,
+    sub_folder: &str,
+) -> Result<StoredImage, String> {
+    if data.is_empty() {
+        return Err("Empty file".to_string());
+    }
+    if data.len() > MAX_UPLOAD_BYTES {
+        return Err("Image too large (max 20MB)".to_string());
+    }
+
+    // 解码与缩放是 CPU 密集操作，放到阻塞线程池执行
+    let encoded = tokio::task::spawn_blocking(move || process_image(&data))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let dir = base_dir.join(sub_folder);
+    if !dir.exists() {
+        fs::create_dir_all(&dir).await.map_err(|e| e.to_string())?;
+    }
+
+    let stem = Uuid::new_v4().to_string();
+    let names = [
+        format!("{}.{}", stem, encoded.ext),
+        format!("{}_md.{}", stem, encoded.ext),
+        format!("{}_sm.{}", stem, encoded.ext),
+    ];
+    let contents = [&encoded.full, &encoded.medium, &encoded.thumb];
+
+    for (i, (name, bytes)) in names.iter().zip(contents).enumerate() {
+        if let Err(e) = fs::write(dir.join(name), bytes).await {
+            // 写入中途失败，清理已写入的文件
+            for written in &names[..i] {
+                let _ = fs::remove_file(dir.join(written)).await;
+            }
+            return Err(format!("Write failed: {}", e));
+        }
+    }
+
+    let url = |name: &str| format!("/uploads/{}/{}", sub_folder, name);
+    Ok(StoredImage {
+        url: url(&names[0]),
+        url_md: url(&names[1]),
+        url_sm: url(&names[2]),
+        width: encoded.width,
+        height: encoded.height,
+    })
+}
+
+fn process_image(data: &[u8]) -> Result<EncodedImage, String> {
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?;
+    if reader.format().is_none() {
+        return Err("Unsupported image format".to_string());
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    reader.limits(limits);
+
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|e| format!("Invalid image: {}", e))?;
+    let orientation = decoder
+        .orientation()
+        .unwrap_or(image::metadata::Orientation::NoTransforms);
+    let mut img =
+        DynamicImage::from_decoder(decoder).map_err(|e| format!("Invalid image: {}", e))?;
+    // 手机照片常以 EXIF 记录旋转，重新编码会丢失 EXIF，所以先把像素摆正
+    img.apply_orientation(orientation);
+
+    let full = shrink(&img, FULL_SIZE);
+    let medium = shrink(&full, MEDIUM_SIZE);
+    let thumb = shrink(&medium, THUMB_SIZE);
+
+    let has_alpha = img.color().has_alpha();
+    let encode = |img: &DynamicImage| -> Result<Vec<u8>, String> {
+        let mut buf = Vec::new();
+        if has_alpha {
+            let rgba = img.to_rgba8();
+            WebPEncoder::new_lossless(&mut buf)
+                .encode(
+                    rgba.as_raw(),
+                    rgba.width(),
+                    rgba.height(),
+                    image::ExtendedColorType::Rgba8,
+                )
+                .map_err(|e| e.to_string())?;
+        } else {
+            let rgb = img.to_rgb8();
+            JpegEncoder::new_with_quality(&mut buf, JPEG_QUALITY)
+                .encode_image(&rgb)
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(buf)
+    };
+
+    Ok(EncodedImage {
+        full: encode(&full)?,
+        medium: encode(&medium)?,
+        thumb: encode(&thumb)?,
+        ext: if has_alpha { "webp" } else { "jpg" },
+        width: full.width(),
+        height: full.height(),
+    })
+}
+
+/// 长边超过 `max_side` 时等比缩小，不放大
+fn shrink(img: &DynamicImage, max_side: u32) -> DynamicImage {
+    if img.width() <= max_side && img.height() <= max_side {
+        img.clone()
+    } else {
+        img.resize(max_side, max_side, FilterType::Lanczos3)
+    }
+}
@@ -0,0 +1,705 @@
+// src/utils/label.rs
+//
+// 商品标签页生成 (A4 标签纸)：每张标签包含商品名、价格和商品编号的 Code128 条码或二维码。
+// 输出 PDF (多页) 或 SVG (多页纵向拼接)。坐标统一使用毫米、左上角为原点。
+
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+};
+use barcoders::sym::code128::Code128;
+use chrono::Local;
+use qrcode::{Color, EcLevel, QrCode};
+use serde::Deserialize;
+
+// A4 纸张尺寸 (mm)
+const A4_WIDTH: f64 = 210.0;
+const A4_HEIGHT: f64 = 297.0;
+// 单次最多生成的标签数，防止误操作生成超大文件
+pub const MAX_LABELS: usize = 5000;
+
+// ==========================================
+// 版式
+// ==========================================
+#[derive(Debug, Clone)]
+pub struct LabelLayout {
+    pub columns: usize,
+    pub rows: usize,
+    pub label_width: f64,
+    pub label_height: f64,
+    pub margin_top: f64,
+    pub margin_left: f64,
+    pub gap_x: f64,
+    pub gap_y: f64,
+}
+
+impl LabelLayout {
+    // 常见 A4 不干胶标签纸规格
+    pub fn preset(name: &str) -> Option<Self> {
+        let (columns, rows, label_width, label_height, margin_top, margin_left, gap_x) = match name
+        {
+            "a4_14" => (2, 7, 99.1, 38.1, 15.15, 4.65, 2.5), // 99.1×38.1 (L7163)
+            "a4_21" => (3, 7, 63.5, 38.1, 15.15, 7.25, 2.5), // 63.5×38.1 (L7160)
+            "a4_24" => (3, 8, 70.0, 37.0, 0.5, 0.0, 0.0),    // 70×37 无边距
+            "a4_40" => (4, 10, 48.5, 25.4, 21.5, 8.0, 0.0),  // 48.5×25.4
+            "a4_65" => (5, 13, 38.1, 21.2, 10.7, 4.75, 2.5), // 38.1×21.2 (L7651)
+            _ => return None,
+        };
+        Some(Self {
+            columns,
+            rows,
+            label_width,
+            label_height,
+            margin_top,
+            margin_left,
+            gap_x,
+            gap_y: 0.0,
+        })
+    }
+
+    pub fn per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    // 第 index 个格子 (页内) 的左上角坐标
+    fn origin(&self, index: usize) -> (f64, f64) {
+        let col = index % self.columns;
+        let row = index / self.columns;
+        (
+            self.margin_left + col as f64 * (self.label_width + self.gap_x),
+            self.margin_top + row as f64 * (self.label_height + self.gap_y),
+        )
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.columns == 0
+            || self.rows == 0
+            || self.label_width <= 0.0
+            || self.label_height <= 0.0
+        {
+            return Err("Invalid label layout".to_string());
+        }
+        let right = self.margin_left
+            + self.columns as f64 * self.label_width
+            + (self.columns - 1) as f64 * self.gap_x;
+        let bottom = self.margin_top
+            + self.rows as f64 * self.label_height
+            + (self.rows - 1) as f64 * self.gap_y;
+        if right > A4_WIDTH + 0.5 || bottom > A4_HEIGHT + 0.5 {
+            return Err("Label layout does not fit on A4".to_string());
+        }
+        Ok(())
+    }
+}
+
+// ==========================================
+// 请求参数 (供 master_product / product 接口 flatten 复用)
+// ==========================================
+#[derive(Debug, Deserialize, Default)]
+pub struct LabelOptions {
+    pub layout: Option<String>, // 预设版式，默认 a4_24
+    // 自定义版式：覆盖预设中的对应值 (mm)
+    pub columns: Option<usize>,
+    pub rows: Option<usize>,
+    pub label_width: Option<f64>,
+    pub label_height: Option<f64>,
+    pub margin_top: Option<f64>,
+    pub margin_left: Option<f64>,
+    pub gap_x: Option<f64>,
+    pub gap_y: Option<f64>,
+    pub code_type: Option<String>, // "code128" (默认) | "qr"
+    pub format: Option<String>,    // "pdf" (默认) | "svg"
+    pub copies: Option<usize>,     // 每个商品打印份数，默认 1 (接口可按商品单独指定)
+    pub skip: Option<usize>,       // 跳过第一页前 N 个格子 (用过一部分的标签纸)
+    pub outline: Option<bool>,     // 绘制裁切边框 (普通 A4 纸打印时用)
+    pub currency: Option<String>,  // 价格前缀，默认 "¥"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CodeType {
+    Code128,
+    Qr,
+}
+
+pub struct LabelItem {
+    pub code: String,
+    pub name: String,
+    pub price: f64,
+    pub copies: usize,
+}
+
+pub struct LabelSheet {
+    pub data: Vec<u8>,
+    pub content_type: &'static str,
+    pub extension: &'static str,
+}
+
+impl LabelSheet {
+    // 以附件形式下载，文件名如 labels_CP30_20261019_1530.pdf
+    pub fn into_download(self, file_stem: &str) -> Response {
+        let filename = format!(
+            "{}_{}.{}",
+            file_stem,
+            Local::now().format("%Y%m%d_%H%M"),
+            self.extension
+        );
+        let disposition = format!(
+            "attachment; filename=\"labels.{}\"; filename*=UTF-8''{}",
+            self.extension,
+            urlencoding::encode(&filename)
+        );
+        (
+            [
+                (header::CONTENT_TYPE, self.content_type.to_string()),
+                (header::CONTENT_DISPOSITION, disposition),
+            ],
+            self.data,
+        )
+            .into_response()
+    }
+}
+
+impl LabelOptions {
+    pub fn resolve_layout(&self) -> Result<LabelLayout, String> {
+        let name = self.layout.as_deref().unwrap_or("a4_24");
+        let mut layout = match LabelLayout::preset(name) {
+            Some(l) => l,
+            None if name == "custom" => LabelLayout::preset("a4_24").unwrap(),
+            None => return Err(format!("Unknown label layout: {}", name)),
+        };
+        if let Some(v) = self.columns {
+            layout.columns = v;
+        }
+        if let Some(v) = self.rows {
+            layout.rows = v;
+        }
+        if let Some(v) = self.label_width {
+            layout.label_width = v;
+        }
+        if let Some(v) = self.label_height {
+            layout.label_height = v;
+        }
+        if let Some(v) = self.margin_top {
+            layout.margin_top = v;
+        }
+        if let Some(v) = self.margin_left {
+            layout.margin_left = v;
+        }
+        if let Some(v) = self.gap_x {
+            layout.gap_x = v;
+        }
+        if let Some(v) = self.gap_y {
+            layout.gap_y = v;
+        }
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    pub fn code_type(&self) -> Result<CodeType, String> {
+        match self.code_type.as_deref() {
+            None | Some("code128") => Ok(CodeType::Code128),
+            Some("qr") => Ok(CodeType::Qr),
+            Some(other) => Err(format!("Unknown code type: {}", other)),
+        }
+    }
+}
+
+// 按选项生成整份标签文件；每个商品按 LabelItem.copies 重复
+pub fn build_sheet(options: &LabelOptions, items: &[LabelItem]) -> Result<LabelSheet, String> {
+    let layout = options.resolve_layout()?;
+    let code_type = options.code_type()?;
+    let skip = options.skip.unwrap_or(0) % layout.per_page();
+    let outline = options.outline.unwrap_or(false);
+    let currency = options.currency.clone().unwrap_or_else(|| "¥".to_string());
+
+    // copies 来自请求，逐项限制并用 checked_add 累加，防止溢出绕过上限
+    let label_count = items
+        .iter()
+        .try_fold(0usize, |total, i| {
+            if i.copies > MAX_LABELS {
+                return None;
+            }
+            total.checked_add(i.copies).filter(|t| *t <= MAX_LABELS)
+        })
+        .ok_or_else(|| format!("Too many labels (max {})", MAX_LABELS))?;
+    if label_count == 0 {
+        return Err("No products selected".to_string());
+    }
+
+    // 预先生成每个商品的条码，编码失败直接报错
+    let mut codes = Vec::with_capacity(items.len());
+    for item in items {
+        codes.push(encode(code_type, &item.code)?);
+    }
+
+    let total = skip + label_count;
+    let page_count = total.div_ceil(layout.per_page());
+    let mut pages: Vec<Canvas> = (0..page_count).map(|_| Canvas::default()).collect();
+
+    let mut slot = skip;
+    for (item, code) in items.iter().zip(&codes) {
+        for _ in 0..item.copies {
+            let canvas = &mut pages[slot / layout.per_page()];
+            let (x, y) = layout.origin(slot % layout.per_page());
+            if outline {
+                canvas
+                    .outlines
+                    .push((x, y, layout.label_width, layout.label_height));
+            }
+            draw_label(
+                canvas,
+                x,
+                y,
+                layout.label_width,
+                layout.label_height,
+                item,
+                code,
+                &currency,
+            );
+            slot += 1;
+        }
+    }
+
+    Ok(match options.format.as_deref() {
+        Some("svg") => LabelSheet {
+            data: render_svg(&pages).into_bytes(),
+            content_type: "image/svg+xml",
+            extension: "svg",
+        },
+        _ => LabelSheet {
+            data: render_pdf(&pages),
+            content_type: "application/pdf",
+            extension: "pdf",
+        },
+    })
+}
+
+// ==========================================
+// 条码编码
+// ==========================================
+enum Encoded {
+    Linear(Vec<u8>),                          // Code128 模块 (1 = 黑条)
+    Matrix { width: usize, dark: Vec<bool> }, // QR 模块
+}
+
+fn encode(code_type: CodeType, code: &str) -> Result<Encoded, String> {
+    match code_type {
+        CodeType::Code128 => {
+            // Ɓ = 字符集 B (可打印 ASCII)
+            Code128::new(format!("Ɓ{}", code))
+                .map(|c| Encoded::Linear(c.encode()))
+                .map_err(|_| {
+                    format!(
+                        "Product code \"{}\" cannot be encoded as Code128, use QR instead",
+                        code
+                    )
+                })
+        }
+        CodeType::Qr => QrCode::with_error_correction_level(code.as_bytes(), EcLevel::M)
+            .map(|qr| Encoded::Matrix {
+                width: qr.width(),
+                dark: qr
+                    .to_colors()
+                    .into_iter()
+                    .map(|c| c == Color::Dark)
+                    .collect(),
+            })
+            .map_err(|e| format!("Product code \"{}\" cannot be encoded as QR: {}", code, e)),
+    }
+}
+
+// ==========================================
+// 绘图 (与输出格式无关)
+// ==========================================
+#[derive(Clone, Copy, PartialEq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+struct Text {
+    x: f64,
+    y: f64, // 基线
+    size: f64,
+    anchor: Anchor,
+    content: String,
+}
+
+#[derive(Default)]
+struct Canvas {
+    rects: Vec<(f64, f64, f64, f64)>,
+    outlines: Vec<(f64, f64, f64, f64)>,
+    texts: Vec<Text>,
+}
+
+impl Canvas {
+    fn text(&mut self, x: f64, y: f64, size: f64, anchor: Anchor, content: String) {
+        if !content.is_empty() {
+            self.texts.push(Text {
+                x,
+                y,
+                size,
+                anchor,
+                content,
+            });
+        }
+    }
+}
+
+// 估算文字宽度：半角 0.5em，全角 1em (与 PDF 中的字宽设置一致)
+fn text_width(s: &str, size: f64) -> f64 {
+    s.chars()
+        .map(|c| if c.is_ascii() { 0.5 } else { 1.0 })
+        .sum::<f64>()
+        * size
+}
+
+// 按宽度折行，超出 max_lines 时末行以省略号结尾
+fn wrap_text(s: &str, size: f64, max_width: f64, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![String::new()];
+    for c in s.chars() {
+        let current = lines.last_mut().unwrap();
+        let mut candidate = current.clone();
+        candidate.push(c);
+        if text_width(&candidate, size) <= max_width || current.is_empty() {
+            *current = candidate;
+            continue;
+        }
+        if lines.len() == max_lines {
+            let last = lines.last_mut().unwrap();
+            while !last.is_empty() && text_width(&format!("{}…", last), size) > max_width {
+                last.pop();
+            }
+            last.push('…');
+            return lines;
+        }
+        lines.push(c.to_string());
+    }
+    lines
+}
+
+fn format_price(currency: &str, price: f64) -> String {
+    if price.fract() == 0.0 {
+        format!("{}{}", currency, price as i64)
+    } else {
+        format!("{}{:.2}", currency, price)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_label(
+    canvas: &mut Canvas,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    item: &LabelItem,
+    code: &Encoded,
+    currency: &str,
+) {
+    let pad = (h * 0.07).clamp(1.0, 2.5);
+    let name_size = (h * 0.12).clamp(2.0, 4.2);
+    let price_size = name_size * 1.25;
+    let code_size = name_size * 0.8;
+    let price = format_price(currency, item.price);
+
+    match code {
+        // 条码：首行 名称 + 价格(右对齐)，中间条码，底部编号
+        Encoded::Linear(modules) => {
+            let price_w = text_width(&price, price_size);
+            let name_lines = wrap_text(&item.name, name_size, w - 2.0 * pad - price_w - 1.0, 1);
+            let first_line = y + pad + price_size;
+            canvas.text(
+                x + pad,
+                first_line,
+                name_size,
+                Anchor::Start,
+                name_lines.concat(),
+            );
+            canvas.text(x + w - pad, first_line, price_size, Anchor::End, price);
+
+            let bar_top = first_line + pad * 0.6;
+            let bar_bottom = y + h - pad - code_size * 1.2;
+            // 两侧各留 10 个模块的静区
+            let module = ((w - 2.0 * pad) / (modules.len() + 20) as f64).min(0.5);
+            let bar_x = x + (w - module * modules.len() as f64) / 2.0;
+            for (start, len) in runs(modules.iter().map(|m| *m == 1)) {
+                canvas.rects.push((
+                    bar_x + start as f64 * module,
+                    bar_top,
+                    len as f64 * module,
+                    (bar_bottom - bar_top).max(1.0),
+                ));
+            }
+            canvas.text(
+                x + w / 2.0,
+                y + h - pad,
+                code_size,
+                Anchor::Middle,
+                item.code.clone(),
+            );
+        }
+        // 二维码：左侧二维码，右侧 名称(最多 3 行) / 价格 / 编号
+        Encoded::Matrix { width, dark } => {
+            let size = (h - 2.0 * pad).min(w * 0.45);
+            let module = size / *width as f64;
+            let qr_y = y + (h - size) / 2.0;
+            for row in 0..*width {
+                let cells = dark[row * width..(row + 1) * width].iter().copied();
+                for (start, len) in runs(cells) {
+                    canvas.rects.push((
+                        x + pad + start as f64 * module,
+                        qr_y + row as f64 * module,
+                        len as f64 * module,
+                        module,
+                    ));
+                }
+            }
+
+            let text_x = x + pad + size + pad;
+            let text_w = x + w - pad - text_x;
+            let max_lines = (((h - 2.0 * pad - price_size - code_size * 1.2) / (name_size * 1.2))
+                as usize)
+                .clamp(1, 3);
+            let mut line_y = y + pad + name_size;
+            for line in wrap_text(&item.name, name_size, text_w, max_lines) {
+                canvas.text(text_x, line_y, name_size, Anchor::Start, line);
+                line_y += name_size * 1.2;
+            }
+            canvas.text(
+                text_x,
+                line_y + price_size * 0.1,
+                price_size,
+                Anchor::Start,
+                price,
+            );
+            let code_lines = wrap_text(&item.code, code_size, text_w, 1);
+            canvas.text(
+                text_x,
+                y + h - pad,
+                code_size,
+                Anchor::Start,
+                code_lines.concat(),
+            );
+        }
+    }
+}
+
+// 连续黑色模块合并成一个矩形，减少输出体积
+fn runs(cells: impl Iterator<Item = bool>) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut len = 0;
+    for (i, on) in cells.enumerate() {
+        len = i + 1;
+        match (on, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                result.push((s, i - s));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        result.push((s, len - s));
+    }
+    result
+}
+
+// ==========================================
+// SVG 输出
+// ==========================================
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_svg(pages: &[Canvas]) -> String {
+    let total_height = A4_HEIGHT * pages.len() as f64;
+    let mut out = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}mm" height="{h}mm" viewBox="0 0 {w} {h}">"#,
+        w = A4_WIDTH,
+        h = total_height
+    );
+    out.push_str(r#"<style>text{font-family:"Noto Sans CJK SC","Microsoft YaHei","PingFang SC",sans-serif}</style>"#);
+
+    for (i, page) in pages.iter().enumerate() {
+        let offset = A4_HEIGHT * i as f64;
+        out.push_str(&format!(r#"<g transform="translate(0 {:.3})">"#, offset));
+        out.push_str(&format!(
+            r#"<rect width="{}" height="{}" fill="white"/>"#,
+            A4_WIDTH, A4_HEIGHT
+        ));
+
+        if !page.outlines.is_empty() {
+            out.push_str(r##"<g fill="none" stroke="#bbbbbb" stroke-width="0.1">"##);
+            for (x, y, w, h) in &page.outlines {
+                out.push_str(&format!(
+                    r#"<rect x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}"/>"#,
+                    x, y, w, h
+                ));
+            }
+            out.push_str("</g>");
+        }
+
+        let mut path = String::new();
+        for (x, y, w, h) in &page.rects {
+            path.push_str(&format!("M{:.3} {:.3}h{:.3}v{:.3}h{:.3}z", x, y, w, h, -w));
+        }
+        out.push_str(&format!(r#"<path fill="black" d="{}"/>"#, path));
+
+        for t in &page.texts {
+            let anchor = match t.anchor {
+                Anchor::Start => "start",
+                Anchor::Middle => "middle",
+                Anchor::End => "end",
+            };
+            out.push_str(&format!(
+                r#"<text x="{:.3}" y="{:.3}" font-size="{:.3}" text-anchor="{}">{}</text>"#,
+                t.x,
+                t.y,
+                t.size,
+                anchor,
+                escape_xml(&t.content)
+            ));
+        }
+        out.push_str("</g>");
+    }
+    out.push_str("</svg>");
+    out
+}
+
+// ==========================================
+// PDF 输出
+// ==========================================
+// 使用 PDF 阅读器内置的 Adobe 中文字体 STSong-Light (UniGB-UCS2-H)，无需嵌入字体文件，
+// 可显示中文、假名和常用符号。半角字符统一按 0.5em 排版。
+
+fn pt(mm: f64) -> f64 {
+    mm * 72.0 / 25.4
+}
+
+// UCS-2 大端十六进制字符串；BMP 以外的字符替换为 '?'
+fn pdf_hex_text(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            // STSong-Light 中没有半角 ¥，换成全角
+            let c = if c == '¥' { '￥' } else { c };
+            let code = if (c as u32) <= 0xFFFF {
+                c as u32
+            } else {
+                '?' as u32
+            };
+            format!("{:04X}", code)
+        })
+        .collect()
+}
+
+fn page_content(page: &Canvas) -> String {
+    let page_h = pt(A4_HEIGHT);
+    let mut out = String::new();
+
+    if !page.outlines.is_empty() {
+        out.push_str("0.73 G 0.3 w\n");
+        for (x, y, w, h) in &page.outlines {
+            out.push_str(&format!(
+                "{:.2} {:.2} {:.2} {:.2} re S\n",
+                pt(*x),
+                page_h - pt(y + h),
+                pt(*w),
+                pt(*h)
+            ));
+        }
+    }
+
+    out.push_str("0 g\n");
+    for (x, y, w, h) in &page.rects {
+        out.push_str(&format!(
+            "{:.3} {:.3} {:.3} {:.3} re\n",
+            pt(*x),
+            page_h - pt(y + h),
+            pt(*w),
+            pt(*h)
+        ));
+    }
+    if !page.rects.is_empty() {
+        out.push_str("f\n");
+    }
+
+    for t in &page.texts {
+        let width = text_width(&t.content, t.size);
+        let x = match t.anchor {
+            Anchor::Start => t.x,
+            Anchor::Middle => t.x - width / 2.0,
+            Anchor::End => t.x - width,
+        };
+        out.push_str(&format!(
+            "BT /F1 {:.2} Tf {:.2} {:.2} Td <{}> Tj ET\n",
+            pt(t.size),
+            pt(x),
+            page_h - pt(t.y),
+            pdf_hex_text(&t.content)
+        ));
+    }
+    out
+}
+
+fn render_pdf(pages: &[Canvas]) -> Vec<u8> {
+    // 对象编号：1 Catalog, 2 Pages, 3 字体, 4 CIDFont, 5 FontDescriptor, 之后每页 (Page, Content)
+    let mut objects: Vec<String> = vec![
+        String::new(), // 1 Catalog (占位，稍后填充)
+        String::new(), // 2 Pages
+        "<< /Type /Font /Subtype /Type0 /BaseFont /STSong-Light /Encoding /UniGB-UCS2-H /DescendantFonts [4 0 R] >>".to_string(),
+        "<< /Type /Font /Subtype /CIDFontType0 /BaseFont /STSong-Light /CIDSystemInfo << /Registry (Adobe) /Ordering (GB1) /Supplement 2 >> /FontDescriptor 5 0 R /DW 1000 /W [1 95 500] >>".to_string(),
+        "<< /Type /FontDescriptor /FontName /STSong-Light /Flags 6 /FontBBox [-25 -254 1000 880] /ItalicAngle 0 /Ascent 880 /Descent -120 /CapHeight 880 /StemV 93 >>".to_string(),
+    ];
+
+    let mut kids = Vec::new();
+    for page in pages {
+        let page_id = objects.len() + 1;
+        let content = page_content(page);
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            pt(A4_WIDTH),
+            pt(A4_HEIGHT),
+            page_id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+        kids.push(format!("{} 0 R", page_id));
+    }
+    objects[0] = "<< /Type /Catalog /Pages 2 0 R >>".to_string();
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    );
+
+    let mut out: Vec<u8> = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, obj) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, obj).as_bytes());
+    }
+    let xref_start = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        out.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    out.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_start
+        )
+        .as_bytes(),
+    );
+    out
+}
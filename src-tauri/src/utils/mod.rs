@@ -1,3 +1,7 @@
 pub mod file;
+pub mod i18n;
+pub mod image;
 pub mod ip;
+pub mod label;
+pub mod search;
 pub mod security;
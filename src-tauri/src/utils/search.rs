@@ -0,0 +1,193 @@
+// src/utils/search.rs
+
+use pinyin::ToPinyin;
+use sqlx::{Row, SqlitePool};
+use wana_kana::ConvertJapanese;
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3400..=0x4DBF     // CJK 扩展 A
+        | 0x4E00..=0x9FFF   // CJK 统一汉字
+        | 0xF900..=0xFAFF   // CJK 兼容汉字
+        | 0x3040..=0x30FF   // 平假名 / 片假名
+        | 0x31F0..=0x31FF   // 片假名扩展
+        | 0xFF66..=0xFF9F) // 半角片假名
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F)
+}
+
+/// 把中日文字符按字拆开，其余部分保持原样
+///
+/// FTS5 的 unicode61 分词器会把一整串汉字当作一个词，拆开后才能做子串匹配
+fn split_cjk(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    for c in text.chars() {
+        if is_cjk(c) {
+            out.push(' ');
+            out.push(c);
+            out.push(' ');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// 生成名称的读音索引文本：
+/// - 汉字：全拼连写 (zhoubian)、逐字全拼 (zhou bian)、首字母 (zb)
+/// - 假名：罗马音 (kawaii)
+fn phonetic(text: &str) -> String {
+    let mut full = String::new();
+    let mut syllables: Vec<&str> = Vec::new();
+    let mut initials = String::new();
+    for p in text.to_pinyin().flatten() {
+        full.push_str(p.plain());
+        syllables.push(p.plain());
+        initials.push_str(p.first_letter());
+    }
+
+    let kana: String = text
+        .chars()
+        .map(|c| if is_kana(c) { c } else { ' ' })
+        .collect();
+    let romaji: Vec<String> = kana.split_whitespace().map(|run| run.to_romaji()).collect();
+
+    let mut parts: Vec<String> = Vec::new();
+    if !syllables.is_empty() {
+        parts.push(full);
+        parts.push(syllables.join(" "));
+        parts.push(initials);
+    }
+    if !romaji.is_empty() {
+        parts.push(romaji.concat());
+        parts.extend(romaji);
+    }
+    parts.join(" ")
+}
+
+/// 把用户输入转换成 FTS5 MATCH 表达式
+///
+/// 每个词都做前缀匹配，多个词之间为 AND；含中日文的词转为逐字短语，
+/// 含假名的词额外匹配其罗马音。返回 None 表示输入里没有可检索的内容。
+pub fn build_match_query(input: &str) -> Option<String> {
+    let mut terms: Vec<String> = Vec::new();
+
+    for word in input.split_whitespace() {
+        // 去掉 FTS5 语法字符，只保留字母数字和中日文
+        let cleaned: String = word
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+            .collect();
+
+        for token in cleaned.split_whitespace() {
+            let token = token.to_lowercase();
+            if token.chars().any(is_cjk) {
+                let chars: Vec<String> = split_cjk(&token)
+                    .split_whitespace()
+                    .map(|s| s.to_string())
+                    .collect();
+                let phrase = format!("\"{}\"", chars.join(" "));
+                if token.chars().any(is_kana) {
+                    let romaji = token.to_romaji();
+                    terms.push(format!("({} OR phonetic : \"{}\"*)", phrase, romaji));
+                } else {
+                    terms.push(phrase);
+                }
+            } else {
+                terms.push(format!("\"{}\"*", token));
+            }
+        }
+    }
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" AND "))
+    }
+}
+
+// 索引来源：商品基本信息 + 各语言名称 + 标签名 (空格拼接)
+const INDEX_SOURCE: &str = r#"
+    SELECT mp.id, mp.product_code,
+        mp.name || COALESCE(' ' || (SELECT GROUP_CONCAT(tr.name, ' ')
+                                    FROM master_product_translations tr
+                                    WHERE tr.master_product_id = mp.id), '') AS name,
+        mp.category,
+        (SELECT GROUP_CONCAT(t.name, ' ')
+         FROM master_product_tags mpt JOIN tags t ON mpt.tag_id = t.id
+         WHERE mpt.master_product_id = mp.id) AS tags
+    FROM master_products mp
+"#;
+
+/// 重建单个商品的检索索引 (商品不存在时仅删除旧索引)
+pub async fn index_master_product(
+    db: &SqlitePool,
+    master_product_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM product_search WHERE master_product_id = ?")
+        .bind(master_product_id)
+        .execute(db)
+        .await?;
+
+    let sql = format!("{} WHERE mp.id = ?", INDEX_SOURCE);
+    let row = sqlx::query(&sql)
+        .bind(master_product_id)
+        .fetch_optional(db)
+        .await?;
+
+    if let Some(row) = row {
+        insert_row(db, &row).await?;
+    }
+    Ok(())
+}
+
+/// 全量重建检索索引 (启动时、批量导入后调用；数百个商品耗时可忽略)
+pub async fn rebuild_index(db: &SqlitePool) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM product_search")
+        .execute(&mut *tx)
+        .await?;
+
+    let rows = sqlx::query(INDEX_SOURCE).fetch_all(&mut *tx).await?;
+
+    for row in &rows {
+        insert_row(&mut *tx, row).await?;
+    }
+    tx.commit().await
+}
+
+async fn insert_row<'e, E>(executor: E, row: &sqlx::sqlite::SqliteRow) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let id: i64 = row.get("id");
+    let code: String = row.get("product_code");
+    let name: String = row.get("name");
+    let category: Option<String> = row.get("category");
+    let category = category.unwrap_or_default();
+    let tags: Option<String> = row.get("tags");
+    let tags = tags.unwrap_or_default();
+
+    sqlx::query(
+        r#"
+        INSERT INTO product_search (master_product_id, product_code, name, category, tags, phonetic)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(id)
+    .bind(&code)
+    .bind(split_cjk(&name))
+    .bind(split_cjk(&category))
+    .bind(split_cjk(&tags))
+    .bind(format!(
+        "{} {} {}",
+        phonetic(&name),
+        phonetic(&category),
+        phonetic(&tags)
+    ))
+    .execute(executor)
+    .await?;
+    Ok(())
+}
@@ -49,16 +49,20 @@ pub fn verify_password(password: &str, hash: &str) -> bool {
     verify(password, hash).unwrap_or(false)
 }
 
+// 默认登录有效期 (小时)；单场次摊主登录可按场次设置覆盖
+pub const DEFAULT_TOKEN_HOURS: i64 = 24;
+
 // 3. 生成 JWT
 pub fn create_jwt(
     role: &str,
     access: &str,
     event_id: Option<i64>,
+    ttl_hours: i64,
     secret: &str,
 ) -> Result<String, AuthError> {
-    // 设置过期时间 (例如 24 小时)
+    // 设置过期时间
     let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(24))
+        .checked_add_signed(Duration::hours(ttl_hours))
         .expect("valid timestamp")
         .timestamp() as usize;
 